@@ -3,8 +3,16 @@ use std::fmt::{Display, Formatter};
 #[derive(Debug, Clone, Copy, Ord, PartialOrd, Eq, PartialEq)]
 pub enum BinaryOperator {
     Add,
+    Sub,
+    Mul,
+    Div,
+    Mod,
     Equals,
+    NotEquals,
     GreaterThan,
+    GreaterEquals,
+    LessThan,
+    LessEquals,
     Or,
     And
 }
@@ -13,8 +21,16 @@ impl Display for BinaryOperator {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         write!(f, "{}", match self {
             BinaryOperator::Add => "+",
+            BinaryOperator::Sub => "-",
+            BinaryOperator::Mul => "*",
+            BinaryOperator::Div => "/",
+            BinaryOperator::Mod => "%",
             BinaryOperator::Equals => "==",
+            BinaryOperator::NotEquals => "!=",
             BinaryOperator::GreaterThan => ">",
+            BinaryOperator::GreaterEquals => ">=",
+            BinaryOperator::LessThan => "<",
+            BinaryOperator::LessEquals => "<=",
             BinaryOperator::Or => "||",
             BinaryOperator::And => "&&"
         })
@@ -1,9 +1,12 @@
 use crate::ast::binary_operator::BinaryOperator;
 use crate::ast::Block;
+use crate::ast::pattern::MatchArm;
 use crate::ast::unary_operator::UnaryOperator;
+use crate::parser::span::Span;
 use crate::parser::token::Token;
 use crate::testing::s_expr::SExpr;
 use crate::testing::to_s_expr::ToSExpr;
+use crate::typed_ast::r#type::Type;
 
 #[derive(Debug, Clone)]
 pub enum Expression {
@@ -11,30 +14,117 @@ pub enum Expression {
         condition: Box<Expression>,
         true_block: Block,
         else_block: Option<Block>,
+        span: Span
     },
     BinaryOperation {
         lhs: Box<Expression>,
         operator: BinaryOperator,
-        rhs: Box<Expression>
+        rhs: Box<Expression>,
+        span: Span
     },
     FunctionCall {
         name: Token,
-        arguments: Vec<Expression>
+        arguments: Vec<Expression>,
+        span: Span
     },
     UnaryOperation {
         operator: UnaryOperator,
-        rhs: Box<Expression>
+        rhs: Box<Expression>,
+        span: Span
     },
     Int(Token),
+    Float(Token),
     Bool(bool, Token),
-    List(Vec<Expression>),
+    Str(Token),
+    Char(Token),
+    List(Vec<Expression>, Span),
+    /// `(1, true)`, or `(1,)` for a single element.
+    Tuple(Vec<Expression>, Span),
+    /// `Point { x: 1, y: 2 }`, with the fields in the order they were written.
+    StructLiteral {
+        name: Token,
+        fields: Vec<(Token, Expression)>,
+        span: Span
+    },
+    /// `Shape::Rect(1, 2)`, or just `Shape::Empty` when the variant has no payload.
+    EnumVariant {
+        enum_name: Token,
+        variant: Token,
+        payload: Vec<Expression>,
+        span: Span
+    },
+    Match {
+        scrutinee: Box<Expression>,
+        arms: Vec<MatchArm>,
+        span: Span
+    },
+    /// `of.field`
+    FieldAccess {
+        of: Box<Expression>,
+        field: Token,
+        span: Span
+    },
+    /// `of[index]`
+    Index {
+        of: Box<Expression>,
+        index: Box<Expression>,
+        span: Span
+    },
+    /// `|x: int| -> int { x + 1 }`
+    Lambda {
+        params: Vec<(Token, Type)>,
+        return_type: Type,
+        body: Block,
+        span: Span
+    },
+    /// `receiver.method(arguments)`, which calls the method of whichever trait the type of
+    /// the receiver implements.
+    MethodCall {
+        receiver: Box<Expression>,
+        method: Token,
+        arguments: Vec<Expression>,
+        span: Span
+    },
+    /// A call to anything that isn't a plain name, like `make_adder(1)(2)`.
+    Call {
+        callee: Box<Expression>,
+        arguments: Vec<Expression>,
+        span: Span
+    },
     Name(Token)
 }
 
+impl Expression {
+    pub fn span(&self) -> Span {
+        match self {
+            Expression::If { span, .. } => *span,
+            Expression::BinaryOperation { span, .. } => *span,
+            Expression::FunctionCall { span, .. } => *span,
+            Expression::UnaryOperation { span, .. } => *span,
+            Expression::Int(t) => t.span(),
+            Expression::Float(t) => t.span(),
+            Expression::Bool(_, t) => t.span(),
+            Expression::Str(t) => t.span(),
+            Expression::Char(t) => t.span(),
+            Expression::List(_, span) => *span,
+            Expression::Tuple(_, span) => *span,
+            Expression::StructLiteral { span, .. } => *span,
+            Expression::FieldAccess { span, .. } => *span,
+            Expression::Index { span, .. } => *span,
+            Expression::Lambda { span, .. } => *span,
+            Expression::Call { span, .. } => *span,
+            Expression::MethodCall { span, .. } => *span,
+            Expression::EnumVariant { span, .. } => *span,
+            Expression::Match { span, .. } => *span,
+            Expression::Name(t) => t.span(),
+        }
+    }
+}
+
 impl ToSExpr for Expression {
     fn to_s_expr(self) -> SExpr<String> {
         match self {
-            Expression::If { condition, true_block, else_block } => {
+            Expression::If { condition, true_block, else_block, span: _ } => {
                 let t_block = true_block.to_s_expr();
                 let mut args = vec![condition.to_s_expr(), t_block];
                 if let Some(else_block) = else_block {
@@ -44,26 +134,78 @@ impl ToSExpr for Expression {
                 let t = SExpr::Function(String::from("if"), args);
                 t
             }
-            Expression::BinaryOperation { lhs, operator, rhs } => {
+            Expression::BinaryOperation { lhs, operator, rhs, span: _ } => {
                 SExpr::Function(operator.to_string(), vec![lhs.to_s_expr(), rhs.to_s_expr()])
             }
-            Expression::FunctionCall { name, arguments } => {
+            Expression::FunctionCall { name, arguments, span: _ } => {
                 SExpr::Function(
                     name.lexeme().to_string(),
                     arguments.into_iter().map(|x| x.to_s_expr()).collect()
                 )
             }
-            Expression::UnaryOperation { operator, rhs } => {
+            Expression::UnaryOperation { operator, rhs, span: _ } => {
                 SExpr::Function(operator.to_string(), vec![rhs.to_s_expr()])
             }
             Expression::Int(value) => SExpr::Value(value.lexeme().to_string()),
+            Expression::Float(value) => SExpr::Value(value.lexeme().to_string()),
             Expression::Bool(value, _) => SExpr::Value(value.to_string()),
-            Expression::List(elements) => {
+            Expression::Str(value) => SExpr::Value(format!("{:?}", value.lexeme())),
+            Expression::Char(value) => SExpr::Value(format!("{:?}", value.lexeme().chars().next().unwrap())),
+            Expression::List(elements, _) => {
                 SExpr::Function(
                     String::from("list"),
                     elements.into_iter().map(|x| x.to_s_expr()).collect()
                 )
             }
+            Expression::Tuple(elements, _) => {
+                SExpr::Function(
+                    String::from("tuple"),
+                    elements.into_iter().map(|x| x.to_s_expr()).collect()
+                )
+            }
+            Expression::StructLiteral { name, fields, span: _ } => {
+                SExpr::Function(
+                    name.lexeme().to_string(),
+                    fields.into_iter()
+                        .map(|(field, value)| SExpr::Function(field.lexeme().to_string(), vec![value.to_s_expr()]))
+                        .collect()
+                )
+            }
+            Expression::EnumVariant { enum_name, variant, payload, span: _ } => {
+                SExpr::Function(
+                    format!("{}::{}", enum_name.lexeme(), variant.lexeme()),
+                    payload.into_iter().map(|x| x.to_s_expr()).collect()
+                )
+            }
+            Expression::Match { scrutinee, arms, span: _ } => {
+                let mut args = vec![scrutinee.to_s_expr()];
+                args.extend(arms.into_iter().map(|x| x.to_s_expr()));
+                SExpr::Function(String::from("match"), args)
+            }
+            Expression::Index { of, index, span: _ } => {
+                SExpr::Function(String::from("index"), vec![of.to_s_expr(), index.to_s_expr()])
+            }
+            Expression::Lambda { params, return_type, body, span: _ } => {
+                let mut args = params.into_iter()
+                    .map(|(name, type_)| SExpr::Value(format!("{}:{}", name.lexeme(), type_)))
+                    .collect::<Vec<_>>();
+                args.push(body.to_s_expr());
+                args.push(SExpr::Value(format!("->{}", return_type)));
+                SExpr::Function(String::from("lambda"), args)
+            }
+            Expression::Call { callee, arguments, span: _ } => {
+                let mut args = vec![callee.to_s_expr()];
+                args.extend(arguments.into_iter().map(|x| x.to_s_expr()));
+                SExpr::Function(String::from("call"), args)
+            }
+            Expression::MethodCall { receiver, method, arguments, span: _ } => {
+                let mut args = vec![receiver.to_s_expr(), SExpr::Value(method.lexeme().to_string())];
+                args.extend(arguments.into_iter().map(|x| x.to_s_expr()));
+                SExpr::Function(String::from("method_call"), args)
+            }
+            Expression::FieldAccess { of, field, span: _ } => {
+                SExpr::Function(String::from("."), vec![of.to_s_expr(), SExpr::Value(field.lexeme().to_string())])
+            }
             Expression::Name(n) => {SExpr::Value(n.lexeme().to_string())}
         }
     }
@@ -2,6 +2,7 @@ pub mod unary_operator;
 pub mod binary_operator;
 pub mod statement;
 pub mod expression;
+pub mod pattern;
 
 use crate::ast::statement::Statement;
 use crate::testing::s_expr::SExpr;
@@ -36,6 +37,7 @@ mod tests {
             condition: Box::new(Expression::Name(Token::un_located(TokenKind::Name, "y"))),
             true_block: vec![Statement::Expression(Expression::Name(Token::un_located(TokenKind::Name, "z")))],
             else_block: None,
+            span: Default::default(),
         };
         let b: Block = vec![Statement::Expression(
             if_internals.clone()
@@ -0,0 +1,64 @@
+use crate::ast::Block;
+use crate::ast::expression::Expression;
+use crate::parser::span::Span;
+use crate::parser::token::Token;
+use crate::testing::s_expr::SExpr;
+use crate::testing::to_s_expr::ToSExpr;
+
+/// What a `match` arm compares its value against.
+#[derive(Debug, Clone)]
+pub enum Pattern {
+    /// `_`
+    Wildcard(Token),
+    /// A name, which matches anything and holds the value in the arm.
+    Binding(Token),
+    /// An int, bool or char literal, or a negated int.
+    Literal(Expression),
+    /// `Shape::Rect(w, _)`
+    Variant {
+        enum_name: Token,
+        variant: Token,
+        fields: Vec<Pattern>,
+        span: Span
+    }
+}
+
+/// `pattern => body` in a `match`.
+#[derive(Debug, Clone)]
+pub struct MatchArm {
+    pub pattern: Pattern,
+    pub body: Block,
+    pub span: Span
+}
+
+impl Pattern {
+    #[allow(dead_code)]
+    pub fn span(&self) -> Span {
+        match self {
+            Pattern::Wildcard(t) => t.span(),
+            Pattern::Binding(t) => t.span(),
+            Pattern::Literal(e) => e.span(),
+            Pattern::Variant { span, .. } => *span,
+        }
+    }
+}
+
+impl ToSExpr for Pattern {
+    fn to_s_expr(self) -> SExpr<String> {
+        match self {
+            Pattern::Wildcard(_) => SExpr::Value(String::from("_")),
+            Pattern::Binding(name) => SExpr::Value(name.lexeme().to_string()),
+            Pattern::Literal(e) => e.to_s_expr(),
+            Pattern::Variant { enum_name, variant, fields, span: _ } => SExpr::Function(
+                format!("{}::{}", enum_name.lexeme(), variant.lexeme()),
+                fields.into_iter().map(|x| x.to_s_expr()).collect()
+            ),
+        }
+    }
+}
+
+impl ToSExpr for MatchArm {
+    fn to_s_expr(self) -> SExpr<String> {
+        SExpr::Function(String::from("=>"), vec![self.pattern.to_s_expr(), self.body.to_s_expr()])
+    }
+}
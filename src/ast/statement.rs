@@ -1,5 +1,8 @@
+use itertools::Itertools;
+use crate::ast::binary_operator::BinaryOperator;
 use crate::ast::Block;
 use crate::ast::expression::Expression;
+use crate::parser::span::Span;
 use crate::parser::token::Token;
 use crate::testing::s_expr::SExpr;
 use crate::testing::to_s_expr::ToSExpr;
@@ -7,64 +10,273 @@ use crate::typed_ast::r#type::Type;
 #[derive(Debug, Clone)]
 pub struct FunctionDefinition {
     pub name: Token,
+    /// The `T` and `U` of `fn pair<T, U>(...)`, which are empty for most functions.
+    pub type_params: Vec<TypeParam>,
     pub type_: Type,
     pub arg_list: Vec<(Token, Type)>,
-    pub body: Block
+    pub body: Block,
+    pub span: Span
 }
+/// A type parameter of a generic function with the traits it must implement, like the
+/// `T: Show + Eq` of `fn f<T: Show + Eq>(...)`.
+#[derive(Debug, Clone)]
+pub struct TypeParam {
+    pub name: Token,
+    pub bounds: Vec<Token>
+}
+
+/// `struct Name { field: type, ... }`
+#[derive(Debug, Clone)]
+pub struct StructDefinition {
+    pub name: Token,
+    pub fields: Vec<(Token, Type)>,
+    pub span: Span
+}
+
+/// `enum Name { Variant, Variant(type, ...), ... }`
+#[derive(Debug, Clone)]
+pub struct EnumDefinition {
+    pub name: Token,
+    pub variants: Vec<(Token, Vec<Type>)>,
+    pub span: Span
+}
+
+/// `trait Name { fn method(self, ...) -> type; ... }`
+#[derive(Debug, Clone)]
+pub struct TraitDefinition {
+    pub name: Token,
+    pub methods: Vec<MethodSignature>,
+    pub span: Span
+}
+
+/// A method of a trait without its body. The first argument is always `self`, of type `Self`.
+#[derive(Debug, Clone)]
+pub struct MethodSignature {
+    pub name: Token,
+    pub arg_list: Vec<(Token, Type)>,
+    pub type_: Type
+}
+
+/// `impl Trait for type { fn method(self, ...) -> type { ... } ... }`
+#[derive(Debug, Clone)]
+pub struct ImplBlock {
+    pub trait_: Token,
+    pub type_: Type,
+    pub methods: Vec<FunctionDefinition>,
+    pub span: Span
+}
+
+/// What a `for` loop walks over.
+#[derive(Debug, Clone)]
+pub enum ForIterable {
+    /// `start..end`, or `start..=end` when inclusive.
+    Range {
+        start: Box<Expression>,
+        end: Box<Expression>,
+        inclusive: bool
+    },
+    List(Box<Expression>)
+}
+
 #[derive(Debug, Clone)]
 pub enum Statement {
     VariableDeclaration {
         name: Token,
         type_: Type,
-        value: Expression
+        value: Expression,
+        span: Span
+    },
+    /// `let (a, b): type_ = value;`
+    TupleDeclaration {
+        names: Vec<Token>,
+        type_: Type,
+        value: Expression,
+        span: Span
     },
     FunctionDefinitionStatement(FunctionDefinition),
+    StructDefinitionStatement(StructDefinition),
+    EnumDefinitionStatement(EnumDefinition),
+    TraitDefinitionStatement(TraitDefinition),
+    ImplStatement(ImplBlock),
+    /// `to = value`, or `to += value` and the like when there is an operator. `fields` is the
+    /// path to the field being assigned, like `[b, c]` for `a.b.c = value`.
     Assignment {
         to: Token,
-        value: Expression
+        fields: Vec<Token>,
+        operator: Option<BinaryOperator>,
+        value: Expression,
+        span: Span
+    },
+    /// `list[index] = value`, or `list[index] += value` and the like when there is an operator.
+    IndexAssignment {
+        list: Box<Expression>,
+        index: Box<Expression>,
+        operator: Option<BinaryOperator>,
+        value: Expression,
+        span: Span
     },
     While {
+        label: Option<Token>,
         condition: Expression,
-        body: Block
+        body: Block,
+        span: Span
     },
-    Return(Expression),
+    For {
+        label: Option<Token>,
+        variable: Token,
+        iterable: ForIterable,
+        body: Block,
+        span: Span
+    },
+    /// `break`, optionally out of the loop with the given label.
+    Break(Option<Token>, Span),
+    Continue(Option<Token>, Span),
+    Return(Expression, Span),
     Expression(Expression)
 }
+
+impl Statement {
+    #[allow(dead_code)]
+    pub fn span(&self) -> Span {
+        match self {
+            Statement::VariableDeclaration { span, .. } => *span,
+            Statement::TupleDeclaration { span, .. } => *span,
+            Statement::FunctionDefinitionStatement(def) => def.span,
+            Statement::StructDefinitionStatement(def) => def.span,
+            Statement::EnumDefinitionStatement(def) => def.span,
+            Statement::TraitDefinitionStatement(def) => def.span,
+            Statement::ImplStatement(block) => block.span,
+            Statement::Assignment { span, .. } => *span,
+            Statement::IndexAssignment { span, .. } => *span,
+            Statement::While { span, .. } => *span,
+            Statement::For { span, .. } => *span,
+            Statement::Break(_, span) => *span,
+            Statement::Continue(_, span) => *span,
+            Statement::Return(_, span) => *span,
+            Statement::Expression(e) => e.span(),
+        }
+    }
+}
 impl ToSExpr for Statement {
     fn to_s_expr(self) -> SExpr<String> {
         type S = Statement;
         match self {
-            S::VariableDeclaration { name, type_, value } => {
+            S::VariableDeclaration { name, type_, value, span: _ } => {
                 SExpr::Function(String::from("variable_declaration"), vec![
                     SExpr::Value(format!("{}:{}", name.lexeme(), type_.to_string())),
                     value.to_s_expr()
                 ])
             }
-            S::FunctionDefinitionStatement(def) => {
+            S::TupleDeclaration { names, type_, value, span: _ } => {
+                SExpr::Function(String::from("variable_declaration"), vec![
+                    SExpr::Value(format!("({}):{}", names.iter().map(|x| x.lexeme()).join(", "), type_)),
+                    value.to_s_expr()
+                ])
+            }
+            S::FunctionDefinitionStatement(def) => def.to_s_expr(),
+            S::StructDefinitionStatement(def) => {
                 let mut args = vec![SExpr::Value(def.name.lexeme().to_string())];
-                for t in def. arg_list
-                    .into_iter()
-                    .map(|(name, ast_type)| {
-                        SExpr::Value(format!("{}:{}", name.lexeme(), ast_type))
-                    }) {
-                    args.push(t)
-                }
-                args.push(def.body.to_s_expr());
-                args.push(SExpr::Value(format!("->{}", def.type_.to_string())));
-
-                SExpr::Function(String::from("function_define"), args)
+                args.extend(def.fields.into_iter().map(|(name, type_)| SExpr::Value(format!("{}:{}", name.lexeme(), type_))));
+                SExpr::Function(String::from("struct"), args)
+            }
+            S::EnumDefinitionStatement(def) => {
+                let mut args = vec![SExpr::Value(def.name.lexeme().to_string())];
+                args.extend(def.variants.into_iter().map(|(name, payload)| {
+                    SExpr::Function(name.lexeme().to_string(), payload.into_iter().map(|x| SExpr::Value(x.to_string())).collect())
+                }));
+                SExpr::Function(String::from("enum"), args)
+            }
+            S::TraitDefinitionStatement(def) => {
+                let mut args = vec![SExpr::Value(def.name.lexeme().to_string())];
+                args.extend(def.methods.into_iter().map(|method| {
+                    SExpr::Function(method.name.lexeme().to_string(), method.arg_list.into_iter()
+                        .map(|(name, type_)| SExpr::Value(format!("{}:{}", name.lexeme(), type_)))
+                        .chain([SExpr::Value(format!("->{}", method.type_))])
+                        .collect())
+                }));
+                SExpr::Function(String::from("trait"), args)
+            }
+            S::ImplStatement(block) => {
+                let mut args = vec![SExpr::Value(block.trait_.lexeme().to_string()), SExpr::Value(block.type_.to_string())];
+                args.extend(block.methods.into_iter().map(|x| x.to_s_expr()));
+                SExpr::Function(String::from("impl"), args)
+            }
+            S::Assignment { to, fields, operator, value, span: _ } => {
+                let name = operator.map(|x| format!("{}=", x)).unwrap_or(String::from("="));
+                SExpr::Function(name, vec![
+                    SExpr::Value(place_name(&to, fields.iter())),
+                    value.to_s_expr(),
+                ])
             }
-            S::Assignment { to, value } => {
-                SExpr::Function(String::from("="), vec![
-                    SExpr::Value(to.lexeme().to_string()),
+            S::IndexAssignment { list, index, operator, value, span: _ } => {
+                let name = operator.map(|x| format!("{}=", x)).unwrap_or(String::from("="));
+                SExpr::Function(name, vec![
+                    SExpr::Function(String::from("index"), vec![list.to_s_expr(), index.to_s_expr()]),
                     value.to_s_expr(),
                 ])
             }
-            S::While { condition, body } => {
-                SExpr::Function(String::from("while"), vec![condition.to_s_expr(), body.to_s_expr()])
+            S::While { label, condition, body, span: _ } => {
+                SExpr::Function(String::from("while"), label_s_expr(label).into_iter()
+                    .chain([condition.to_s_expr(), body.to_s_expr()])
+                    .collect())
             }
-            S::Return(e) => SExpr::Function(String::from("return"), vec![e.to_s_expr()]),
+            S::For { label, variable, iterable, body, span: _ } => {
+                let iterable = match iterable {
+                    ForIterable::Range { start, end, inclusive } => {
+                        SExpr::Function(String::from(if inclusive { "..=" } else { ".." }), vec![start.to_s_expr(), end.to_s_expr()])
+                    }
+                    ForIterable::List(list) => list.to_s_expr()
+                };
+                SExpr::Function(String::from("for"), label_s_expr(label).into_iter()
+                    .chain([SExpr::Value(variable.lexeme().to_string()), iterable, body.to_s_expr()])
+                    .collect())
+            }
+            S::Break(label, _) => SExpr::Function(String::from("break"), label_s_expr(label).into_iter().collect()),
+            S::Continue(label, _) => SExpr::Function(String::from("continue"), label_s_expr(label).into_iter().collect()),
+            S::Return(e, _) => SExpr::Function(String::from("return"), vec![e.to_s_expr()]),
             S::Expression(e) => e.to_s_expr(),
         }
     }
-}
\ No newline at end of file
+}
+
+impl ToSExpr for FunctionDefinition {
+    fn to_s_expr(self) -> SExpr<String> {
+        let mut args = vec![SExpr::Value(generic_name(&self.name, &self.type_params))];
+        for t in self.arg_list
+            .into_iter()
+            .map(|(name, ast_type)| {
+                SExpr::Value(format!("{}:{}", name.lexeme(), ast_type))
+            }) {
+            args.push(t)
+        }
+        args.push(self.body.to_s_expr());
+        args.push(SExpr::Value(format!("->{}", self.type_.to_string())));
+
+        SExpr::Function(String::from("function_define"), args)
+    }
+}
+
+/// Loop labels lead the s-expr of the statement they belong to.
+pub fn label_s_expr(label: Option<Token>) -> Option<SExpr<String>> {
+    label.map(|x| SExpr::Value(x.lexeme().to_string()))
+}
+
+/// The name of a function with its type parameters and their bounds, like `pair<T:Show+Eq,U>`,
+/// or just `pair`.
+pub fn generic_name(name: &Token, type_params: &[TypeParam]) -> String {
+    if type_params.is_empty() {
+        return name.lexeme().to_string()
+    }
+    let type_params = type_params.iter().map(|param| {
+        if param.bounds.is_empty() {
+            return param.name.lexeme().to_string()
+        }
+        format!("{}:{}", param.name.lexeme(), param.bounds.iter().map(|x| x.lexeme()).join("+"))
+    });
+    format!("{}<{}>", name.lexeme(), type_params.format(","))
+}
+
+/// How an assignment target reads in the source, like `a.b.c`.
+pub fn place_name<'a>(to: &'a Token, fields: impl Iterator<Item=&'a Token>) -> String {
+    std::iter::once(to).chain(fields).map(|x| x.lexeme()).collect::<Vec<_>>().join(".")
+}
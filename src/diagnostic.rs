@@ -0,0 +1,329 @@
+use std::fmt::{Display, Formatter};
+use itertools::Itertools;
+use crate::llvm::CodegenError;
+use crate::monomorphizer::MonomorphizeError;
+use crate::parser::lex_error::LexErrors;
+use crate::parser::parser_error::ParserErrors;
+use crate::parser::span::Span;
+use crate::typer::TypingError;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[allow(dead_code)]
+pub enum Severity {
+    Error,
+    Warning,
+    Note,
+}
+
+impl Severity {
+    fn colour(&self) -> &'static str {
+        match self {
+            Severity::Error => RED,
+            Severity::Warning => YELLOW,
+            Severity::Note => BLUE,
+        }
+    }
+}
+
+impl Display for Severity {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", match self {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+            Severity::Note => "note",
+        })
+    }
+}
+
+/// A span of source with a short message to print under it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Label {
+    pub span: Span,
+    pub message: String,
+}
+
+/// A problem found in the program by any phase of the compiler, in a form that can be shown
+/// to the user next to the source it is about.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub message: String,
+    pub primary: Option<Label>,
+    pub secondary: Vec<Label>,
+    pub notes: Vec<String>,
+    pub help: Option<String>,
+}
+
+pub trait ToDiagnostic {
+    fn to_diagnostic(&self) -> Diagnostic;
+}
+
+const RED: &str = "\x1b[1;31m";
+const YELLOW: &str = "\x1b[1;33m";
+const BLUE: &str = "\x1b[1;34m";
+const BOLD: &str = "\x1b[1m";
+const RESET: &str = "\x1b[0m";
+
+#[allow(dead_code)]
+impl Diagnostic {
+    pub fn new(severity: Severity, message: impl AsRef<str>) -> Self {
+        Self {
+            severity,
+            message: message.as_ref().to_string(),
+            primary: None,
+            secondary: vec![],
+            notes: vec![],
+            help: None,
+        }
+    }
+
+    pub fn error(message: impl AsRef<str>) -> Self {
+        Self::new(Severity::Error, message)
+    }
+
+    /// Where the problem is. Spans that do not point into the source, like the default span
+    /// of a token the compiler made up, are left off.
+    pub fn with_primary(mut self, span: Span, message: impl AsRef<str>) -> Self {
+        if span.start.row() > 0 {
+            self.primary = Some(Label { span, message: message.as_ref().to_string() });
+        }
+        self
+    }
+
+    /// Somewhere else that explains the problem, like where a variable was declared.
+    pub fn with_secondary(mut self, span: Span, message: impl AsRef<str>) -> Self {
+        if span.start.row() > 0 {
+            self.secondary.push(Label { span, message: message.as_ref().to_string() });
+        }
+        self
+    }
+
+    pub fn with_note(mut self, note: impl AsRef<str>) -> Self {
+        self.notes.push(note.as_ref().to_string());
+        self
+    }
+
+    pub fn with_help(mut self, help: impl AsRef<str>) -> Self {
+        self.help = Some(help.as_ref().to_string());
+        self
+    }
+
+    /// Render against the `source` the spans point into, rustc style:
+    ///
+    /// ```text
+    /// error: mismatched types
+    ///  --> 2:12
+    ///   |
+    /// 2 |     return 1 + true;
+    ///   |                ^^^^ expected int, found bool
+    ///   |
+    ///   = help: ...
+    /// ```
+    pub fn render(&self, source: &str, colour: bool) -> String {
+        let paint = |code: &'static str, text: &str| if colour { format!("{}{}{}", code, text, RESET) } else { text.to_string() };
+        let severity_colour = self.severity.colour();
+
+        let mut out = format!("{}{}\n",
+            paint(severity_colour, &self.severity.to_string()),
+            paint(BOLD, &format!(": {}", self.message))
+        );
+
+        let labels = self.primary.iter().map(|x| (x, true))
+            .chain(self.secondary.iter().map(|x| (x, false)))
+            .sorted_by_key(|(label, primary)| (label.span.start.row(), !*primary))
+            .collect_vec();
+
+        let gutter_width = labels.iter().map(|(x, _)| x.span.start.row().to_string().len()).max().unwrap_or(0);
+        let gutter = " ".repeat(gutter_width);
+        let bar = paint(BLUE, "|");
+
+        if let Some((first, _)) = labels.first() {
+            let at = self.primary.as_ref().unwrap_or(first).span.start;
+            out += &format!("{}{} {}:{}\n", gutter, paint(BLUE, "-->"), at.row(), at.column());
+            out += &format!("{} {}\n", gutter, bar);
+        }
+
+        let lines = source.split('\n').map(|x| x.trim_end_matches('\r')).collect_vec();
+        for row_labels in labels.chunk_by(|a, b| a.0.span.start.row() == b.0.span.start.row()) {
+            let row = row_labels[0].0.span.start.row();
+            let line = lines.get(row - 1).copied().unwrap_or("");
+            out += &format!("{} {} {}\n", paint(BLUE, &format!("{:>width$}", row, width = gutter_width)), bar, line);
+
+            for (label, primary) in row_labels {
+                // copy tabs from the line so the underline lines up however tabs are shown
+                let indent: String = line.chars()
+                    .take(label.span.start.column() - 1)
+                    .map(|x| if x == '\t' { '\t' } else { ' ' })
+                    .collect();
+                let end_column = if label.span.end.row() == row {
+                    label.span.end.column()
+                } else {
+                    line.chars().count() + 1
+                };
+                let width = end_column.saturating_sub(label.span.start.column()).max(1);
+                let (mark, mark_colour) = if *primary { ("^", severity_colour) } else { ("-", BLUE) };
+
+                let underline = format!("{} {}", mark.repeat(width), label.message);
+                out += &format!("{} {} {}{}\n", gutter, bar, indent, paint(mark_colour, underline.trim_end()));
+            }
+        }
+
+        if !labels.is_empty() && (!self.notes.is_empty() || self.help.is_some()) {
+            out += &format!("{} {}\n", gutter, bar);
+        }
+        for note in &self.notes {
+            out += &format!("{} {} {}\n", gutter, paint(BOLD, "= note:"), note);
+        }
+        if let Some(help) = &self.help {
+            out += &format!("{} {} {}\n", gutter, paint(BOLD, "= help:"), help);
+        }
+
+        out
+    }
+}
+
+impl Display for Diagnostic {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.severity, self.message)
+    }
+}
+
+/// The diagnostics behind an error from any phase of the compiler. Errors that did not come
+/// from the program itself, like failing to write a file, become a single bare diagnostic.
+pub fn from_error(error: &anyhow::Error) -> Vec<Diagnostic> {
+    if let Some(errors) = error.downcast_ref::<LexErrors>() {
+        return errors.0.iter().map(ToDiagnostic::to_diagnostic).collect()
+    }
+    if let Some(errors) = error.downcast_ref::<ParserErrors>() {
+        return errors.0.iter().map(ToDiagnostic::to_diagnostic).collect()
+    }
+    if let Some(error) = error.downcast_ref::<TypingError>() {
+        return vec![error.to_diagnostic()]
+    }
+    if let Some(error) = error.downcast_ref::<MonomorphizeError>() {
+        return vec![error.to_diagnostic()]
+    }
+    if let Some(error) = error.downcast_ref::<CodegenError>() {
+        return vec![error.to_diagnostic()]
+    }
+
+    vec![Diagnostic::error(format!("{:#}", error))]
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::llvm::convert;
+    use crate::parser::location::Location;
+    use crate::parser::Parser;
+    use crate::typer::Typer;
+    use super::*;
+
+    fn span(offset: usize, row: usize, column: usize, length: usize) -> Span {
+        Span::new(Location::new(offset, row, column), Location::new(offset + length, row, column + length))
+    }
+
+    /// Run `text` through the whole compiler, returning how it failed.
+    fn compile_error(text: &str) -> Vec<Diagnostic> {
+        let error = Parser::new(text).map_err(anyhow::Error::from)
+            .and_then(|x| x.parse())
+            .and_then(|x| Ok(Typer::type_ast(x)?))
+            .and_then(|x| convert(x))
+            .unwrap_err();
+        from_error(&error)
+    }
+
+    #[test]
+    fn labels_notes_and_help() {
+        let source = "let x: int = 1;\nx = true;";
+        let diagnostic = Diagnostic::error("mismatched types")
+            .with_primary(span(20, 2, 5, 4), "expected int, found bool")
+            .with_secondary(span(7, 1, 8, 3), "declared here")
+            .with_note("x is an int")
+            .with_help("compare with == to get a bool");
+
+        assert_eq!(diagnostic.render(source, false), "\
+error: mismatched types
+ --> 2:5
+  |
+1 | let x: int = 1;
+  |        --- declared here
+2 | x = true;
+  |     ^^^^ expected int, found bool
+  |
+  = note: x is an int
+  = help: compare with == to get a bool
+");
+    }
+
+    #[test]
+    fn spans_running_past_the_line_and_tabs() {
+        let source = "\tx = 1 +\n\t\t2;";
+        let diagnostic = Diagnostic::error("oops")
+            .with_primary(Span::new(Location::new(5, 1, 6), Location::new(12, 2, 4)), "");
+        assert_eq!(diagnostic.render(source, false), "error: oops\n --> 1:6\n  |\n1 | \tx = 1 +\n  | \t    ^^^\n");
+    }
+
+    #[test]
+    fn unlocated_diagnostics() {
+        let diagnostic = Diagnostic::error("no main function")
+            .with_primary(Span::default(), "ignored")
+            .with_note("every program needs one");
+        assert_eq!(diagnostic.render("", false), "error: no main function\n = note: every program needs one\n");
+    }
+
+    #[test]
+    fn colour() {
+        let diagnostic = Diagnostic::error("oops").with_primary(span(0, 1, 1, 1), "here");
+        let rendered = diagnostic.render("x", true);
+        assert!(rendered.starts_with("\x1b[1;31merror\x1b[0m"));
+        assert!(rendered.contains("\x1b[1;31m^ here\x1b[0m"));
+        assert!(!diagnostic.render("x", false).contains('\x1b'));
+    }
+
+    #[test]
+    fn lex_errors_underline_the_whole_lexeme() {
+        let source = "let x: int = 123456789012345678901234567890123456789012345;";
+        assert_eq!(compile_error(source)[0].render(source, false), "\
+error: integer literal `123456789012345678901234567890123456789012345` is too large
+ --> 1:14
+  |
+1 | let x: int = 123456789012345678901234567890123456789012345;
+  |              ^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^ too large
+");
+
+        let source = "let s: str = \"abc\nlet t: str = \"\\q\";";
+        assert_eq!(compile_error(source)[0].render(source, false), "\
+error: unterminated string
+ --> 1:14
+  |
+1 | let s: str = \"abc
+  |              ^^^^ opened here
+");
+        assert_eq!(compile_error(source)[1].primary.as_ref().unwrap().span, span(32, 2, 15, 2));
+    }
+
+    #[test]
+    fn every_phase_converts() {
+        let lex = compile_error("let s: str = \"abc");
+        assert_eq!(lex[0].message, "unterminated string");
+        assert_eq!(lex[0].primary.as_ref().unwrap().span.start, Location::new(13, 1, 14));
+
+        let parse = compile_error("let x: int = ;\nlet y: int = 2 +;");
+        assert_eq!(parse.len(), 2);
+        assert_eq!(parse[1].message, "unexpected `;`");
+        assert_eq!(parse[1].primary.as_ref().unwrap().span.start.row(), 2);
+
+        let typing = compile_error("fn main() -> int { return 1 + \"a\"; }");
+        assert_eq!(typing[0].render("fn main() -> int { return 1 + \"a\"; }", false), "\
+error: mismatched types
+ --> 1:31
+  |
+1 | fn main() -> int { return 1 + \"a\"; }
+  |                               ^^^ expected int, found str
+");
+
+        let codegen = compile_error("fn main() -> bool { return !true; }");
+        assert_eq!(codegen[0].message, "`!` can not be compiled yet");
+        assert_eq!(codegen[0].primary.as_ref().unwrap().span.byte_range(), 27..32);
+    }
+}
@@ -14,8 +14,14 @@ impl Counters {
     }
 
     pub fn next(&mut self, what: impl AsRef<str>) -> String {
+        let count = self.count(what.as_ref());
+        return format!("{}_{}", what.as_ref(), count)
+    }
+
+    /// How many times `what` has been counted, this one included.
+    pub fn count(&mut self, what: impl AsRef<str>) -> usize {
         let count = self.counters.entry(what.as_ref().to_string()).or_insert(0);
         *count += 1;
-        return format!("{}_{}", what.as_ref(), *count)
+        *count
     }
 }
\ No newline at end of file
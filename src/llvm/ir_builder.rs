@@ -1,14 +1,21 @@
+use std::collections::{BTreeSet, HashMap, HashSet};
 use itertools::Itertools;
 use crate::ast::binary_operator::BinaryOperator;
 use crate::ast::unary_operator::UnaryOperator;
+use crate::llvm::CodegenError;
 use crate::llvm::counters::Counters;
 use crate::llvm::element::Element;
 use crate::llvm::element::Element::{Elem, Scope};
 use crate::llvm::ir_builder::MemoryValue::{Const, Temp};
+use crate::llvm::runtime::Runtime;
 use crate::llvm::variable::Variable;
+use crate::monomorphizer::method_name;
+use crate::parser::span::Span;
+use crate::parser::token::Token;
 use crate::typed_ast::r#type::Type;
 use crate::typed_ast::typed_expression::TypedExpression;
-use crate::typed_ast::typed_statement::TypedStatement;
+use crate::typed_ast::typed_pattern::{TypedMatchArm, TypedPattern};
+use crate::typed_ast::typed_statement::{FunctionDefinition, TypedForIterable, TypedStatement};
 use crate::typed_ast::TypedBlock;
 
 #[derive(Debug, Clone)]
@@ -31,20 +38,455 @@ impl MemoryValue {
         }
     }
 }
+/// The blocks `continue` and `break` jump to from inside a loop.
+struct LoopTargets {
+    label: Option<String>,
+    next: String,
+    end: String,
+}
+
 pub struct IrBuilder {
     counters: Counters,
+    globals: Vec<Element>,
+    runtime: BTreeSet<Runtime>,
+    /// Variables living in their own alloca, like loop variables, by the name they have in the source.
+    renames: Vec<HashMap<String, String>>,
+    /// The loops around the statement being converted, innermost last.
+    loops: Vec<LoopTargets>,
+    /// The field types of every struct, to know how large they are.
+    structs: HashMap<String, Vec<Type>>,
+    /// The payload types of every variant of every enum, in declaration order.
+    enums: HashMap<String, Vec<Vec<Type>>>,
+    /// Lambdas, and wrappers for named functions used as values, which can't be defined in
+    /// the middle of the function that uses them.
+    functions: Vec<Element>,
+    /// The named functions that already have a wrapper.
+    wrapped: HashSet<String>,
+    /// The allocas of the function being converted. They all go at the start of its entry
+    /// block, so code that runs many times, like a loop body, doesn't grow the stack each time.
+    allocas: Vec<Element>,
+    /// The names of the allocas of the function being converted, which no other alloca in it
+    /// can have.
+    homes: HashSet<String>,
 }
 
 impl IrBuilder {
     pub fn new() -> Self {
         Self {
             counters: Counters::new(),
+            globals: vec![],
+            runtime: Default::default(),
+            renames: vec![HashMap::new()],
+            loops: vec![],
+            structs: HashMap::new(),
+            enums: HashMap::new(),
+            functions: vec![],
+            wrapped: HashSet::new(),
+            allocas: vec![],
+            homes: HashSet::new(),
+        }
+    }
+
+    /// Make room for a `type_` named `%home` in the entry block of the current function.
+    fn alloca(&mut self, home: &str, type_: &Type) {
+        self.homes.insert(home.to_string());
+        self.allocas.push(Elem(format!("%{} = alloca {}", home, type_.llvm_type())));
+    }
+
+    /// The allocas made since the last call, for code outside any function.
+    pub fn take_allocas(&mut self) -> Vec<Element> {
+        std::mem::take(&mut self.allocas)
+    }
+
+    /// Wrap the converted program with the globals and runtime it refers to.
+    pub fn into_module(self, program: Vec<Element>) -> Vec<Element> {
+        let mut module = self.globals;
+        module.extend(program);
+        module.extend(self.functions);
+        for piece in self.runtime {
+            module.extend(piece.elements());
+        }
+        module
+    }
+
+    fn use_runtime(&mut self, piece: Runtime) {
+        for dependency in piece.dependencies() {
+            self.use_runtime(dependency);
+        }
+        self.runtime.insert(piece);
+    }
+
+    /// Emit `text` as a private global and return a str value pointing at it.
+    fn string_constant(&mut self, text: &str) -> MemoryValue {
+        let name = self.counters.next(".str");
+        let len = text.len();
+        let escaped: String = text.bytes().map(|b| match b {
+            b'"' | b'\\' => format!("\\{:02X}", b),
+            b' '..=b'~' => (b as char).to_string(),
+            _ => format!("\\{:02X}", b),
+        }).collect();
+
+        self.globals.push(Elem(format!("@{} = private unnamed_addr constant [{} x i8] c\"{}\"", name, len, escaped)));
+        Const(
+            format!("{{ i32 {}, i8* getelementptr inbounds ([{} x i8], [{} x i8]* @{}, i32 0, i32 0) }}", len, len, len, name),
+            Type::Str
+        )
+    }
+
+    /// `len(s)` is the byte length stored alongside the string's data, `len(xs)` the number of
+    /// elements in the list's header.
+    fn convert_len(&mut self, arguments: Vec<TypedExpression>, scope: &mut Vec<Element>) -> anyhow::Result<MemoryValue> {
+        let argument = arguments.into_iter().next().unwrap();
+        let is_list = matches!(argument.get_type(), Type::List(_));
+        let value = self.convert_expression(argument, scope)?;
+        if is_list {
+            return Ok(self.list_len(&value, scope))
+        }
+        let ans = Temp(self.counters.next("len"), Type::Int);
+        scope.push(Elem(format!("{} = extractvalue {}, 0", ans.clone().to_ir(false), value.to_ir(true))));
+        Ok(ans)
+    }
+
+    /// `push(xs, v)` puts `v` in a new slot at the end of the list.
+    fn convert_push(&mut self, arguments: Vec<TypedExpression>, scope: &mut Vec<Element>) -> anyhow::Result<MemoryValue> {
+        let [list, value] = <[TypedExpression; 2]>::try_from(arguments).unwrap();
+        let element = value.get_type();
+        let list = self.convert_expression(list, scope)?;
+        let value = self.convert_expression(value, scope)?;
+        let slot = self.push_slot(&list, &element, scope);
+        scope.push(Elem(Variable::new(slot, element).store(value)));
+        Ok(Const(String::new(), Type::Unit))
+    }
+
+    fn list_len(&mut self, list: &MemoryValue, scope: &mut Vec<Element>) -> MemoryValue {
+        let pointer = self.counters.next("len_ptr");
+        scope.push(Elem(format!("%{} = getelementptr inbounds {{ i8*, i32, i32 }}, {}, i32 0, i32 1", pointer, list.clone().to_ir(true))));
+        let ans = Temp(self.counters.next("len"), Type::Int);
+        scope.push(Elem(Variable::new(pointer, Type::Int).load(ans.clone())));
+        ans
+    }
+
+    /// A pointer to the first element of `list`, good until the next push moves the data.
+    fn list_data(&mut self, list: &MemoryValue, element: &Type, scope: &mut Vec<Element>) -> String {
+        let pointer = self.counters.next("data_ptr");
+        scope.push(Elem(format!("%{} = getelementptr inbounds {{ i8*, i32, i32 }}, {}, i32 0, i32 0", pointer, list.clone().to_ir(true))));
+        let data = self.counters.next("data");
+        scope.push(Elem(format!("%{} = load i8*, i8** %{}", data, pointer)));
+        self.element_pointer(data, element, scope)
+    }
+
+    /// The type of the code in a function value, which takes what the function captured
+    /// before its arguments.
+    fn code_type(params: &[Type], return_type: &Type) -> String {
+        let params = ["i8*".to_string()].into_iter().chain(params.iter().map(|x| x.llvm_type()));
+        format!("{} ({})*", return_type.llvm_type(), params.into_iter().join(", "))
+    }
+
+    /// A function value that runs `@code` and captured nothing.
+    fn function_value(code: &str, params: &[Type], return_type: &Type) -> String {
+        format!("{{ i8* bitcast ({} @{} to i8*), i8* null }}", Self::code_type(params, return_type), code)
+    }
+
+    /// A named function as a value, through a wrapper that takes the unused captures first
+    /// like a lambda would.
+    fn convert_function_value(&mut self, name: Token, type_: Type) -> MemoryValue {
+        let Type::Function(params, return_type) = &type_ else { unreachable!("a function has a function type") };
+        let wrapper = format!("{}.closure", name.lexeme());
+        if self.wrapped.insert(name.lexeme().to_string()) {
+            let args = params.iter().enumerate().map(|(index, x)| format!("{} %arg_{}", x.llvm_type(), index)).collect_vec();
+            self.functions.extend([
+                Elem(format!("define private {} @{}({}) {{",
+                    return_type.llvm_type(),
+                    wrapper,
+                    ["i8* %closure.env".to_string()].iter().chain(&args).join(", ")
+                )),
+                Scope(vec![
+                    Elem(format!("%result = call {} @{}({})", return_type.llvm_type(), name.lexeme(), args.join(", "))),
+                    Elem(format!("ret {} %result", return_type.llvm_type())),
+                ]),
+                Elem(String::from("}")),
+            ]);
+        }
+        Const(Self::function_value(&wrapper, params, return_type), type_.clone())
+    }
+
+    /// Define the body of a lambda as a function of its own and make a value to call it with.
+    /// Whatever it captured is copied to the heap when the value is made, and the body reads
+    /// that copy. The typer makes sure the body never assigns to it.
+    fn convert_lambda(&mut self, params: Vec<(Token, Type)>, captures: Vec<(Token, Type)>, body: TypedBlock, type_: Type, span: Span, scope: &mut Vec<Element>) -> anyhow::Result<MemoryValue> {
+        let Type::Function(param_types, return_type) = &type_ else { unreachable!("a lambda has a function type") };
+        // no identifier has a `.` in it, so this can't be the name of one of the program's functions
+        let name = format!("lambda.{}", self.counters.count("lambda"));
+        let environment = Type::Tuple(captures.iter().map(|(_, x)| x.clone()).collect());
+
+        let mut entry = vec![];
+        let mut homes = HashMap::new();
+        if !captures.is_empty() {
+            entry.push(Elem(format!("%closure.captures = bitcast i8* %closure.env to {}*", environment.llvm_type())));
+        }
+        for (index, (capture, _)) in captures.iter().enumerate() {
+            let home = self.counters.next(format!("{}.capture", capture.lexeme()));
+            entry.push(Elem(format!("%{} = getelementptr inbounds {}, {}* %closure.captures, i32 0, i32 {}",
+                home,
+                environment.llvm_type(),
+                environment.llvm_type(),
+                index
+            )));
+            homes.insert(capture.lexeme().to_string(), home);
+        }
+
+        // nothing from around the lambda is in scope in a function of its own
+        let outer_renames = std::mem::replace(&mut self.renames, vec![homes]);
+        let outer_loops = std::mem::take(&mut self.loops);
+        let outer_allocas = std::mem::take(&mut self.allocas);
+        let outer_homes = std::mem::take(&mut self.homes);
+        let returns = matches!(body.body.last(), Some(TypedStatement::Return(_)));
+        let converted = params.iter()
+            .try_for_each(|(name, type_)| {
+                self.alloca(name.lexeme(), type_);
+                self.store_variable(&mut entry, type_.clone(), name.lexeme(), Temp(format!("_{}", name.lexeme()), type_.clone()))
+            })
+            .and_then(|_| self.convert_block(body));
+        self.renames = outer_renames;
+        self.loops = outer_loops;
+        self.homes = outer_homes;
+        let mut prologue = std::mem::replace(&mut self.allocas, outer_allocas);
+        let (body, value) = converted?;
+        prologue.extend(entry);
+
+        let header = format!("define private {} @{}({}) {{",
+            return_type.llvm_type(),
+            name,
+            ["i8* %closure.env".to_string()].into_iter()
+                .chain(params.iter().map(|(name, type_)| format!("{} %_{}", type_.llvm_type(), name.lexeme())))
+                .join(", ")
+        );
+        self.functions.push(Elem(header));
+        self.functions.push(Scope(prologue));
+        self.functions.push(Scope(body));
+        if !returns {
+            let Some(value) = value else {
+                return Err(CodegenError::Unsupported(String::from("a lambda that doesn't end in a value"), span).into())
+            };
+            self.functions.push(Scope(vec![Elem(format!("ret {}", value.to_ir(true)))]));
+        }
+        self.functions.push(Elem(String::from("}")));
+
+        let code = Self::function_value(&name, param_types, return_type);
+        if captures.is_empty() {
+            return Ok(Const(code, type_.clone()))
+        }
+
+        self.use_runtime(Runtime::Malloc);
+        let env = self.counters.next("env");
+        scope.push(Elem(format!("%{} = call i8* @malloc({})", env, Self::size_of(&environment))));
+        let stored = self.counters.next("captures");
+        scope.push(Elem(format!("%{} = bitcast i8* %{} to {}*", stored, env, environment.llvm_type())));
+        for (index, (capture, capture_type)) in captures.into_iter().enumerate() {
+            let value = self.load_variable(scope, capture_type.clone(), capture.lexeme())?;
+            let slot = self.counters.next("capture_ptr");
+            scope.push(Elem(format!("%{} = getelementptr inbounds {}, {}* %{}, i32 0, i32 {}",
+                slot,
+                environment.llvm_type(),
+                environment.llvm_type(),
+                stored,
+                index
+            )));
+            scope.push(Elem(Variable::new(slot, capture_type).store(value)));
         }
+
+        let ans = Temp(self.counters.next("closure"), type_.clone());
+        scope.push(Elem(format!("{} = insertvalue {} {}, i8* %{}, 1", ans.clone().to_ir(false), type_.llvm_type(), code, env)));
+        Ok(ans)
+    }
+
+    /// Call the code in a function value, handing it what the function captured.
+    fn convert_call(&mut self, callee: TypedExpression, arguments: Vec<TypedExpression>, type_: Type, scope: &mut Vec<Element>) -> anyhow::Result<MemoryValue> {
+        let Type::Function(params, return_type) = callee.get_type() else { unreachable!("only functions are called") };
+        let callee = self.convert_expression(callee, scope)?;
+        let arguments: Vec<String> = arguments.into_iter()
+            .map(|x| self.convert_expression(x, scope).map(|x| x.to_ir(true)))
+            .try_collect()?;
+
+        let code = self.counters.next("code");
+        scope.push(Elem(format!("%{} = extractvalue {}, 0", code, callee.clone().to_ir(true))));
+        let function = self.counters.next("function");
+        scope.push(Elem(format!("%{} = bitcast i8* %{} to {}", function, code, Self::code_type(&params, &return_type))));
+        let env = self.counters.next("env");
+        scope.push(Elem(format!("%{} = extractvalue {}, 1", env, callee.to_ir(true))));
+
+        let ans = Temp(self.counters.next("call"), type_.clone());
+        scope.push(Elem(format!("{} = call {} %{}({})",
+            ans.clone().to_ir(false),
+            type_.llvm_type(),
+            function,
+            [format!("i8* %{}", env)].into_iter().chain(arguments).join(", ")
+        )));
+        Ok(ans)
+    }
+
+    /// The size of a `type_` in bytes, as a constant LLVM works out for the target.
+    fn size_of(type_: &Type) -> String {
+        let llvm_type = type_.llvm_type();
+        format!("i64 ptrtoint ({}* getelementptr ({}, {}* null, i32 1) to i64)", llvm_type, llvm_type, llvm_type)
+    }
+
+    /// Make room at the end of `list` and return a pointer to the new slot.
+    fn push_slot(&mut self, list: &MemoryValue, element: &Type, scope: &mut Vec<Element>) -> String {
+        self.use_runtime(Runtime::ListPush);
+        let slot = self.counters.next("slot");
+        scope.push(Elem(format!("%{} = call i8* @__list_push({}, {})", slot, list.clone().to_ir(true), Self::size_of(element))));
+        self.element_pointer(slot, element, scope)
+    }
+
+    /// `lhs operator rhs`, which the typer has found to have type `type_`.
+    fn convert_binary_operation(&mut self, lhs: MemoryValue, operator: BinaryOperator, rhs: MemoryValue, type_: Type, span: Span, scope: &mut Vec<Element>) -> MemoryValue {
+        let (Temp(_, lhs_type) | Const(_, lhs_type)) = &lhs;
+        let lhs_type = lhs_type.clone();
+
+        if lhs_type == Type::Str {
+            let (function, piece, op_name) = match operator {
+                BinaryOperator::Add => ("__str_concat", Runtime::StrConcat, "concat"),
+                BinaryOperator::Equals | BinaryOperator::NotEquals => ("__str_eq", Runtime::StrEq, "eq"),
+                _ => unreachable!("The typer only allows +, == and != on strings.")
+            };
+            self.use_runtime(piece);
+
+            let ans = Temp(self.counters.next(op_name), type_.clone());
+            scope.push(Elem(format!("{} = call {} @{}({}, {})",
+                                    ans.clone().to_ir(false),
+                                    type_.llvm_type(),
+                                    function,
+                                    lhs.to_ir(true),
+                                    rhs.to_ir(true)
+            )));
+
+            if operator == BinaryOperator::NotEquals {
+                let negated = Temp(self.counters.next("ne"), Type::Bool);
+                scope.push(Elem(format!("{} = xor {}, 1", negated.clone().to_ir(false), ans.to_ir(true))));
+                return negated
+            }
+            return ans
+        }
+
+        // a constant divisor other than 0 or -1 can never trap, anything else is checked
+        let safe_divisor = matches!(&rhs, Const(v, _) if v.parse::<i64>().is_ok_and(|x| x != 0 && x != -1));
+        if lhs_type == Type::Int && matches!(operator, BinaryOperator::Div | BinaryOperator::Mod) && !safe_divisor {
+            let (function, piece, op_name) = match operator {
+                BinaryOperator::Div => ("__int_div", Runtime::IntDiv, "div"),
+                _ => ("__int_rem", Runtime::IntRem, "rem"),
+            };
+            self.use_runtime(piece);
+
+            let ans = Temp(self.counters.next(op_name), Type::Int);
+            scope.push(Elem(format!("{} = call i32 @{}({}, {}, i32 {}, i32 {})",
+                                    ans.clone().to_ir(false),
+                                    function,
+                                    lhs.to_ir(true),
+                                    rhs.to_ir(true),
+                                    span.start.row(),
+                                    span.start.column()
+            )));
+            return ans
+        }
+
+        let (op_string, op_name) = match (operator, &lhs_type) {
+            (BinaryOperator::Add, Type::Float) => ("fadd", "fadd"),
+            (BinaryOperator::Sub, Type::Float) => ("fsub", "fsub"),
+            (BinaryOperator::Mul, Type::Float) => ("fmul", "fmul"),
+            (BinaryOperator::Div, Type::Float) => ("fdiv", "fdiv"),
+            (BinaryOperator::Mod, Type::Float) => ("frem", "frem"),
+            (BinaryOperator::Equals, Type::Float) => ("fcmp oeq", "feq"),
+            // != is the one unordered comparison, so a NaN is unequal to everything
+            (BinaryOperator::NotEquals, Type::Float) => ("fcmp une", "fne"),
+            (BinaryOperator::GreaterThan, Type::Float) => ("fcmp ogt", "fgt"),
+            (BinaryOperator::GreaterEquals, Type::Float) => ("fcmp oge", "fge"),
+            (BinaryOperator::LessThan, Type::Float) => ("fcmp olt", "flt"),
+            (BinaryOperator::LessEquals, Type::Float) => ("fcmp ole", "fle"),
+            (BinaryOperator::Add, _) => ("add", "add"),
+            (BinaryOperator::Sub, _) => ("sub", "sub"),
+            (BinaryOperator::Mul, _) => ("mul", "mul"),
+            (BinaryOperator::Div, _) => ("sdiv", "div"),
+            (BinaryOperator::Mod, _) => ("srem", "rem"),
+            (BinaryOperator::Equals, _) => ("icmp eq", "eq"),
+            (BinaryOperator::NotEquals, _) => ("icmp ne", "ne"),
+            (BinaryOperator::GreaterThan, _) => ("icmp sgt", "gt"),
+            (BinaryOperator::GreaterEquals, _) => ("icmp sge", "ge"),
+            (BinaryOperator::LessThan, _) => ("icmp slt", "lt"),
+            (BinaryOperator::LessEquals, _) => ("icmp sle", "le"),
+            (BinaryOperator::And, _) => ("and", "and"),
+            (BinaryOperator::Or, _) => ("or", "or"),
+        };
+
+        let ans_name = self.counters.next(op_name);
+        let ans = Temp(ans_name.clone(),type_.clone());
+        scope.push(Elem(format!("{} = {} {} {}, {}",
+                                ans.clone().to_ir(false),
+                                op_string,
+                                lhs_type.llvm_type(),
+                                lhs.to_ir(false),
+                                rhs.to_ir(false)
+        )));
+
+        ans
+    }
+
+    /// A pointer to the element at `index` in `list`, aborting with the location of `span` if
+    /// there isn't one.
+    fn index_slot(&mut self, list: MemoryValue, index: MemoryValue, element: &Type, span: Span, scope: &mut Vec<Element>) -> String {
+        self.use_runtime(Runtime::ListIndex);
+        let slot = self.counters.next("slot");
+        scope.push(Elem(format!("%{} = call i8* @__list_index({}, {}, {}, i32 {}, i32 {})",
+            slot,
+            list.to_ir(true),
+            index.to_ir(true),
+            Self::size_of(element),
+            span.start.row(),
+            span.start.column()
+        )));
+        self.element_pointer(slot, element, scope)
+    }
+
+    fn element_pointer(&mut self, slot: String, element: &Type, scope: &mut Vec<Element>) -> String {
+        let pointer = self.counters.next("element_ptr");
+        scope.push(Elem(format!("%{} = bitcast i8* %{} to {}*", pointer, slot, element.llvm_type())));
+        pointer
+    }
+
+    /// `ord(c)` and `chr(i)` only change the type, a char is already held as its scalar value.
+    /// An int given to `chr` is checked to be one unless it is a constant that is, aborting
+    /// with the location of `span` if not.
+    fn convert_scalar_cast(&mut self, arguments: Vec<TypedExpression>, to: Type, span: Span, scope: &mut Vec<Element>) -> anyhow::Result<MemoryValue> {
+        let value = self.convert_expression(arguments.into_iter().next().unwrap(), scope)?;
+        let scalar = matches!(&value, Const(v, _) if v.parse::<u32>().ok().and_then(char::from_u32).is_some());
+        if to == Type::Char && !scalar {
+            self.use_runtime(Runtime::Chr);
+            let ans = Temp(self.counters.next("chr"), Type::Char);
+            scope.push(Elem(format!("{} = call i32 @__chr({}, i32 {}, i32 {})",
+                                    ans.clone().to_ir(false),
+                                    value.to_ir(true),
+                                    span.start.row(),
+                                    span.start.column()
+            )));
+            return Ok(ans)
+        }
+        Ok(match value {
+            Temp(name, _) => Temp(name, to),
+            Const(value, _) => Const(value, to),
+        })
+    }
+
+    /// The alloca `var` lives in.
+    fn resolve(&self, var: &str) -> String {
+        self.renames.iter().rev()
+            .find_map(|x| x.get(var))
+            .cloned()
+            .unwrap_or(var.to_string())
     }
 
     /// Load from a variable into a temp
     fn load_variable(&mut self, scope: &mut Vec<Element>, type_: Type, var: impl AsRef<str>) -> anyhow::Result<MemoryValue> {
-        let var = var.as_ref();
+        let var = &self.resolve(var.as_ref());
         let temp = self.counters.next(var);
         let home = Temp(temp, type_.clone());
 
@@ -57,14 +499,37 @@ impl IrBuilder {
 
     /// Store from a temp to a variable
     fn store_variable(&mut self, scope: &mut Vec<Element>, type_: Type, var: impl AsRef<str>, from: MemoryValue) -> anyhow::Result<()> {
-        let var = var.as_ref();
+        let var = &self.resolve(var.as_ref());
         let v = Variable::new(var.to_string(), type_.clone());
         let ir = v.store(from);
         scope.push(Elem(ir));
         Ok(())
     }
 
+    /// The alloca for a variable declared as `name`. That is `%name` the first time the function
+    /// declares it. After that, like in the next loop that declares one, or when `name` means
+    /// a variable living somewhere else, like a loop variable or a capture, the new variable
+    /// gets an alloca of its own.
+    fn declare(&mut self, name: &str) -> String {
+        if self.resolve(name) == name && !self.homes.contains(name) {
+            return name.to_string()
+        }
+        let home = self.counters.next(format!("{}.let", name));
+        self.renames.last_mut()
+            .expect("there is always a scope to declare in")
+            .insert(name.to_string(), home.clone());
+        home
+    }
+
     fn convert_block(&mut self, block: TypedBlock) -> anyhow::Result<(Vec<Element>, Option<MemoryValue>)> {
+        // what the block declares only shadows the variables around it until the block ends
+        self.renames.push(HashMap::new());
+        let converted = self.convert_statements(block);
+        self.renames.pop();
+        converted
+    }
+
+    fn convert_statements(&mut self, block: TypedBlock) -> anyhow::Result<(Vec<Element>, Option<MemoryValue>)> {
         let mut new_scope = vec![];
         let mut final_mv = None;
         for index in 0..block.body.len() {
@@ -77,49 +542,161 @@ impl IrBuilder {
         return Ok((new_scope, final_mv));
     }
 
+    fn convert_function(&mut self, name: &str, func_def: FunctionDefinition, scope: &mut Vec<Element>) -> anyhow::Result<()> {
+        let header = format!("define {} @{}({}) {{",
+                             func_def.type_.llvm_type(),
+                             name,
+            func_def.arg_list.iter()
+                                 .map(|(token, type_)| format!("{} %_{}", type_.llvm_type(), token.lexeme()))
+                                 .join(", ")
+        );
+        let outer_allocas = std::mem::take(&mut self.allocas);
+        let outer_homes = std::mem::take(&mut self.homes);
+        let mut stores = vec![];
+        for (name, type_) in func_def.arg_list.iter() {
+            self.alloca(name.lexeme(), type_);
+            self.store_variable(
+                &mut stores,
+                type_.clone(),
+                name.lexeme(),
+                Temp(format!("_{}", name.lexeme()), type_.clone())
+            )?;
+        }
+        let converted = self.convert_block(func_def.body);
+        self.homes = outer_homes;
+        let mut prologue = std::mem::replace(&mut self.allocas, outer_allocas);
+        let (body, _) = converted?;
+        prologue.extend(stores);
+        let tail = String::from("}");
+
+        scope.push(Elem(header));
+        scope.push(Scope(prologue));
+        scope.push(Scope(body));
+        scope.push(Elem(tail));
+
+        Ok(())
+    }
+
+    /// Call the function `@name` straight, with the result in a temporary named after `label`.
+    fn convert_direct_call(&mut self, name: &str, label: &str, arguments: Vec<TypedExpression>, type_: Type, scope: &mut Vec<Element>) -> anyhow::Result<MemoryValue> {
+        let expr_homes: Vec<String> = arguments
+            .into_iter()
+            .map(|x| self.convert_expression(x, scope).map(|x| x.to_ir(true)))
+            .try_collect()?;
+
+        let expr_homes = expr_homes.join(",");
+
+
+        let ans = self.counters.next(label);
+        let ans_home = Temp(ans, type_.clone());
+
+        let push = format!("{} = call {} @{}({})",
+                           ans_home.clone().to_ir(false),
+                           type_.llvm_type(),
+                           name,
+                           expr_homes
+        );
+
+        scope.push(Elem(push));
+
+        return Ok(ans_home)
+    }
+
     pub fn convert_statement(&mut self, statement: TypedStatement, scope: &mut Vec<Element>) -> anyhow::Result<Option<MemoryValue>> {
         match statement {
             TypedStatement::FunctionDefinitionStatement(func_def) => {
-                let header = format!("define {} @{}({}) {{",
-                                     func_def.type_.llvm_type(),
-                                     func_def.name.lexeme(),
-                    func_def.arg_list.iter()
-                                         .map(|(token, type_)| format!("{} %_{}", type_.llvm_type(), token.lexeme()))
-                                         .join(", ")
-                );
-                let mut allocs = vec![];
-                for (name, type_) in func_def.arg_list.iter() {
-                    allocs.push(Elem(format!("{} = alloca {}", format!("%{}", name.lexeme()), type_.clone().llvm_type())));
-                    self.store_variable(
-                        &mut allocs,
-                        type_.clone(),
-                        name.lexeme(),
-                        Temp(format!("_{}", name.lexeme()), type_.clone())
-                    )?;
+                let name = func_def.name.lexeme().to_string();
+                self.convert_function(&name, func_def, scope)?;
+                Ok(None)
+            },
+            // the methods are all that's left of a trait once its calls are typed
+            TypedStatement::TraitDefinition { .. } => Ok(None),
+            TypedStatement::Impl { trait_, type_, methods } => {
+                for method in methods {
+                    let name = method_name(trait_.lexeme(), method.name.lexeme(), &type_);
+                    self.convert_function(&name, method, scope)?;
                 }
-                let (body, _) = self.convert_block(func_def.body)?;
-                let tail = String::from("}");
-
-                scope.push(Elem(header));
-                scope.push(Scope(allocs));
-                scope.push(Scope(body));
-                scope.push(Elem(tail));
-
                 Ok(None)
             },
             TypedStatement::VariableDeclaration { name, type_, value } => {
                 let value = self.convert_expression(value, scope)?;
-                scope.push(Elem(format!("%{} = alloca {}", name.lexeme(), type_.clone().llvm_type())));
+                let home = self.declare(name.lexeme());
+                self.alloca(&home, &type_);
                 self.store_variable(scope, type_, name.lexeme(), value)?;
                 Ok(None)
             }
+            TypedStatement::TupleDeclaration { names, type_, value } => {
+                let Type::Tuple(elements) = type_ else { unreachable!("the typer only takes apart tuples") };
+                let value = self.convert_expression(value, scope)?;
+                for (index, (name, element)) in names.into_iter().zip(elements).enumerate() {
+                    if name.lexeme() == "_" {
+                        continue
+                    }
+                    let part = Temp(self.counters.next("element"), element.clone());
+                    scope.push(Elem(format!("{} = extractvalue {}, {}", part.clone().to_ir(false), value.clone().to_ir(true), index)));
+                    let home = self.declare(name.lexeme());
+                    self.alloca(&home, &element);
+                    self.store_variable(scope, element, name.lexeme(), part)?;
+                }
+                Ok(None)
+            }
+            TypedStatement::StructDefinition { name, fields } => {
+                self.globals.push(Elem(format!("{} = type {{ {} }}",
+                    Type::Struct(name.lexeme().to_string()).llvm_type(),
+                    fields.iter().map(|(_, type_)| type_.llvm_type()).join(", ")
+                )));
+                self.structs.insert(name.lexeme().to_string(), fields.into_iter().map(|(_, type_)| type_).collect());
+                Ok(None)
+            },
+            TypedStatement::EnumDefinition { name, variants } => {
+                let name = name.lexeme().to_string();
+                self.enums.insert(name.clone(), variants.into_iter().map(|(_, payload)| payload).collect());
+                // the tag, then enough words for the largest payload
+                self.globals.push(Elem(format!("{} = type {{ i32, [{} x i64] }}",
+                    Type::Enum(name.clone()).llvm_type(),
+                    self.payload_words(&name)
+                )));
+                Ok(None)
+            },
+            TypedStatement::FieldAssignment { to, type_, fields, value } => {
+                let field_type = value.get_type().llvm_type();
+                let value = self.convert_expression(value, scope)?;
+                let pointer = self.counters.next(format!("{}.field", to.lexeme()));
+                scope.push(Elem(format!("%{} = getelementptr inbounds {}, {}* %{}, i32 0, {}",
+                    pointer,
+                    type_.llvm_type(),
+                    type_.llvm_type(),
+                    self.resolve(to.lexeme()),
+                    fields.iter().map(|(_, index)| format!("i32 {}", index)).join(", ")
+                )));
+                scope.push(Elem(format!("store {}, {}* %{}", value.to_ir(true), field_type, pointer)));
+                Ok(None)
+            },
             TypedStatement::Assignment { to, value } => {
                 let type_ = value.get_type();
                 let value = self.convert_expression(value, scope)?;
                 self.store_variable(scope, type_, to.lexeme(), value)?;
                 Ok(None)
             },
-            TypedStatement::While { condition, body } => {
+            TypedStatement::IndexAssignment { list, index, operator, value, span } => {
+                let element = value.get_type();
+                let list = self.convert_expression(*list, scope)?;
+                let index = self.convert_expression(*index, scope)?;
+                let value = self.convert_expression(value, scope)?;
+                let slot = Variable::new(self.index_slot(list, index, &element, span, scope), element.clone());
+                // `xs[i] += v` reads and writes the element through the one slot
+                let value = match operator {
+                    Some(operator) => {
+                        let current = Temp(self.counters.next("element"), element.clone());
+                        scope.push(Elem(slot.load(current.clone())));
+                        self.convert_binary_operation(current, operator, value, element, span, scope)
+                    },
+                    None => value
+                };
+                scope.push(Elem(slot.store(value)));
+                Ok(None)
+            },
+            TypedStatement::While { label, condition, body } => {
                 let mut while_scope = vec![];
                 let while_entry = self.counters.next("while");
 
@@ -136,7 +713,7 @@ impl IrBuilder {
                 )));
 
                 self.push_label(&mut while_scope, while_true);
-                let (body, _) = self.convert_block(body)?;
+                let (body, _) = self.convert_loop_body(label, &while_entry, &while_end, body)?;
                 while_scope.push(Scope(body));
                 while_scope.push(Elem(format!("br label %{}", while_entry)));
 
@@ -145,6 +722,80 @@ impl IrBuilder {
                 scope.push(Scope(while_scope));
                 Ok(None)
             },
+            TypedStatement::For { label, variable, iterable, body } => {
+                let (start, end, inclusive) = match iterable {
+                    TypedForIterable::Range { start, end, inclusive } => (*start, *end, inclusive),
+                    TypedForIterable::List(list) => {
+                        let for_scope = self.convert_for_list(label, variable, *list, body)?;
+                        scope.push(Scope(for_scope));
+                        return Ok(None)
+                    }
+                };
+
+                let mut for_scope = vec![];
+                let start = self.convert_expression(start, &mut for_scope)?;
+                // the end is worked out once, before the first iteration
+                let end = self.convert_expression(end, &mut for_scope)?;
+
+                // every loop gets its own alloca so loops one after another don't redefine it
+                let home = self.counters.next(format!("{}.for", variable.lexeme()));
+                self.renames.push(HashMap::from([(variable.lexeme().to_string(), home.clone())]));
+                self.alloca(&home, &Type::Int);
+                self.store_variable(&mut for_scope, Type::Int, variable.lexeme(), start)?;
+
+                let for_entry = self.counters.next("for");
+                let for_true = self.counters.next("for_true");
+                let for_step = self.counters.next("for_step");
+                let for_end = self.counters.next("for_end");
+
+                for_scope.push(Elem(format!("br label %{}", &for_entry)));
+                self.push_label(&mut for_scope, &for_entry);
+                let current = self.load_variable(&mut for_scope, Type::Int, variable.lexeme())?;
+                let condition = self.counters.next("for_cond");
+                for_scope.push(Elem(format!("%{} = icmp {} {}, {}",
+                    condition,
+                    if inclusive { "sle" } else { "slt" },
+                    current.to_ir(true),
+                    end.clone().to_ir(false)
+                )));
+                for_scope.push(Elem(format!("br i1 %{}, label %{}, label %{}", condition, &for_true, &for_end)));
+
+                self.push_label(&mut for_scope, &for_true);
+                let (body, _) = self.convert_loop_body(label, &for_step, &for_end, body)?;
+                for_scope.push(Scope(body));
+                for_scope.push(Elem(format!("br label %{}", &for_step)));
+
+                self.push_label(&mut for_scope, &for_step);
+                let current = self.load_variable(&mut for_scope, Type::Int, variable.lexeme())?;
+                if inclusive {
+                    // stop on the end itself, so `..=` up to the largest int doesn't overflow
+                    let last = self.counters.next("for_last");
+                    let for_next = self.counters.next("for_next");
+                    for_scope.push(Elem(format!("%{} = icmp eq {}, {}", last, current.clone().to_ir(true), end.to_ir(false))));
+                    for_scope.push(Elem(format!("br i1 %{}, label %{}, label %{}", last, &for_end, &for_next)));
+                    self.push_label(&mut for_scope, &for_next);
+                }
+                let next = Temp(self.counters.next("for_inc"), Type::Int);
+                for_scope.push(Elem(format!("{} = add {}, 1", next.clone().to_ir(false), current.to_ir(true))));
+                self.store_variable(&mut for_scope, Type::Int, variable.lexeme(), next)?;
+                self.renames.pop();
+                for_scope.push(Elem(format!("br label %{}", &for_entry)));
+
+                self.push_label(&mut for_scope, for_end);
+
+                scope.push(Scope(for_scope));
+                Ok(None)
+            },
+            TypedStatement::Break(label) => {
+                let target = self.loop_targets(label).end.clone();
+                self.branch_out(scope, target, "after_break");
+                Ok(None)
+            },
+            TypedStatement::Continue(label) => {
+                let target = self.loop_targets(label).next.clone();
+                self.branch_out(scope, target, "after_continue");
+                Ok(None)
+            },
             TypedStatement::Return(e) => {
                 let v = self.convert_expression(e, scope)?;
                 scope.push(Elem(format!("ret {}", v.to_ir(true))));
@@ -158,6 +809,354 @@ impl IrBuilder {
         }
     }
 
+    /// Walks a hidden index up to the list's length, which is read again before every
+    /// iteration so elements pushed by the body are visited too.
+    fn convert_for_list(&mut self, label: Option<Token>, variable: Token, list: TypedExpression, body: TypedBlock) -> anyhow::Result<Vec<Element>> {
+        let Type::List(element) = list.get_type() else { unreachable!("only lists are iterated over") };
+        let element = *element;
+
+        let mut for_scope = vec![];
+        let list = self.convert_expression(list, &mut for_scope)?;
+        let index = self.counters.next("for_index");
+        self.alloca(&index, &Type::Int);
+        for_scope.push(Elem(format!("store i32 0, i32* %{}", index)));
+
+        let home = self.counters.next(format!("{}.for", variable.lexeme()));
+        self.renames.push(HashMap::from([(variable.lexeme().to_string(), home.clone())]));
+        self.alloca(&home, &element);
+
+        let for_entry = self.counters.next("for");
+        let for_true = self.counters.next("for_true");
+        let for_step = self.counters.next("for_step");
+        let for_end = self.counters.next("for_end");
+
+        for_scope.push(Elem(format!("br label %{}", &for_entry)));
+        self.push_label(&mut for_scope, &for_entry);
+        let current = Temp(self.counters.next("for_current"), Type::Int);
+        for_scope.push(Elem(Variable::new(index.clone(), Type::Int).load(current.clone())));
+        let len = self.list_len(&list, &mut for_scope);
+        let condition = self.counters.next("for_cond");
+        for_scope.push(Elem(format!("%{} = icmp slt {}, {}", condition, current.clone().to_ir(true), len.to_ir(false))));
+        for_scope.push(Elem(format!("br i1 %{}, label %{}, label %{}", condition, &for_true, &for_end)));
+
+        self.push_label(&mut for_scope, &for_true);
+        let data = self.list_data(&list, &element, &mut for_scope);
+        let pointer = self.counters.next("element_ptr");
+        for_scope.push(Elem(format!("%{} = getelementptr {}, {}* %{}, {}", pointer, element.llvm_type(), element.llvm_type(), data, current.to_ir(true))));
+        let value = Temp(self.counters.next("element"), element.clone());
+        for_scope.push(Elem(Variable::new(pointer, element.clone()).load(value.clone())));
+        self.store_variable(&mut for_scope, element, variable.lexeme(), value)?;
+        let (body, _) = self.convert_loop_body(label, &for_step, &for_end, body)?;
+        for_scope.push(Scope(body));
+        for_scope.push(Elem(format!("br label %{}", &for_step)));
+
+        self.push_label(&mut for_scope, &for_step);
+        let current = Temp(self.counters.next("for_current"), Type::Int);
+        for_scope.push(Elem(Variable::new(index.clone(), Type::Int).load(current.clone())));
+        let next = Temp(self.counters.next("for_inc"), Type::Int);
+        for_scope.push(Elem(format!("{} = add {}, 1", next.clone().to_ir(false), current.to_ir(true))));
+        for_scope.push(Elem(Variable::new(index, Type::Int).store(next)));
+        self.renames.pop();
+        for_scope.push(Elem(format!("br label %{}", &for_entry)));
+
+        self.push_label(&mut for_scope, for_end);
+        Ok(for_scope)
+    }
+
+    fn convert_loop_body(&mut self, label: Option<Token>, next: &str, end: &str, body: TypedBlock) -> anyhow::Result<(Vec<Element>, Option<MemoryValue>)> {
+        self.loops.push(LoopTargets {
+            label: label.map(|x| x.lexeme().to_string()),
+            next: next.to_string(),
+            end: end.to_string(),
+        });
+        let body = self.convert_block(body);
+        self.loops.pop();
+        body
+    }
+
+    /// The loop a `break` or `continue` leaves, which the typer has already checked exists.
+    fn loop_targets(&self, label: Option<Token>) -> &LoopTargets {
+        match label {
+            Some(label) => self.loops.iter().rev().find(|x| x.label.as_deref() == Some(label.lexeme())).unwrap(),
+            None => self.loops.last().unwrap(),
+        }
+    }
+
+    /// Jump to `target`, starting a new block for anything after the jump since a block can't
+    /// carry on past its terminator.
+    fn branch_out(&mut self, scope: &mut Vec<Element>, target: String, after: &str) {
+        scope.push(Elem(format!("br label %{}", target)));
+        let after = self.counters.next(after);
+        self.push_label(scope, after);
+    }
+
+    /// How many words the largest payload of the enum `name` fits in.
+    fn payload_words(&self, name: &str) -> usize {
+        self.enums[name].iter()
+            .map(|payload| payload.iter().map(|x| self.size_bound(x)).sum::<usize>())
+            .max()
+            .unwrap_or(0)
+            .div_ceil(8)
+    }
+
+    /// At least the number of bytes a `type_` takes up. Nothing is aligned to more than 8 bytes,
+    /// so rounding everything up to 8 leaves room for any padding.
+    fn size_bound(&self, type_: &Type) -> usize {
+        match type_ {
+            Type::Int | Type::Float | Type::Bool | Type::Char => 8,
+            Type::Str => 16,
+            Type::List(_) => 8,
+            Type::Struct(name) => self.structs[name].iter().map(|x| self.size_bound(x)).sum(),
+            Type::Tuple(elements) => elements.iter().map(|x| self.size_bound(x)).sum(),
+            Type::Enum(name) => 8 + 8 * self.payload_words(name),
+            Type::Function(..) => 16,
+            Type::Unit => 0,
+            Type::Named(_) | Type::Param(_) => unreachable!("the typer resolves {} and monomorphizing replaces type parameters", type_),
+        }
+    }
+
+    /// The anonymous struct one variant's payload is stored as.
+    fn payload_type(payload: &[Type]) -> String {
+        format!("{{ {} }}", payload.iter().map(|x| x.llvm_type()).join(", "))
+    }
+
+    /// Point at the payload of the enum stored at `%pointer` as if it held the variant with `payload`.
+    fn payload_pointer(&mut self, type_: &Type, pointer: &str, payload: &[Type], scope: &mut Vec<Element>) -> String {
+        let Type::Enum(name) = type_ else { unreachable!("only enums have payloads") };
+        let words = self.payload_words(name);
+        let slot = self.counters.next("payload_slot");
+        scope.push(Elem(format!("%{} = getelementptr inbounds {}, {}* %{}, i32 0, i32 1", slot, type_.llvm_type(), type_.llvm_type(), pointer)));
+        let cast = self.counters.next("payload_ptr");
+        scope.push(Elem(format!("%{} = bitcast [{} x i64]* %{} to {}*", cast, words, slot, Self::payload_type(payload))));
+        cast
+    }
+
+    /// The fields of the payload of `value`, which the caller knows holds the variant `tag` of `type_`.
+    fn payload_fields(&mut self, value: MemoryValue, type_: &Type, tag: usize, scope: &mut Vec<Element>) -> Vec<MemoryValue> {
+        let Type::Enum(name) = type_ else { unreachable!("only enums have payloads") };
+        let payload = self.enums[name][tag].clone();
+        let payload_type = Self::payload_type(&payload);
+
+        // only memory can be seen as another type, so the enum takes a trip through an alloca
+        let home = self.counters.next("enum");
+        self.alloca(&home, type_);
+        scope.push(Elem(Variable::new(home.clone(), type_.clone()).store(value)));
+        let pointer = self.payload_pointer(type_, &home, &payload, scope);
+        let loaded = self.counters.next("payload");
+        scope.push(Elem(format!("%{} = load {}, {}* %{}", loaded, payload_type, payload_type, pointer)));
+
+        payload.into_iter().enumerate().map(|(index, field_type)| {
+            let field = Temp(self.counters.next("field"), field_type);
+            scope.push(Elem(format!("{} = extractvalue {} %{}, {}", field.clone().to_ir(false), payload_type, loaded, index)));
+            field
+        }).collect()
+    }
+
+    fn convert_enum_variant(&mut self, type_: Type, tag: usize, payload: Vec<TypedExpression>, scope: &mut Vec<Element>) -> anyhow::Result<MemoryValue> {
+        if payload.is_empty() {
+            let ans = Temp(self.counters.next("enum"), type_.clone());
+            scope.push(Elem(format!("{} = insertvalue {} undef, i32 {}, 0", ans.clone().to_ir(false), type_.llvm_type(), tag)));
+            return Ok(ans)
+        }
+
+        let payload_types = payload.iter().map(|x| x.get_type()).collect_vec();
+        let payload_type = Self::payload_type(&payload_types);
+        let mut value = String::from("undef");
+        for (index, field) in payload.into_iter().enumerate() {
+            let field = self.convert_expression(field, scope)?;
+            let next = self.counters.next("payload");
+            scope.push(Elem(format!("%{} = insertvalue {} {}, {}, {}", next, payload_type, value, field.to_ir(true), index)));
+            value = format!("%{}", next);
+        }
+
+        let home = self.counters.next("enum");
+        self.alloca(&home, &type_);
+        let tag_pointer = self.counters.next("tag_ptr");
+        scope.push(Elem(format!("%{} = getelementptr inbounds {}, {}* %{}, i32 0, i32 0", tag_pointer, type_.llvm_type(), type_.llvm_type(), home)));
+        scope.push(Elem(format!("store i32 {}, i32* %{}", tag, tag_pointer)));
+        let pointer = self.payload_pointer(&type_, &home, &payload_types, scope);
+        scope.push(Elem(format!("store {} {}, {}* %{}", payload_type, value, payload_type, pointer)));
+
+        let ans = Temp(self.counters.next("enum"), type_.clone());
+        scope.push(Elem(Variable::new(home, type_).load(ans.clone())));
+        Ok(ans)
+    }
+
+    /// Switch on the tag of an enum to the arms that may match its variant, then try those in
+    /// order. Anything else tries every arm in order.
+    fn convert_match(&mut self, scrutinee: TypedExpression, arms: Vec<TypedMatchArm>, type_: Type, scope: &mut Vec<Element>) -> anyhow::Result<MemoryValue> {
+        let mut match_scope = vec![];
+        let scrutinee_type = scrutinee.get_type();
+        let value = self.convert_expression(scrutinee, &mut match_scope)?;
+
+        let has_value = type_ != Type::Unit;
+        let ret_var = Variable::new(self.counters.next("match_ret_var"), type_.clone());
+        if has_value {
+            self.alloca(&ret_var.name, &type_);
+        }
+
+        let arm_labels = arms.iter().map(|_| self.counters.next("match_arm")).collect_vec();
+        let match_fail = self.counters.next("match_fail");
+        let match_end = self.counters.next("match_end");
+
+        let patterns = arms.iter().map(|x| &x.pattern).collect_vec();
+        if let Type::Enum(name) = &scrutinee_type {
+            let variant_labels = (0..self.enums[name].len()).map(|_| self.counters.next("match_variant")).collect_vec();
+            let tag = self.counters.next("tag");
+            match_scope.push(Elem(format!("%{} = extractvalue {}, 0", tag, value.clone().to_ir(true))));
+            match_scope.push(Elem(format!("switch i32 %{}, label %{} [{}]",
+                tag,
+                match_fail,
+                variant_labels.iter().enumerate().map(|(variant, label)| format!("i32 {}, label %{}", variant, label)).join(" ")
+            )));
+
+            for (variant, label) in variant_labels.iter().enumerate() {
+                self.push_label(&mut match_scope, label);
+                let candidates = patterns.iter().copied().zip(&arm_labels)
+                    .filter(|(pattern, _)| !matches!(pattern, TypedPattern::Variant { tag, .. } if *tag != variant))
+                    .collect_vec();
+                self.convert_arm_tests(candidates, true, &value, &match_fail, &mut match_scope)?;
+            }
+        } else {
+            let candidates = patterns.into_iter().zip(&arm_labels).collect_vec();
+            self.convert_arm_tests(candidates, false, &value, &match_fail, &mut match_scope)?;
+        }
+
+        // the typer made sure some arm always matches
+        self.push_label(&mut match_scope, &match_fail);
+        match_scope.push(Elem(String::from("unreachable")));
+
+        for (arm, label) in arms.into_iter().zip(arm_labels) {
+            self.push_label(&mut match_scope, label);
+            let mut bindings = HashMap::new();
+            self.bind_pattern(&arm.pattern, value.clone(), &mut bindings, &mut match_scope);
+            self.renames.push(bindings);
+            let body = self.convert_block(arm.body);
+            self.renames.pop();
+
+            let (body, final_memory) = body?;
+            match_scope.push(Scope(body));
+            if let Some(final_memory) = final_memory.filter(|_| has_value) {
+                self.store_variable(&mut match_scope, type_.clone(), ret_var.name.clone(), final_memory)?;
+            }
+            match_scope.push(Elem(format!("br label %{}", match_end)));
+        }
+
+        self.push_label(&mut match_scope, &match_end);
+        if !has_value {
+            scope.push(Scope(match_scope));
+            return Ok(Const(String::new(), Type::Unit))
+        }
+        let ans = self.load_variable(&mut match_scope, type_, ret_var.name)?;
+        scope.push(Scope(match_scope));
+        Ok(ans)
+    }
+
+    /// Try `arms` one after another, jumping to the first whose pattern matches `value`. When
+    /// `tag_known`, the switch has already checked the variant of any variant pattern.
+    fn convert_arm_tests(&mut self, arms: Vec<(&TypedPattern, &String)>, tag_known: bool, value: &MemoryValue, fail: &str, scope: &mut Vec<Element>) -> anyhow::Result<()> {
+        for (pattern, arm_label) in arms {
+            let always_matches = match pattern {
+                TypedPattern::Variant { fields, .. } if tag_known => fields.iter().all(|x| x.is_irrefutable()),
+                _ => pattern.is_irrefutable()
+            };
+            if always_matches {
+                scope.push(Elem(format!("br label %{}", arm_label)));
+                return Ok(())
+            }
+
+            let next = self.counters.next("match_next");
+            match pattern {
+                TypedPattern::Variant { type_, tag, fields, .. } if tag_known => {
+                    self.convert_field_tests(type_, *tag, fields, value.clone(), &next, scope)?
+                }
+                _ => self.convert_pattern_test(pattern, value.clone(), &next, scope)?
+            }
+            scope.push(Elem(format!("br label %{}", arm_label)));
+            self.push_label(scope, next);
+        }
+
+        scope.push(Elem(format!("br label %{}", fail)));
+        Ok(())
+    }
+
+    /// Jump to `fail` unless `value` matches `pattern`, carrying on in a new block if it does.
+    fn convert_pattern_test(&mut self, pattern: &TypedPattern, value: MemoryValue, fail: &str, scope: &mut Vec<Element>) -> anyhow::Result<()> {
+        match pattern {
+            TypedPattern::Wildcard | TypedPattern::Binding(..) => {},
+            TypedPattern::Literal(literal) => {
+                let literal = self.convert_expression(literal.clone(), scope)?;
+                let matched = self.counters.next("pattern_eq");
+                scope.push(Elem(format!("%{} = icmp eq {}, {}", matched, value.to_ir(true), literal.to_ir(false))));
+                self.branch_unless(scope, &matched, fail);
+            },
+            TypedPattern::Variant { type_, tag, fields, .. } => {
+                let actual = self.counters.next("tag");
+                scope.push(Elem(format!("%{} = extractvalue {}, 0", actual, value.clone().to_ir(true))));
+                let matched = self.counters.next("pattern_eq");
+                scope.push(Elem(format!("%{} = icmp eq i32 %{}, {}", matched, actual, tag)));
+                self.branch_unless(scope, &matched, fail);
+                self.convert_field_tests(type_, *tag, fields, value, fail, scope)?;
+            },
+        }
+        Ok(())
+    }
+
+    /// Jump to `fail` unless each part of the payload of `value` matches its pattern in `fields`.
+    fn convert_field_tests(&mut self, type_: &Type, tag: usize, fields: &[TypedPattern], value: MemoryValue, fail: &str, scope: &mut Vec<Element>) -> anyhow::Result<()> {
+        if fields.iter().all(|x| x.is_irrefutable()) {
+            return Ok(())
+        }
+        let values = self.payload_fields(value, type_, tag, scope);
+        for (field, value) in fields.iter().zip(values) {
+            self.convert_pattern_test(field, value, fail, scope)?;
+        }
+        Ok(())
+    }
+
+    fn branch_unless(&mut self, scope: &mut Vec<Element>, condition: &str, fail: &str) {
+        let next = self.counters.next("pattern_next");
+        scope.push(Elem(format!("br i1 %{}, label %{}, label %{}", condition, next, fail)));
+        self.push_label(scope, next);
+    }
+
+    /// Give every name `pattern` binds an alloca holding its part of `value`.
+    fn bind_pattern(&mut self, pattern: &TypedPattern, value: MemoryValue, bindings: &mut HashMap<String, String>, scope: &mut Vec<Element>) {
+        match pattern {
+            TypedPattern::Binding(name, type_) => {
+                let home = self.counters.next(format!("{}.bind", name.lexeme()));
+                self.alloca(&home, type_);
+                scope.push(Elem(Variable::new(home.clone(), type_.clone()).store(value)));
+                bindings.insert(name.lexeme().to_string(), home);
+            },
+            TypedPattern::Variant { type_, tag, fields, .. } if pattern.binds() => {
+                let values = self.payload_fields(value, type_, *tag, scope);
+                for (field, value) in fields.iter().zip(values) {
+                    self.bind_pattern(field, value, bindings, scope);
+                }
+            },
+            _ => {}
+        }
+    }
+
+    /// Fill in an undefined struct or tuple one field at a time, in the order they were written.
+    fn convert_aggregate(&mut self, fields: Vec<(usize, TypedExpression)>, type_: Type, scope: &mut Vec<Element>) -> anyhow::Result<MemoryValue> {
+        let temp_name = if let Type::Tuple(_) = type_ { "tuple" } else { "struct" };
+        let mut value = Const(String::from("undef"), type_.clone());
+        for (index, field) in fields {
+            let field = self.convert_expression(field, scope)?;
+            let next = Temp(self.counters.next(temp_name), type_.clone());
+            scope.push(Elem(format!("{} = insertvalue {}, {}, {}",
+                next.clone().to_ir(false),
+                value.to_ir(true),
+                field.to_ir(true),
+                index
+            )));
+            value = next;
+        }
+        Ok(value)
+    }
+
     fn push_label(&self, scope: &mut Vec<Element>, label: impl AsRef<str>) {
         scope.push(Elem(format!("{}:", label.as_ref())));
     }
@@ -165,16 +1164,31 @@ impl IrBuilder {
     fn convert_expression(&mut self, expression: TypedExpression, scope: &mut Vec<Element>) -> anyhow::Result<MemoryValue> {
         type T = TypedExpression;
         match expression {
-            T::If { condition, true_block, else_block } => {
+            T::Tuple(elements, type_, _) => {
+                let fields = elements.into_iter().enumerate().collect();
+                self.convert_aggregate(fields, type_, scope)
+            },
+            T::StructLiteral { fields, type_, span: _ } => self.convert_aggregate(fields, type_, scope),
+            T::EnumVariant { type_, variant: _, tag, payload, span: _ } => self.convert_enum_variant(type_, tag, payload, scope),
+            T::Match { scrutinee, arms, type_, span: _ } => self.convert_match(*scrutinee, arms, type_, scope),
+            T::FieldAccess { of, index, type_, span: _ } => {
+                let of = self.convert_expression(*of, scope)?;
+                let ans = Temp(self.counters.next("field"), type_);
+                scope.push(Elem(format!("{} = extractvalue {}, {}", ans.clone().to_ir(false), of.to_ir(true), index)));
+                Ok(ans)
+            },
+            T::If { condition, true_block, else_block, span: _ } => {
                 let mut if_scope = vec![];
                 let condition = self.convert_expression(*condition, &mut if_scope)?;
                 let true_block_type = true_block.type_.clone();
 
+                // an if that only runs statements, like one ending in `break`, has no value to keep
+                let has_value = true_block_type != Type::Unit;
                 let ret_var = self.counters.next("if_ret_var");
                 let ret_var = Variable::new(ret_var, true_block_type.clone());
-                if_scope.push(Elem(
-                    format!("%{} = alloca {}",ret_var.name.clone(), ret_var.type_.llvm_type()))
-                );
+                if has_value {
+                    self.alloca(&ret_var.name, &ret_var.type_);
+                }
 
                 let if_true = self.counters.next("if_true");
                 let if_end = self.counters.next("if_end");
@@ -188,7 +1202,7 @@ impl IrBuilder {
                 self.push_label(&mut if_scope, &if_true);
                 let (true_scope, final_memory) = self.convert_block(true_block)?;
                 if_scope.push(Scope(true_scope));
-                if let Some(final_memory) = final_memory {
+                if let Some(final_memory) = final_memory.filter(|_| has_value) {
                     self.store_variable(&mut if_scope, true_block_type.clone(), ret_var.name.clone(), final_memory)?;
                 }
                 if_scope.push(Elem(format!("br label %{}", &if_end)));
@@ -199,7 +1213,7 @@ impl IrBuilder {
                     let else_block_type = else_block.type_.clone();
                     let (else_scope, final_memory) = self.convert_block(else_block)?;
                     if_scope.push(Scope(else_scope));
-                    if let Some(final_memory) = final_memory{
+                    if let Some(final_memory) = final_memory.filter(|_| has_value) {
                         self.store_variable(&mut if_scope, else_block_type, ret_var.name.clone(), final_memory)?;
                     }
                 }
@@ -208,66 +1222,52 @@ impl IrBuilder {
 
                 self.push_label(&mut if_scope, &if_end);
 
+                if !has_value {
+                    scope.push(Scope(if_scope));
+                    return Ok(Const(String::new(), Type::Unit))
+                }
                 let ret_var_temp = self.load_variable(&mut if_scope, true_block_type, ret_var.name)?;
 
                 scope.push(Scope(if_scope));
                 return Ok(ret_var_temp)
             },
-            T::BinaryOperation { lhs, operator, rhs, type_ } => {
-                let lhs_type = lhs.get_type();
+            T::BinaryOperation { lhs, operator, rhs, type_, span } => {
                 let lhs = self.convert_expression(*lhs, scope)?;
                 let rhs = self.convert_expression(*rhs, scope)?;
-                let (op_string, op_name) = match operator {
-                    BinaryOperator::Add => ("add", "add"),
-                    BinaryOperator::Equals => ("icmp eq", "eq"),
-                    BinaryOperator::GreaterThan => ("icmp sgt", "gt"),
-                    BinaryOperator::And => ("and", "and"),
-                    BinaryOperator::Or => ("or", "or"),
-                };
-
-                let ans_name = self.counters.next(op_name);
-                let ans = Temp(ans_name.clone(),type_.clone());
-
-
-                scope.push(Elem(format!("{} = {} {} {}, {}",
-                                        ans.clone().to_ir(false),
-                                        op_string,
-                                        lhs_type.llvm_type(),
-                                        lhs.to_ir(false),
-                                        rhs.to_ir(false)
-                )));
-
-                return Ok(ans)
+                Ok(self.convert_binary_operation(lhs, operator, rhs, type_, span, scope))
             },
-            T::FunctionCall { name, arguments, type_ } => {
-                let expr_homes: Vec<String> = arguments
-                    .into_iter()
-                    .map(|x| self.convert_expression(x, scope).map(|x| x.to_ir(true)))
-                    .try_collect()?;
-
-                let expr_homes = expr_homes.join(",");
-
-
-                let ans = self.counters.next(format!("function_{}", name.lexeme()));
-                let ans_home = Temp(ans, type_.clone());
-
-                let push = format!("{} = call {} @{}({})",
-                                   ans_home.clone().to_ir(false),
-                                   type_.llvm_type(),
-                                   name.lexeme(),
-                                   expr_homes
-                );
-
-                scope.push(Elem(push));
+            T::FunctionCall { name, arguments, type_arguments: _, type_, span } => {
+                match name.lexeme() {
+                    "len" => return self.convert_len(arguments, scope),
+                    "push" => return self.convert_push(arguments, scope),
+                    "ord" | "chr" => return self.convert_scalar_cast(arguments, type_, span, scope),
+                    _ => {}
+                }
 
-                return Ok(ans_home)
+                self.convert_direct_call(name.lexeme(), &format!("function_{}", name.lexeme()), arguments, type_, scope)
+            },
+            // static dispatch: the type of the receiver says which impl's method to call
+            T::MethodCall { receiver, trait_, method, arguments, type_, span: _ } => {
+                let name = method_name(&trait_, method.lexeme(), &receiver.get_type());
+                let arguments = [*receiver].into_iter().chain(arguments).collect();
+                self.convert_direct_call(&name, &format!("method_{}", method.lexeme()), arguments, type_, scope)
             },
-            T::UnaryOperation { operator, rhs } => {
+            T::UnaryOperation { operator, rhs, span } => {
                 match operator {
                     UnaryOperator::Sub => {
+                        // fold negative literals so -2147483648 never has to exist as a positive i32
+                        if let T::Int(t) = *rhs {
+                            return Ok(Const(format!("-{}", t.lexeme()), Type::Int))
+                        }
+
                         let rhs_type = rhs.get_type();
                         let rhs = self.convert_expression(*rhs, scope)?;
 
+                        if rhs_type == Type::Float {
+                            let ans = Temp(self.counters.next("fneg"), Type::Float);
+                            scope.push(Elem(format!("{} = fneg {}", ans.clone().to_ir(false), rhs.to_ir(true))));
+                            return Ok(ans)
+                        }
 
                         let ans_name = self.counters.next("sub");
                         let ans = Temp(ans_name.clone(),rhs_type.clone());
@@ -279,12 +1279,43 @@ impl IrBuilder {
 
                         return Ok(ans)
                     },
-                    UnaryOperator::Not => unimplemented!()
+                    UnaryOperator::Not => Err(CodegenError::Unsupported(String::from("`!`"), span).into())
                 }
             },
             T::Int(t) => Ok(Const(t.lexeme().to_string(), Type::Int)),
+            // LLVM only takes decimal doubles that are exactly representable, hex always works
+            T::Float(t) => Ok(Const(format!("0x{:016X}", t.lexeme().parse::<f64>()?.to_bits()), Type::Float)),
             T::Bool(v, _) => Ok(Const(String::from(if v { "1" } else { "0" }), Type::Bool)),
-            T::List(_, _) => unimplemented!(),
+            T::Str(t) => Ok(self.string_constant(t.lexeme())),
+            T::Char(t) => Ok(Const((t.lexeme().chars().next().unwrap() as u32).to_string(), Type::Char)),
+            T::List(elements, type_, _) => {
+                let Type::List(element) = &type_ else { unreachable!("a list literal has a list type") };
+                self.use_runtime(Runtime::ListNew);
+                let list = Temp(self.counters.next("list"), type_.clone());
+                scope.push(Elem(format!("{} = call {} @__list_new({}, i32 {})",
+                    list.clone().to_ir(false),
+                    type_.llvm_type(),
+                    Self::size_of(element),
+                    elements.len()
+                )));
+                for value in elements {
+                    let value = self.convert_expression(value, scope)?;
+                    let slot = self.push_slot(&list, element, scope);
+                    scope.push(Elem(Variable::new(slot, *element.clone()).store(value)));
+                }
+                Ok(list)
+            },
+            T::Index { of, index, type_, span } => {
+                let list = self.convert_expression(*of, scope)?;
+                let index = self.convert_expression(*index, scope)?;
+                let slot = self.index_slot(list, index, &type_, span, scope);
+                let ans = Temp(self.counters.next("element"), type_.clone());
+                scope.push(Elem(Variable::new(slot, type_).load(ans.clone())));
+                Ok(ans)
+            },
+            T::Lambda { params, captures, body, type_, span } => self.convert_lambda(params, captures, body, type_, span, scope),
+            T::Call { callee, arguments, type_, span: _ } => self.convert_call(*callee, arguments, type_, scope),
+            T::Function(name, type_) => Ok(self.convert_function_value(name, type_)),
             T::Name(t, type_) => self.load_variable(scope, type_, t.lexeme()),
         }
     }
@@ -293,12 +1324,13 @@ impl IrBuilder {
 #[cfg(test)]
 mod tests {
     use crate::llvm::convert;
+    use crate::monomorphizer::monomorphize;
     use crate::parser::Parser;
     use crate::typer::Typer;
 
     #[test]
     fn forty_two() -> anyhow::Result<()> {
-        let ast = Parser::new(crate::testing::demo_programs::THE_UNIVERSE).parse().unwrap();
+        let ast = Parser::new(crate::testing::demo_programs::THE_UNIVERSE).unwrap().parse().unwrap();
         let typed = Typer::type_ast(ast)?;
         let converted = convert(typed)?;
         assert_eq!(converted.join("\n"), "define i32 @main() {\n\tret i32 42\n}");
@@ -308,11 +1340,488 @@ mod tests {
 
     #[test]
     fn forty_two_add() -> anyhow::Result<()> {
-        let ast = Parser::new(crate::testing::demo_programs::THE_UNIVERSE_BY_ADDITION).parse().unwrap();
+        let ast = Parser::new(crate::testing::demo_programs::THE_UNIVERSE_BY_ADDITION).unwrap().parse().unwrap();
         let typed = Typer::type_ast(ast)?;
         let converted = convert(typed)?;
         assert_eq!(converted.join("\n"), "define i32 @main() {\n\t%add_1 = add i32 20, 22\n\tret i32 %add_1\n}");
 
         Ok(())
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn string_constant() -> anyhow::Result<()> {
+        let ast = Parser::new(r#"fn main() -> int { return len("a\"b\n"); }"#).unwrap().parse().unwrap();
+        let typed = Typer::type_ast(ast)?;
+        let converted = convert(typed)?;
+        assert_eq!(converted, vec![
+            "@.str_1 = private unnamed_addr constant [4 x i8] c\"a\\22b\\0A\"",
+            "define i32 @main() {",
+            "\t%len_1 = extractvalue { i32, i8* } { i32 4, i8* getelementptr inbounds ([4 x i8], [4 x i8]* @.str_1, i32 0, i32 0) }, 0",
+            "\tret i32 %len_1",
+            "}",
+        ]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn float_instructions() -> anyhow::Result<()> {
+        let ast = Parser::new("fn main() -> bool { return 0.1 + -1.5 > 2e0; }").unwrap().parse().unwrap();
+        let converted = convert(Typer::type_ast(ast)?)?;
+        assert_eq!(converted, vec![
+            "define i1 @main() {",
+            "\t%fneg_1 = fneg double 0x3FF8000000000000",
+            "\t%fadd_1 = fadd double 0x3FB999999999999A, %fneg_1",
+            "\t%fgt_1 = fcmp ogt double %fadd_1, 0x4000000000000000",
+            "\tret i1 %fgt_1",
+            "}",
+        ]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn division() -> anyhow::Result<()> {
+        let ast = Parser::new("fn f(x: int) -> int {\n return x / 3 - 7 % x; }").unwrap().parse().unwrap();
+        let converted = convert(Typer::type_ast(ast)?)?;
+        assert_eq!(converted[..7], vec![
+            "define i32 @f(i32 %_x) {",
+            "\t%x = alloca i32",
+            "\tstore i32 %_x, i32* %x",
+            "\t%x_1 = load i32, i32* %x",
+            "\t%div_1 = sdiv i32 %x_1, 3",
+            "\t%x_2 = load i32, i32* %x",
+            "\t%rem_1 = call i32 @__int_rem(i32 7, i32 %x_2, i32 2, i32 17)",
+        ]);
+        assert!(converted.iter().any(|x| x.starts_with("define private void @__division_by_zero")));
+        assert!(!converted.iter().any(|x| x.contains("@__int_div(")));
+
+        Ok(())
+    }
+
+    #[test]
+    fn compound_assignment() -> anyhow::Result<()> {
+        let ast = Parser::new("fn f(x: int) -> int { x *= 3; return x; }").unwrap().parse().unwrap();
+        let converted = convert(Typer::type_ast(ast)?)?;
+        assert_eq!(converted[3..6], vec![
+            "\t%x_1 = load i32, i32* %x",
+            "\t%mul_1 = mul i32 %x_1, 3",
+            "\tstore i32 %mul_1, i32* %x",
+        ]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn structs() -> anyhow::Result<()> {
+        let text = "struct P { x: int, y: int } struct L { a: P, b: P }
+fn f(l: L) -> P { l.b.y = 7; return P { y: l.a.x, x: 2 }; }";
+        let ast = Parser::new(text).unwrap().parse().unwrap();
+        let converted = convert(Typer::type_ast(ast)?)?;
+        assert_eq!(converted, vec![
+            "%struct.P = type { i32, i32 }",
+            "%struct.L = type { %struct.P, %struct.P }",
+            "define %struct.P @f(%struct.L %_l) {",
+            "\t%l = alloca %struct.L",
+            "\tstore %struct.L %_l, %struct.L* %l",
+            "\t%l.field_1 = getelementptr inbounds %struct.L, %struct.L* %l, i32 0, i32 1, i32 1",
+            "\tstore i32 7, i32* %l.field_1",
+            "\t%l_1 = load %struct.L, %struct.L* %l",
+            "\t%field_1 = extractvalue %struct.L %l_1, 0",
+            "\t%field_2 = extractvalue %struct.P %field_1, 0",
+            "\t%struct_1 = insertvalue %struct.P undef, i32 %field_2, 1",
+            "\t%struct_2 = insertvalue %struct.P %struct_1, i32 2, 0",
+            "\tret %struct.P %struct_2",
+            "}",
+        ]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn comparison_instructions() -> anyhow::Result<()> {
+        let ast = Parser::new("fn main() -> bool { return 1 < 2 != 3 >= 4 == 5 <= 6; }").unwrap().parse().unwrap();
+        let converted = convert(Typer::type_ast(ast)?)?;
+        assert_eq!(converted, vec![
+            "define i1 @main() {",
+            "\t%lt_1 = icmp slt i32 1, 2",
+            "\t%ge_1 = icmp sge i32 3, 4",
+            "\t%ne_1 = icmp ne i1 %lt_1, %ge_1",
+            "\t%le_1 = icmp sle i32 5, 6",
+            "\t%eq_1 = icmp eq i1 %ne_1, %le_1",
+            "\tret i1 %eq_1",
+            "}",
+        ]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn char_constants() -> anyhow::Result<()> {
+        let ast = Parser::new(r"fn main() -> int { return ord('\u{1F600}') + ord('\n'); }").unwrap().parse().unwrap();
+        let converted = convert(Typer::type_ast(ast)?)?;
+        assert_eq!(converted.join("\n"), "define i32 @main() {\n\t%add_1 = add i32 128512, 10\n\tret i32 %add_1\n}");
+
+        Ok(())
+    }
+
+    #[test]
+    fn chr_checks() -> anyhow::Result<()> {
+        let ast = Parser::new("fn f(x: int) -> char { return chr(x); }\nfn main() -> char { return chr(233); }").unwrap().parse().unwrap();
+        let converted = convert(Typer::type_ast(ast)?)?;
+        assert_eq!(converted[..4], [
+            "define i32 @f(i32 %_x) {",
+            "\t%x = alloca i32",
+            "\tstore i32 %_x, i32* %x",
+            "\t%x_1 = load i32, i32* %x",
+        ]);
+        assert_eq!(converted[4], "\t%chr_1 = call i32 @__chr(i32 %x_1, i32 1, i32 31)");
+        // a constant that is a scalar value needs no check
+        assert!(converted.contains(&String::from("\tret i32 233")));
+        assert_eq!(converted.iter().filter(|x| x.contains("@__chr(")).count(), 2);
+
+        for value in ["-1", "55296", "57343", "1114112"] {
+            let ast = Parser::new(format!("fn main() -> char {{ return chr({}); }}", value)).unwrap().parse().unwrap();
+            let converted = convert(Typer::type_ast(ast)?)?;
+            assert!(converted.iter().any(|x| x.contains("call i32 @__chr(")), "chr({}) is not checked", value);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn for_range() -> anyhow::Result<()> {
+        let text = "fn n() -> int { return 1; } fn f(x: int) -> int { return x; }
+fn main() -> int { for i in 0..=n() { f(i); } return 0; }";
+        let ast = Parser::new(text).unwrap().parse().unwrap();
+        let converted = convert(Typer::type_ast(ast)?)?;
+        let main = converted.iter().skip_while(|x| !x.starts_with("define i32 @main")).cloned().collect::<Vec<_>>();
+        assert_eq!(main, vec![
+            "define i32 @main() {",
+            "\t%i.for_1 = alloca i32",
+            "\t\t%function_n_1 = call i32 @n()",
+            "\t\tstore i32 0, i32* %i.for_1",
+            "\t\tbr label %for_1",
+            "\t\tfor_1:",
+            "\t\t%i.for_1_1 = load i32, i32* %i.for_1",
+            "\t\t%for_cond_1 = icmp sle i32 %i.for_1_1, %function_n_1",
+            "\t\tbr i1 %for_cond_1, label %for_true_1, label %for_end_1",
+            "\t\tfor_true_1:",
+            "\t\t\t%i.for_1_2 = load i32, i32* %i.for_1",
+            "\t\t\t%function_f_1 = call i32 @f(i32 %i.for_1_2)",
+            "\t\tbr label %for_step_1",
+            "\t\tfor_step_1:",
+            "\t\t%i.for_1_3 = load i32, i32* %i.for_1",
+            "\t\t%for_last_1 = icmp eq i32 %i.for_1_3, %function_n_1",
+            "\t\tbr i1 %for_last_1, label %for_end_1, label %for_next_1",
+            "\t\tfor_next_1:",
+            "\t\t%for_inc_1 = add i32 %i.for_1_3, 1",
+            "\t\tstore i32 %for_inc_1, i32* %i.for_1",
+            "\t\tbr label %for_1",
+            "\t\tfor_end_1:",
+            "\tret i32 0",
+            "}",
+        ]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn loop_control() -> anyhow::Result<()> {
+        let text = "fn main() -> int { 'outer: while true { while false { continue 'outer; } break; } return 0; }";
+        let ast = Parser::new(text).unwrap().parse().unwrap();
+        let converted = convert(Typer::type_ast(ast)?)?;
+        let converted: Vec<&str> = converted.iter().map(|x| x.trim()).collect();
+        assert_eq!(converted, vec![
+            "define i32 @main() {",
+            "br label %while_1",
+            "while_1:",
+            "br i1 1, label %while_true_1, label %while_end_1",
+            "while_true_1:",
+            "br label %while_2",
+            "while_2:",
+            "br i1 0, label %while_true_2, label %while_end_2",
+            "while_true_2:",
+            "br label %while_1",
+            "after_continue_1:",
+            "br label %while_2",
+            "while_end_2:",
+            "br label %while_end_1",
+            "after_break_1:",
+            "br label %while_1",
+            "while_end_1:",
+            "ret i32 0",
+            "}",
+        ]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn enums() -> anyhow::Result<()> {
+        let text = "enum E { A, B(int) } fn f(e: E) -> int { return match e { E::B(1) => 1, E::B(x) => x, E::A => 0 }; } fn main() -> int { return f(E::B(2)); }";
+        let converted = convert(Typer::type_ast(Parser::new(text).unwrap().parse().unwrap())?)?;
+        assert_eq!(converted[0], "%enum.E = type { i32, [1 x i64] }");
+        assert!(converted.contains(&String::from(
+            "\t\tswitch i32 %tag_1, label %match_fail_1 [i32 0, label %match_variant_1 i32 1, label %match_variant_2]"
+        )));
+        // the match's allocas, payload copies and bindings included, are all made on entry
+        assert_eq!(converted[1..8], vec![
+            "define i32 @f(%enum.E %_e) {",
+            "\t%e = alloca %enum.E",
+            "\t%match_ret_var_1 = alloca i32",
+            "\t%enum_1 = alloca %enum.E",
+            "\t%enum_2 = alloca %enum.E",
+            "\t%x.bind_1 = alloca i32",
+            "\tstore %enum.E %_e, %enum.E* %e",
+        ]);
+        // the variant is known once the switch picks a block, so only the payload is tested
+        assert_eq!(converted[11..22], vec![
+            "\t\tmatch_variant_1:",
+            "\t\tbr label %match_arm_3",
+            "\t\tmatch_variant_2:",
+            "\t\tstore %enum.E %e_1, %enum.E* %enum_1",
+            "\t\t%payload_slot_1 = getelementptr inbounds %enum.E, %enum.E* %enum_1, i32 0, i32 1",
+            "\t\t%payload_ptr_1 = bitcast [1 x i64]* %payload_slot_1 to { i32 }*",
+            "\t\t%payload_1 = load { i32 }, { i32 }* %payload_ptr_1",
+            "\t\t%field_1 = extractvalue { i32 } %payload_1, 0",
+            "\t\t%pattern_eq_1 = icmp eq i32 %field_1, 1",
+            "\t\tbr i1 %pattern_eq_1, label %pattern_next_1, label %match_next_1",
+            "\t\tpattern_next_1:",
+        ]);
+        assert!(converted.contains(&String::from("\t\tstore i32 %field_2, i32* %x.bind_1")));
+        assert!(converted.contains(&String::from("\tstore i32 1, i32* %tag_ptr_1")));
+
+        Ok(())
+    }
+
+    #[test]
+    fn tuples() -> anyhow::Result<()> {
+        let text = "fn f(a: int) -> (int, bool) { return (a, true); } fn main() -> int { let (x, _): (int, bool) = f(1); return x; }";
+        let converted = convert(Typer::type_ast(Parser::new(text).unwrap().parse().unwrap())?)?;
+        assert_eq!(converted, vec![
+            "define { i32, i1 } @f(i32 %_a) {",
+            "\t%a = alloca i32",
+            "\tstore i32 %_a, i32* %a",
+            "\t%a_1 = load i32, i32* %a",
+            "\t%tuple_1 = insertvalue { i32, i1 } undef, i32 %a_1, 0",
+            "\t%tuple_2 = insertvalue { i32, i1 } %tuple_1, i1 1, 1",
+            "\tret { i32, i1 } %tuple_2",
+            "}",
+            "define i32 @main() {",
+            "\t%x = alloca i32",
+            "\t%function_f_1 = call { i32, i1 } @f(i32 1)",
+            "\t%element_1 = extractvalue { i32, i1 } %function_f_1, 0",
+            "\tstore i32 %element_1, i32* %x",
+            "\t%x_1 = load i32, i32* %x",
+            "\tret i32 %x_1",
+            "}",
+        ]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn lists() -> anyhow::Result<()> {
+        let text = "fn main() -> int { let xs: list int = [7]; xs[0] = 2; return xs[0]; }";
+        let converted = convert(Typer::type_ast(Parser::new(text).unwrap().parse().unwrap())?)?;
+        let size = "i64 ptrtoint (i32* getelementptr (i32, i32* null, i32 1) to i64)";
+        assert_eq!(converted[..17], vec![
+            String::from("define i32 @main() {"),
+            String::from("\t%xs = alloca { i8*, i32, i32 }*"),
+            format!("\t%list_1 = call {{ i8*, i32, i32 }}* @__list_new({}, i32 1)", size),
+            format!("\t%slot_1 = call i8* @__list_push({{ i8*, i32, i32 }}* %list_1, {})", size),
+            String::from("\t%element_ptr_1 = bitcast i8* %slot_1 to i32*"),
+            String::from("\tstore i32 7, i32* %element_ptr_1"),
+            String::from("\tstore { i8*, i32, i32 }* %list_1, { i8*, i32, i32 }** %xs"),
+            String::from("\t%xs_1 = load { i8*, i32, i32 }*, { i8*, i32, i32 }** %xs"),
+            // bad indices are reported at the assignment and at the read
+            format!("\t%slot_2 = call i8* @__list_index({{ i8*, i32, i32 }}* %xs_1, i32 0, {}, i32 1, i32 44)", size),
+            String::from("\t%element_ptr_2 = bitcast i8* %slot_2 to i32*"),
+            String::from("\tstore i32 2, i32* %element_ptr_2"),
+            String::from("\t%xs_2 = load { i8*, i32, i32 }*, { i8*, i32, i32 }** %xs"),
+            format!("\t%slot_3 = call i8* @__list_index({{ i8*, i32, i32 }}* %xs_2, i32 0, {}, i32 1, i32 62)", size),
+            String::from("\t%element_ptr_3 = bitcast i8* %slot_3 to i32*"),
+            String::from("\t%element_1 = load i32, i32* %element_ptr_3"),
+            String::from("\tret i32 %element_1"),
+            String::from("}"),
+        ]);
+        for runtime in ["@__list_new(", "@__list_push(", "@__list_index(", "@__index_out_of_bounds(", "@realloc("] {
+            assert!(converted.iter().any(|x| (x.starts_with("define") || x.starts_with("declare")) && x.contains(runtime)));
+        }
+        assert!(!converted.iter().any(|x| x.contains("@__str_concat(")));
+
+        Ok(())
+    }
+
+    #[test]
+    fn closures() -> anyhow::Result<()> {
+        let text = "fn one() -> int { return 1; } fn main() -> int { let n: int = 2; let f: fn(int) -> int = |x: int| -> int { x + n }; let g: fn() -> int = one; return f(g()); }";
+        let converted = convert(Typer::type_ast(Parser::new(text).unwrap().parse().unwrap())?)?;
+        assert_eq!(converted, vec![
+            "define i32 @one() {",
+            "\tret i32 1",
+            "}",
+            "define i32 @main() {",
+            "\t%n = alloca i32",
+            "\t%f = alloca { i8*, i8* }",
+            "\t%g = alloca { i8*, i8* }",
+            "\tstore i32 2, i32* %n",
+            // `n` is copied into the lambda's environment when the lambda is made
+            "\t%env_1 = call i8* @malloc(i64 ptrtoint ({ i32 }* getelementptr ({ i32 }, { i32 }* null, i32 1) to i64))",
+            "\t%captures_1 = bitcast i8* %env_1 to { i32 }*",
+            "\t%n_1 = load i32, i32* %n",
+            "\t%capture_ptr_1 = getelementptr inbounds { i32 }, { i32 }* %captures_1, i32 0, i32 0",
+            "\tstore i32 %n_1, i32* %capture_ptr_1",
+            "\t%closure_1 = insertvalue { i8*, i8* } { i8* bitcast (i32 (i8*, i32)* @lambda.1 to i8*), i8* null }, i8* %env_1, 1",
+            "\tstore { i8*, i8* } %closure_1, { i8*, i8* }* %f",
+            "\tstore { i8*, i8* } { i8* bitcast (i32 (i8*)* @one.closure to i8*), i8* null }, { i8*, i8* }* %g",
+            "\t%f_1 = load { i8*, i8* }, { i8*, i8* }* %f",
+            "\t%g_1 = load { i8*, i8* }, { i8*, i8* }* %g",
+            "\t%code_1 = extractvalue { i8*, i8* } %g_1, 0",
+            "\t%function_1 = bitcast i8* %code_1 to i32 (i8*)*",
+            "\t%env_2 = extractvalue { i8*, i8* } %g_1, 1",
+            "\t%call_1 = call i32 %function_1(i8* %env_2)",
+            "\t%code_2 = extractvalue { i8*, i8* } %f_1, 0",
+            "\t%function_2 = bitcast i8* %code_2 to i32 (i8*, i32)*",
+            "\t%env_3 = extractvalue { i8*, i8* } %f_1, 1",
+            "\t%call_2 = call i32 %function_2(i8* %env_3, i32 %call_1)",
+            "\tret i32 %call_2",
+            "}",
+            "define private i32 @lambda.1(i8* %closure.env, i32 %_x) {",
+            "\t%x = alloca i32",
+            "\t%closure.captures = bitcast i8* %closure.env to { i32 }*",
+            "\t%n.capture_1 = getelementptr inbounds { i32 }, { i32 }* %closure.captures, i32 0, i32 0",
+            "\tstore i32 %_x, i32* %x",
+            "\t%x_1 = load i32, i32* %x",
+            "\t%n.capture_1_1 = load i32, i32* %n.capture_1",
+            "\t%add_1 = add i32 %x_1, %n.capture_1_1",
+            "\tret i32 %add_1",
+            "}",
+            "define private i32 @one.closure(i8* %closure.env) {",
+            "\t%result = call i32 @one()",
+            "\tret i32 %result",
+            "}",
+            "declare i8* @malloc(i64)",
+        ]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn redeclared_locals() -> anyhow::Result<()> {
+        let text = crate::testing::demo_programs::REDECLARED_LOCALS;
+        let converted = convert(Typer::type_ast(Parser::new(text).unwrap().parse().unwrap())?)?;
+        let allocas: Vec<&String> = converted.iter().filter(|x| x.contains(" = alloca ")).collect();
+        // every declaration after the first gets an alloca of its own, even in another block
+        assert_eq!(allocas, vec![
+            "\t%s = alloca i32",
+            "\t%i.for_1 = alloca i32",
+            "\t%y = alloca i32",
+            "\t%i.for_2 = alloca i32",
+            "\t%y.let_1 = alloca i32",
+            "\t%if_ret_var_1 = alloca i32",
+            "\t%y.let_2 = alloca i32",
+            "\t%y.let_3 = alloca i32",
+            "\t%a = alloca i32",
+            "\t%b = alloca i32",
+            "\t%a.let_1 = alloca i32",
+            "\t%b.let_1 = alloca i32",
+        ]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn lambda_names_clash_with_nothing() -> anyhow::Result<()> {
+        let text = "fn lambda_1() -> int { return 1; } fn lambda() -> int { return 2; } fn main() -> int { let f: fn() -> int = || -> int { 3 }; return f() + lambda_1() + lambda(); }";
+        let converted = convert(Typer::type_ast(Parser::new(text).unwrap().parse().unwrap())?)?;
+        let defines: Vec<&String> = converted.iter().filter(|x| x.starts_with("define")).collect();
+        assert_eq!(defines, vec![
+            "define i32 @lambda_1() {",
+            "define i32 @lambda() {",
+            "define i32 @main() {",
+            "define private i32 @lambda.1(i8* %closure.env) {",
+        ]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn string_runtime_only_when_used() -> anyhow::Result<()> {
+        let ast = Parser::new(crate::testing::demo_programs::STR_EQ_FALSE).unwrap().parse().unwrap();
+        let converted = convert(Typer::type_ast(ast)?)?;
+        assert!(converted.iter().any(|x| x.starts_with("define private i1 @__str_eq")));
+        assert!(converted.iter().any(|x| x.starts_with("declare i32 @memcmp")));
+        assert!(!converted.iter().any(|x| x.contains("@__str_concat(")));
+
+        Ok(())
+    }
+
+    #[test]
+    fn generics() -> anyhow::Result<()> {
+        let text = "fn id<T>(x: T) -> T { return x; } fn main() -> int { let b: bool = id(true); return id(1); }";
+        let typed = Typer::type_ast(Parser::new(text).unwrap().parse().unwrap())?;
+        let converted = convert(monomorphize(typed)?)?;
+        assert_eq!(converted, vec![
+            // one copy of id for each type it is called with, where id was
+            "define i32 @id.int(i32 %_x) {",
+            "\t%x = alloca i32",
+            "\tstore i32 %_x, i32* %x",
+            "\t%x_1 = load i32, i32* %x",
+            "\tret i32 %x_1",
+            "}",
+            "define i1 @id.bool(i1 %_x) {",
+            "\t%x = alloca i1",
+            "\tstore i1 %_x, i1* %x",
+            "\t%x_2 = load i1, i1* %x",
+            "\tret i1 %x_2",
+            "}",
+            "define i32 @main() {",
+            "\t%b = alloca i1",
+            "\t%function_id.bool_1 = call i1 @id.bool(i1 1)",
+            "\tstore i1 %function_id.bool_1, i1* %b",
+            "\t%function_id.int_1 = call i32 @id.int(i32 1)",
+            "\tret i32 %function_id.int_1",
+            "}",
+        ]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn traits() -> anyhow::Result<()> {
+        let text = "trait Double { fn double(self) -> int; } impl Double for int { fn double(self) -> int { return self * 2; } } fn quad<T: Double>(x: T) -> int { return x.double(); } fn main() -> int { let n: int = 5; return quad(n) + n.double(); }";
+        let typed = Typer::type_ast(Parser::new(text).unwrap().parse().unwrap())?;
+        let converted = convert(monomorphize(typed)?)?;
+        assert_eq!(converted, vec![
+            "define i32 @Double.double.int(i32 %_self) {",
+            "\t%self = alloca i32",
+            "\tstore i32 %_self, i32* %self",
+            "\t%self_1 = load i32, i32* %self",
+            "\t%mul_1 = mul i32 %self_1, 2",
+            "\tret i32 %mul_1",
+            "}",
+            // the copy of quad for int calls int's double straight
+            "define i32 @quad.int(i32 %_x) {",
+            "\t%x = alloca i32",
+            "\tstore i32 %_x, i32* %x",
+            "\t%x_1 = load i32, i32* %x",
+            "\t%method_double_1 = call i32 @Double.double.int(i32 %x_1)",
+            "\tret i32 %method_double_1",
+            "}",
+            "define i32 @main() {",
+            "\t%n = alloca i32",
+            "\tstore i32 5, i32* %n",
+            "\t%n_1 = load i32, i32* %n",
+            "\t%function_quad.int_1 = call i32 @quad.int(i32 %n_1)",
+            "\t%n_2 = load i32, i32* %n",
+            "\t%method_double_2 = call i32 @Double.double.int(i32 %n_2)",
+            "\t%add_1 = add i32 %function_quad.int_1, %method_double_2",
+            "\tret i32 %add_1",
+            "}",
+        ]);
+
+        Ok(())
+    }
+}
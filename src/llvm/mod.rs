@@ -1,17 +1,40 @@
+use thiserror::Error;
+use crate::diagnostic::{Diagnostic, ToDiagnostic};
 use crate::llvm::ir_builder::IrBuilder;
+use crate::parser::span::Span;
 use crate::typed_ast::typed_statement::TypedStatement;
 
 mod counters;
 mod ir_builder;
 mod element;
 mod variable;
+pub mod runtime;
+
+#[derive(Debug, Error)]
+pub enum CodegenError {
+    #[error("{0} can not be compiled yet at ({1}).")]
+    Unsupported(String, Span),
+}
+
+impl ToDiagnostic for CodegenError {
+    fn to_diagnostic(&self) -> Diagnostic {
+        match self {
+            CodegenError::Unsupported(what, span) => Diagnostic::error(format!("{} can not be compiled yet", what))
+                .with_primary(*span, "not supported by the LLVM backend"),
+        }
+    }
+}
 
 pub fn convert(ast: Vec<TypedStatement>) -> anyhow::Result<Vec<String>> {
     let mut builder = IrBuilder::new();
     let mut elements = vec![];
     for statement in ast {
-        builder.convert_statement(statement, &mut elements)?;
+        let mut converted = vec![];
+        builder.convert_statement(statement, &mut converted)?;
+        // code outside any function keeps its allocas just before it
+        elements.extend(builder.take_allocas());
+        elements.extend(converted);
     }
 
-    Ok(elements.into_iter().map(|x| x.flatten()).flatten().collect())
+    Ok(builder.into_module(elements).into_iter().flat_map(|x| x.flatten()).collect())
 }
\ No newline at end of file
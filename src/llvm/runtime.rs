@@ -0,0 +1,224 @@
+use crate::llvm::element::Element;
+use crate::llvm::element::Element::Elem;
+
+/// Pieces of hand written IR that generated code can call into. Only the pieces a program
+/// actually uses are emitted, after the program itself.
+#[derive(Debug, Copy, Clone, Ord, PartialOrd, Eq, PartialEq)]
+pub enum Runtime {
+    Malloc,
+    Realloc,
+    Memcpy,
+    Memcmp,
+    StrEq,
+    StrConcat,
+    Exit,
+    Dprintf,
+    DivisionByZero,
+    IntDiv,
+    IntRem,
+    IndexOutOfBounds,
+    ListNew,
+    ListPush,
+    ListIndex,
+    Chr,
+}
+
+impl Runtime {
+    /// Whether a function of the runtime, or of the C library it calls into, is named `name`.
+    /// The program's functions share a namespace with them, so none of them can have the name.
+    pub fn reserves(name: &str) -> bool {
+        name.starts_with("__") || ["malloc", "realloc", "memcmp", "exit", "dprintf"].contains(&name)
+    }
+
+    /// Other pieces that must be emitted alongside this one.
+    pub fn dependencies(&self) -> Vec<Runtime> {
+        match self {
+            Runtime::Malloc | Runtime::Realloc | Runtime::Memcpy | Runtime::Memcmp | Runtime::Exit | Runtime::Dprintf => vec![],
+            Runtime::StrEq => vec![Runtime::Memcmp],
+            Runtime::StrConcat => vec![Runtime::Malloc, Runtime::Memcpy],
+            Runtime::DivisionByZero => vec![Runtime::Dprintf, Runtime::Exit],
+            Runtime::IntDiv | Runtime::IntRem => vec![Runtime::DivisionByZero],
+            Runtime::IndexOutOfBounds => vec![Runtime::Dprintf, Runtime::Exit],
+            Runtime::ListNew => vec![Runtime::Malloc],
+            Runtime::ListPush => vec![Runtime::Realloc],
+            Runtime::ListIndex => vec![Runtime::IndexOutOfBounds],
+            Runtime::Chr => vec![Runtime::Dprintf, Runtime::Exit],
+        }
+    }
+
+    pub fn ir(&self) -> &'static str {
+        match self {
+            Runtime::Malloc => "declare i8* @malloc(i64)",
+            Runtime::Realloc => "declare i8* @realloc(i8*, i64)",
+            Runtime::Memcpy => "declare void @llvm.memcpy.p0i8.p0i8.i64(i8*, i8*, i64, i1)",
+            Runtime::Memcmp => "declare i32 @memcmp(i8*, i8*, i64)",
+            Runtime::Exit => "declare void @exit(i32) noreturn",
+            Runtime::Dprintf => "declare i32 @dprintf(i32, i8*, ...)",
+            // prints where the division was to stderr and exits with status 101
+            Runtime::DivisionByZero => r#"@.division_by_zero = private unnamed_addr constant [30 x i8] c"division by zero at (%d, %d)\0A\00"
+define private void @__division_by_zero(i32 %row, i32 %column) noreturn {
+	%message = getelementptr inbounds [30 x i8], [30 x i8]* @.division_by_zero, i32 0, i32 0
+	call i32 (i32, i8*, ...) @dprintf(i32 2, i8* %message, i32 %row, i32 %column)
+	call void @exit(i32 101)
+	unreachable
+}"#,
+            // int_min / -1 wraps back to int_min rather than being undefined like a bare sdiv
+            Runtime::IntDiv => r"define private i32 @__int_div(i32 %a, i32 %b, i32 %row, i32 %column) {
+	%zero = icmp eq i32 %b, 0
+	br i1 %zero, label %fail, label %nonzero
+fail:
+	call void @__division_by_zero(i32 %row, i32 %column)
+	unreachable
+nonzero:
+	%min = icmp eq i32 %a, -2147483648
+	%negative_one = icmp eq i32 %b, -1
+	%overflow = and i1 %min, %negative_one
+	br i1 %overflow, label %wrap, label %divide
+wrap:
+	ret i32 -2147483648
+divide:
+	%result = sdiv i32 %a, %b
+	ret i32 %result
+}",
+            // the remainder takes the sign of the dividend, and int_min % -1 is 0
+            Runtime::IntRem => r"define private i32 @__int_rem(i32 %a, i32 %b, i32 %row, i32 %column) {
+	%zero = icmp eq i32 %b, 0
+	br i1 %zero, label %fail, label %nonzero
+fail:
+	call void @__division_by_zero(i32 %row, i32 %column)
+	unreachable
+nonzero:
+	%negative_one = icmp eq i32 %b, -1
+	br i1 %negative_one, label %wrap, label %divide
+wrap:
+	ret i32 0
+divide:
+	%result = srem i32 %a, %b
+	ret i32 %result
+}",
+            // prints the bad index and where it was used to stderr and exits with status 101
+            Runtime::IndexOutOfBounds => r#"@.index_out_of_bounds = private unnamed_addr constant [63 x i8] c"index %d is out of bounds for a list of length %d at (%d, %d)\0A\00"
+define private void @__index_out_of_bounds(i32 %index, i32 %len, i32 %row, i32 %column) noreturn {
+	%message = getelementptr inbounds [63 x i8], [63 x i8]* @.index_out_of_bounds, i32 0, i32 0
+	call i32 (i32, i8*, ...) @dprintf(i32 2, i8* %message, i32 %index, i32 %len, i32 %row, i32 %column)
+	call void @exit(i32 101)
+	unreachable
+}"#,
+            // a list is a pointer to its data, length and capacity, which live on the heap so that
+            // every copy of the list sees what is pushed to it
+            Runtime::ListNew => r"define private { i8*, i32, i32 }* @__list_new(i64 %size, i32 %capacity) {
+	%header = call i8* @malloc(i64 16)
+	%list = bitcast i8* %header to { i8*, i32, i32 }*
+	%wide_capacity = sext i32 %capacity to i64
+	%bytes = mul i64 %size, %wide_capacity
+	%data = call i8* @malloc(i64 %bytes)
+	%with_data = insertvalue { i8*, i32, i32 } undef, i8* %data, 0
+	%with_len = insertvalue { i8*, i32, i32 } %with_data, i32 0, 1
+	%full = insertvalue { i8*, i32, i32 } %with_len, i32 %capacity, 2
+	store { i8*, i32, i32 } %full, { i8*, i32, i32 }* %list
+	ret { i8*, i32, i32 }* %list
+}",
+            // makes room for one more element of `size` bytes, doubling the capacity when it is
+            // used up, and returns where to put it
+            Runtime::ListPush => r"define private i8* @__list_push({ i8*, i32, i32 }* %list, i64 %size) {
+entry:
+	%header = load { i8*, i32, i32 }, { i8*, i32, i32 }* %list
+	%data = extractvalue { i8*, i32, i32 } %header, 0
+	%len = extractvalue { i8*, i32, i32 } %header, 1
+	%capacity = extractvalue { i8*, i32, i32 } %header, 2
+	%full = icmp eq i32 %len, %capacity
+	br i1 %full, label %grow, label %append
+grow:
+	%empty = icmp eq i32 %capacity, 0
+	%doubled = mul i32 %capacity, 2
+	%new_capacity = select i1 %empty, i32 4, i32 %doubled
+	%wide_capacity = sext i32 %new_capacity to i64
+	%bytes = mul i64 %size, %wide_capacity
+	%grown = call i8* @realloc(i8* %data, i64 %bytes)
+	br label %append
+append:
+	%final_data = phi i8* [ %data, %entry ], [ %grown, %grow ]
+	%final_capacity = phi i32 [ %capacity, %entry ], [ %new_capacity, %grow ]
+	%new_len = add i32 %len, 1
+	%with_data = insertvalue { i8*, i32, i32 } undef, i8* %final_data, 0
+	%with_len = insertvalue { i8*, i32, i32 } %with_data, i32 %new_len, 1
+	%updated = insertvalue { i8*, i32, i32 } %with_len, i32 %final_capacity, 2
+	store { i8*, i32, i32 } %updated, { i8*, i32, i32 }* %list
+	%wide_len = sext i32 %len to i64
+	%offset = mul i64 %size, %wide_len
+	%slot = getelementptr i8, i8* %final_data, i64 %offset
+	ret i8* %slot
+}",
+            // where the element at `index` lives, after checking there is one
+            Runtime::ListIndex => r"define private i8* @__list_index({ i8*, i32, i32 }* %list, i32 %index, i64 %size, i32 %row, i32 %column) {
+	%header = load { i8*, i32, i32 }, { i8*, i32, i32 }* %list
+	%len = extractvalue { i8*, i32, i32 } %header, 1
+	%in_bounds = icmp ult i32 %index, %len
+	br i1 %in_bounds, label %found, label %fail
+fail:
+	call void @__index_out_of_bounds(i32 %index, i32 %len, i32 %row, i32 %column)
+	unreachable
+found:
+	%data = extractvalue { i8*, i32, i32 } %header, 0
+	%wide_index = zext i32 %index to i64
+	%offset = mul i64 %size, %wide_index
+	%slot = getelementptr i8, i8* %data, i64 %offset
+	ret i8* %slot
+}",
+            // the int as a char, or if it isn't a unicode scalar value, prints it and where `chr`
+            // was called to stderr and exits with status 101
+            Runtime::Chr => r#"@.invalid_char = private unnamed_addr constant [46 x i8] c"%d is not a unicode scalar value at (%d, %d)\0A\00"
+define private i32 @__chr(i32 %value, i32 %row, i32 %column) {
+	%in_range = icmp ule i32 %value, 1114111
+	%below_surrogates = icmp ult i32 %value, 55296
+	%above_surrogates = icmp ugt i32 %value, 57343
+	%not_surrogate = or i1 %below_surrogates, %above_surrogates
+	%scalar = and i1 %in_range, %not_surrogate
+	br i1 %scalar, label %valid, label %fail
+fail:
+	%message = getelementptr inbounds [46 x i8], [46 x i8]* @.invalid_char, i32 0, i32 0
+	call i32 (i32, i8*, ...) @dprintf(i32 2, i8* %message, i32 %value, i32 %row, i32 %column)
+	call void @exit(i32 101)
+	unreachable
+valid:
+	ret i32 %value
+}"#,
+            Runtime::StrEq => r"define private i1 @__str_eq({ i32, i8* } %a, { i32, i8* } %b) {
+	%a_len = extractvalue { i32, i8* } %a, 0
+	%b_len = extractvalue { i32, i8* } %b, 0
+	%same_len = icmp eq i32 %a_len, %b_len
+	br i1 %same_len, label %compare, label %different
+compare:
+	%a_ptr = extractvalue { i32, i8* } %a, 1
+	%b_ptr = extractvalue { i32, i8* } %b, 1
+	%len = zext i32 %a_len to i64
+	%cmp = call i32 @memcmp(i8* %a_ptr, i8* %b_ptr, i64 %len)
+	%equal = icmp eq i32 %cmp, 0
+	ret i1 %equal
+different:
+	ret i1 0
+}",
+            Runtime::StrConcat => r"define private { i32, i8* } @__str_concat({ i32, i8* } %a, { i32, i8* } %b) {
+	%a_len = extractvalue { i32, i8* } %a, 0
+	%b_len = extractvalue { i32, i8* } %b, 0
+	%a_ptr = extractvalue { i32, i8* } %a, 1
+	%b_ptr = extractvalue { i32, i8* } %b, 1
+	%len = add i32 %a_len, %b_len
+	%size = zext i32 %len to i64
+	%a_size = zext i32 %a_len to i64
+	%b_size = zext i32 %b_len to i64
+	%ptr = call i8* @malloc(i64 %size)
+	call void @llvm.memcpy.p0i8.p0i8.i64(i8* %ptr, i8* %a_ptr, i64 %a_size, i1 0)
+	%tail = getelementptr i8, i8* %ptr, i64 %a_size
+	call void @llvm.memcpy.p0i8.p0i8.i64(i8* %tail, i8* %b_ptr, i64 %b_size, i1 0)
+	%with_len = insertvalue { i32, i8* } undef, i32 %len, 0
+	%result = insertvalue { i32, i8* } %with_len, i8* %ptr, 1
+	ret { i32, i8* } %result
+}",
+        }
+    }
+
+    pub fn elements(&self) -> Vec<Element> {
+        self.ir().lines().map(|x| Elem(x.to_string())).collect()
+    }
+}
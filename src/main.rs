@@ -1,23 +1,31 @@
+// errors carry the tokens and types their diagnostics point at, and a compile stops at the
+// first few, so there is nothing to gain from boxing them
+#![allow(clippy::result_large_err)]
+
+use std::io::IsTerminal;
 use std::path::Path;
 use std::process::Output;
 use anyhow::{Context, Error};
 use crate::llvm::convert;
+use crate::monomorphizer::monomorphize;
 use crate::parser::Parser;
 use crate::typer::Typer;
 
 mod ast;
+mod diagnostic;
 mod parser;
 mod testing;
 mod interpreter;
 mod llvm;
+mod monomorphizer;
 mod typer;
 mod typed_ast;
 
 fn ir_text(text: impl AsRef<str>) -> anyhow::Result<Vec<String>> {
     let text = text.as_ref();
-    let parsed = Parser::new(text).parse()?;
+    let parsed = Parser::new(text)?.parse()?;
     let typed = Typer::type_ast(parsed)?;
-    return Ok(convert(typed)?)
+    convert(monomorphize(typed)?)
 }
 
 /// Take a program as text in memory and write it to a .ll
@@ -58,7 +66,13 @@ fn main() -> anyhow::Result<()> {
     };
 
     let text = std::fs::read_to_string(&args[1])?;
-    write_ll(text, target_ll)?;
+    if let Err(error) = write_ll(&text, target_ll) {
+        let colour = std::io::stderr().is_terminal() && std::env::var_os("NO_COLOR").is_none();
+        for diagnostic in diagnostic::from_error(&error) {
+            eprintln!("{}", diagnostic.render(&text, colour));
+        }
+        return Err(Error::msg("Could not compile because of the errors above."));
+    }
     compile(target_ll)?;
 
     println!("Done!");
@@ -73,6 +87,8 @@ mod tests {
     fn run_test(path: impl AsRef<Path>, text: impl AsRef<str>) -> anyhow::Result<i32> {
         let path = path.as_ref();
         write_ll(text, path)?;
+        // a stale executable would make a failed compile look like a pass
+        let _ = std::fs::remove_file(path.with_extension("exe"));
 
         let compile_out = path.with_extension("compile_out");
         let compile_err = path.with_extension("compile_err");
@@ -295,4 +311,193 @@ mod tests {
         assert_eq!(run_test(path, WHILE_IF)?, 42);
         Ok(())
     }
+
+    #[test]
+    fn str_len() -> anyhow::Result<()> {
+        let path = ".\\testing\\str_len.ll";
+        assert_eq!(run_test(path, STR_LEN)?, 42);
+        Ok(())
+    }
+
+    #[test]
+    fn str_eq() -> anyhow::Result<()> {
+        let path = ".\\testing\\str_eq.ll";
+        assert_ne!(run_test(path, STR_EQ)?, 0);
+        Ok(())
+    }
+
+    #[test]
+    fn str_eq_false() -> anyhow::Result<()> {
+        let path = ".\\testing\\str_eq_false.ll";
+        assert_eq!(run_test(path, STR_EQ_FALSE)?, 0);
+        Ok(())
+    }
+
+    #[test]
+    fn int_bases() -> anyhow::Result<()> {
+        let path = ".\\testing\\int_bases.ll";
+        assert_eq!(run_test(path, INT_BASES)?, 42);
+        Ok(())
+    }
+
+    #[test]
+    fn arithmetic() -> anyhow::Result<()> {
+        let path = ".\\testing\\arithmetic.ll";
+        assert_eq!(run_test(path, ARITHMETIC)?, 42);
+        Ok(())
+    }
+
+    #[test]
+    fn division_by_zero() -> anyhow::Result<()> {
+        let path = ".\\testing\\division_by_zero.ll";
+        assert_eq!(run_test(path, DIVISION_BY_ZERO)?, 101);
+        Ok(())
+    }
+
+    #[test]
+    fn for_range() -> anyhow::Result<()> {
+        let path = ".\\testing\\for_range.ll";
+        assert_eq!(run_test(path, FOR_RANGE)?, 42);
+        Ok(())
+    }
+
+    #[test]
+    fn redeclared_locals() -> anyhow::Result<()> {
+        let path = ".\\testing\\redeclared_locals.ll";
+        assert_eq!(run_test(path, REDECLARED_LOCALS)?, 42);
+        Ok(())
+    }
+
+    #[test]
+    fn nested_loops() -> anyhow::Result<()> {
+        let path = ".\\testing\\nested_loops.ll";
+        assert_eq!(run_test(path, NESTED_LOOPS)?, 42);
+        Ok(())
+    }
+
+    #[test]
+    fn loop_control() -> anyhow::Result<()> {
+        let path = ".\\testing\\loop_control.ll";
+        assert_eq!(run_test(path, LOOP_CONTROL)?, 42);
+        Ok(())
+    }
+
+    #[test]
+    fn compound_assignment() -> anyhow::Result<()> {
+        let path = ".\\testing\\compound_assignment.ll";
+        assert_eq!(run_test(path, COMPOUND_ASSIGNMENT)?, 42);
+        Ok(())
+    }
+
+    #[test]
+    fn structs() -> anyhow::Result<()> {
+        let path = ".\\testing\\structs.ll";
+        assert_eq!(run_test(path, STRUCTS)?, 42);
+        Ok(())
+    }
+
+    #[test]
+    fn enums() -> anyhow::Result<()> {
+        let path = ".\\testing\\enums.ll";
+        assert_eq!(run_test(path, ENUMS)?, 42);
+        Ok(())
+    }
+
+    #[test]
+    fn enums_in_loop() -> anyhow::Result<()> {
+        let path = ".\\testing\\enums_in_loop.ll";
+        assert_eq!(run_test(path, ENUMS_IN_LOOP)?, 42);
+        Ok(())
+    }
+
+    #[test]
+    fn tuples() -> anyhow::Result<()> {
+        let path = ".\\testing\\tuples.ll";
+        assert_eq!(run_test(path, TUPLES)?, 42);
+        Ok(())
+    }
+
+    #[test]
+    fn lists() -> anyhow::Result<()> {
+        let path = ".\\testing\\lists.ll";
+        assert_eq!(run_test(path, LISTS)?, 42);
+        Ok(())
+    }
+
+    #[test]
+    fn index_out_of_bounds() -> anyhow::Result<()> {
+        let path = ".\\testing\\index_out_of_bounds.ll";
+        assert_eq!(run_test(path, INDEX_OUT_OF_BOUNDS)?, 101);
+        Ok(())
+    }
+
+    #[test]
+    fn index_compound_assignment() -> anyhow::Result<()> {
+        let path = ".\\testing\\index_compound_assignment.ll";
+        assert_eq!(run_test(path, INDEX_COMPOUND_ASSIGNMENT)?, 42);
+        Ok(())
+    }
+
+    #[test]
+    fn closures() -> anyhow::Result<()> {
+        let path = ".\\testing\\closures.ll";
+        assert_eq!(run_test(path, CLOSURES)?, 42);
+        Ok(())
+    }
+
+    #[test]
+    fn generics() -> anyhow::Result<()> {
+        let path = ".\\testing\\generics.ll";
+        assert_eq!(run_test(path, GENERICS)?, 42);
+        Ok(())
+    }
+
+    #[test]
+    fn traits() -> anyhow::Result<()> {
+        let path = ".\\testing\\traits.ll";
+        assert_eq!(run_test(path, TRAITS)?, 42);
+        Ok(())
+    }
+
+    #[test]
+    fn shadowing() -> anyhow::Result<()> {
+        let path = ".\\testing\\shadowing.ll";
+        assert_eq!(run_test(path, SHADOWING)?, 42);
+        Ok(())
+    }
+
+    #[test]
+    fn comparisons() -> anyhow::Result<()> {
+        let path = ".\\testing\\comparisons.ll";
+        assert_eq!(run_test(path, COMPARISONS)?, 42);
+        Ok(())
+    }
+
+    #[test]
+    fn float_cmp() -> anyhow::Result<()> {
+        let path = ".\\testing\\float_cmp.ll";
+        assert_ne!(run_test(path, FLOAT_CMP)?, 0);
+        Ok(())
+    }
+
+    #[test]
+    fn char_shift() -> anyhow::Result<()> {
+        let path = ".\\testing\\char_shift.ll";
+        assert_eq!(run_test(path, CHAR_SHIFT)?, 42);
+        Ok(())
+    }
+
+    #[test]
+    fn invalid_char() -> anyhow::Result<()> {
+        let path = ".\\testing\\invalid_char.ll";
+        assert_eq!(run_test(path, INVALID_CHAR)?, 101);
+        Ok(())
+    }
+
+    #[test]
+    fn char_cmp() -> anyhow::Result<()> {
+        let path = ".\\testing\\char_cmp.ll";
+        assert_ne!(run_test(path, CHAR_CMP)?, 0);
+        Ok(())
+    }
 }
\ No newline at end of file
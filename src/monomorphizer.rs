@@ -0,0 +1,418 @@
+use std::collections::{HashMap, HashSet};
+use itertools::Itertools;
+use thiserror::Error;
+use crate::diagnostic::{Diagnostic, ToDiagnostic};
+use crate::parser::span::Span;
+use crate::parser::token::Token;
+use crate::typed_ast::r#type::Type;
+use crate::typed_ast::typed_expression::TypedExpression;
+use crate::typed_ast::typed_pattern::{TypedMatchArm, TypedPattern};
+use crate::typed_ast::typed_statement::{FunctionDefinition, TypedForIterable, TypedStatement};
+use crate::typed_ast::TypedBlock;
+
+/// How many copies deep one generic function can ask for another. Only a function that calls
+/// itself with ever bigger types, like `f((x, x))` inside `f<T>(x: T)`, gets this far, and the
+/// types in it double with every step.
+const INSTANTIATION_LIMIT: usize = 16;
+
+#[derive(Debug, Error)]
+pub enum MonomorphizeError {
+    #[error("Copying {0} at ({1}) needs copies more than {INSTANTIATION_LIMIT} deep.")]
+    TooDeep(String, Span),
+}
+
+impl ToDiagnostic for MonomorphizeError {
+    fn to_diagnostic(&self) -> Diagnostic {
+        match self {
+            MonomorphizeError::TooDeep(name, span) => Diagnostic::error(format!("`{}` asks for copies of itself without end", name))
+                .with_primary(*span, "every copy calls one for bigger types")
+                .with_note(format!("copies of generic functions can only be nested {} deep", INSTANTIATION_LIMIT))
+                .with_help("a generic function can't call itself with a type built from its own type parameters"),
+        }
+    }
+}
+
+/// Replace every generic function with a copy for each list of types it is called with, so
+/// that nothing after this has to know about type parameters. A copy of `max<T>` called with
+/// an int is named `max.int`, and a generic function that is never called disappears. Method
+/// calls in a copy end up with a receiver of a real type, which says which impl they call.
+/// A function whose copies keep asking for copies for bigger types is an error.
+pub fn monomorphize(statements: Vec<TypedStatement>) -> Result<Vec<TypedStatement>, MonomorphizeError> {
+    let mut monomorphizer = Monomorphizer {
+        generics: HashMap::new(),
+        seen: HashSet::new(),
+        pending: vec![],
+        depth: 0,
+    };
+
+    // the generic functions are taken out first so calls to them before their definition work
+    let statements = statements.into_iter()
+        .map(|statement| match statement {
+            TypedStatement::FunctionDefinitionStatement(def) if !def.type_params.is_empty() => {
+                let name = def.name.lexeme().to_string();
+                monomorphizer.generics.insert(name.clone(), def);
+                Err(name)
+            }
+            statement => Ok(statement)
+        })
+        .collect_vec();
+    let statements = statements.into_iter()
+        .map(|x| x.map(|statement| monomorphizer.statement(statement, &HashMap::new())))
+        .collect_vec();
+
+    let mut instances: HashMap<String, Vec<TypedStatement>> = HashMap::new();
+    while let Some(Pending { name, type_arguments, depth, span }) = monomorphizer.pending.pop() {
+        if depth > INSTANTIATION_LIMIT {
+            return Err(MonomorphizeError::TooDeep(name, span))
+        }
+        monomorphizer.depth = depth;
+        let instance = monomorphizer.instantiate(&name, &type_arguments);
+        instances.entry(name).or_default().push(TypedStatement::FunctionDefinitionStatement(instance));
+    }
+
+    // each copy goes where the generic function was
+    Ok(statements.into_iter()
+        .flat_map(|x| match x {
+            Ok(statement) => vec![statement],
+            Err(name) => instances.remove(&name).unwrap_or_default(),
+        })
+        .collect())
+}
+
+/// The name of the copy of `name` for `type_arguments`, like `first.list.int`. Lists, tuples
+/// and functions are written before what they hold, with how many things that is, so that
+/// different types never give the same name.
+pub fn mangle(name: &str, type_arguments: &[Type]) -> String {
+    fn mangle_type(type_: &Type) -> String {
+        match type_ {
+            Type::List(element) => format!("list.{}", mangle_type(element)),
+            Type::Tuple(elements) => format!("tuple{}.{}", elements.len(), elements.iter().map(mangle_type).join(".")),
+            Type::Function(params, return_type) => {
+                let types = params.iter().chain([return_type.as_ref()]).map(mangle_type).join(".");
+                format!("fn{}.{}", params.len(), types)
+            }
+            Type::Param(_) | Type::Named(_) => unreachable!("only concrete types are mangled"),
+            type_ => type_.to_string()
+        }
+    }
+
+    [name.to_string()].into_iter().chain(type_arguments.iter().map(mangle_type)).join(".")
+}
+
+/// The name of `method` in the impl of `trait_` for `type_`, like `Show.show.Point`.
+pub fn method_name(trait_: &str, method: &str, type_: &Type) -> String {
+    mangle(&format!("{}.{}", trait_, method), std::slice::from_ref(type_))
+}
+
+struct Monomorphizer {
+    generics: HashMap<String, FunctionDefinition>,
+    /// The mangled names of every copy asked for so far.
+    seen: HashSet<String>,
+    /// Copies that have been asked for but not made yet.
+    pending: Vec<Pending>,
+    /// How many copies deep the one being made is, where the program outside them is 0.
+    depth: usize,
+}
+
+/// A copy of the generic function `name` for `type_arguments`, asked for by the call at `span`
+/// from a copy `depth - 1` deep.
+struct Pending {
+    name: String,
+    type_arguments: Vec<Type>,
+    depth: usize,
+    span: Span,
+}
+
+impl Monomorphizer {
+    /// The copy of the generic function `name` with its type parameters bound to `type_arguments`.
+    fn instantiate(&mut self, name: &str, type_arguments: &[Type]) -> FunctionDefinition {
+        let def = self.generics[name].clone();
+        let bindings: HashMap<String, Type> = def.type_params.iter()
+            .map(|x| x.name.lexeme().to_string())
+            .zip(type_arguments.iter().cloned())
+            .collect();
+
+        FunctionDefinition {
+            name: Token::new(def.name.kind(), def.name.span(), mangle(name, type_arguments)),
+            type_params: vec![],
+            type_: def.type_.substitute(&bindings),
+            arg_list: def.arg_list.into_iter().map(|(name, type_)| (name, type_.substitute(&bindings))).collect(),
+            body: self.block(def.body, &bindings),
+        }
+    }
+
+    fn block(&mut self, block: TypedBlock, bindings: &HashMap<String, Type>) -> TypedBlock {
+        TypedBlock {
+            body: block.body.into_iter().map(|x| self.statement(x, bindings)).collect(),
+            type_: block.type_.substitute(bindings),
+        }
+    }
+
+    fn statement(&mut self, statement: TypedStatement, bindings: &HashMap<String, Type>) -> TypedStatement {
+        type S = TypedStatement;
+        match statement {
+            S::VariableDeclaration { name, type_, value } => S::VariableDeclaration {
+                name,
+                type_: type_.substitute(bindings),
+                value: self.expression(value, bindings),
+            },
+            S::TupleDeclaration { names, type_, value } => S::TupleDeclaration {
+                names,
+                type_: type_.substitute(bindings),
+                value: self.expression(value, bindings),
+            },
+            S::FunctionDefinitionStatement(def) => S::FunctionDefinitionStatement(FunctionDefinition {
+                body: self.block(def.body, bindings),
+                ..def
+            }),
+            S::Impl { trait_, type_, methods } => S::Impl {
+                trait_,
+                type_,
+                methods: methods.into_iter()
+                    .map(|def| FunctionDefinition { body: self.block(def.body, bindings), ..def })
+                    .collect(),
+            },
+            S::StructDefinition { .. } | S::EnumDefinition { .. } | S::TraitDefinition { .. } | S::Break(_) | S::Continue(_) => statement,
+            S::Assignment { to, value } => S::Assignment { to, value: self.expression(value, bindings) },
+            S::FieldAssignment { to, type_, fields, value } => S::FieldAssignment {
+                to,
+                type_: type_.substitute(bindings),
+                fields,
+                value: self.expression(value, bindings),
+            },
+            S::IndexAssignment { list, index, operator, value, span } => S::IndexAssignment {
+                list: Box::new(self.expression(*list, bindings)),
+                index: Box::new(self.expression(*index, bindings)),
+                operator,
+                value: self.expression(value, bindings),
+                span,
+            },
+            S::While { label, condition, body } => S::While {
+                label,
+                condition: self.expression(condition, bindings),
+                body: self.block(body, bindings),
+            },
+            S::For { label, variable, iterable, body } => S::For {
+                label,
+                variable,
+                iterable: match iterable {
+                    TypedForIterable::Range { start, end, inclusive } => TypedForIterable::Range {
+                        start: Box::new(self.expression(*start, bindings)),
+                        end: Box::new(self.expression(*end, bindings)),
+                        inclusive,
+                    },
+                    TypedForIterable::List(list) => TypedForIterable::List(Box::new(self.expression(*list, bindings))),
+                },
+                body: self.block(body, bindings),
+            },
+            S::Return(e) => S::Return(self.expression(e, bindings)),
+            S::Expression(e) => S::Expression(self.expression(e, bindings)),
+        }
+    }
+
+    fn expressions(&mut self, expressions: Vec<TypedExpression>, bindings: &HashMap<String, Type>) -> Vec<TypedExpression> {
+        expressions.into_iter().map(|x| self.expression(x, bindings)).collect()
+    }
+
+    fn boxed(&mut self, expression: Box<TypedExpression>, bindings: &HashMap<String, Type>) -> Box<TypedExpression> {
+        Box::new(self.expression(*expression, bindings))
+    }
+
+    fn expression(&mut self, expression: TypedExpression, bindings: &HashMap<String, Type>) -> TypedExpression {
+        type E = TypedExpression;
+        match expression {
+            E::If { condition, true_block, else_block, span } => E::If {
+                condition: self.boxed(condition, bindings),
+                true_block: self.block(true_block, bindings),
+                else_block: else_block.map(|x| self.block(x, bindings)),
+                span,
+            },
+            E::BinaryOperation { lhs, operator, rhs, type_, span } => E::BinaryOperation {
+                lhs: self.boxed(lhs, bindings),
+                operator,
+                rhs: self.boxed(rhs, bindings),
+                type_: type_.substitute(bindings),
+                span,
+            },
+            E::FunctionCall { name, arguments, type_arguments, type_, span } => {
+                let arguments = self.expressions(arguments, bindings);
+                let type_ = type_.substitute(bindings);
+                if type_arguments.is_empty() {
+                    return E::FunctionCall { name, arguments, type_arguments, type_, span }
+                }
+
+                let type_arguments = type_arguments.iter().map(|x| x.substitute(bindings)).collect_vec();
+                let mangled = mangle(name.lexeme(), &type_arguments);
+                if self.seen.insert(mangled.clone()) {
+                    self.pending.push(Pending {
+                        name: name.lexeme().to_string(),
+                        type_arguments,
+                        depth: self.depth + 1,
+                        span,
+                    });
+                }
+                E::FunctionCall {
+                    name: Token::new(name.kind(), name.span(), mangled),
+                    arguments,
+                    type_arguments: vec![],
+                    type_,
+                    span,
+                }
+            }
+            E::UnaryOperation { operator, rhs, span } => E::UnaryOperation {
+                operator,
+                rhs: self.boxed(rhs, bindings),
+                span,
+            },
+            E::Int(_) | E::Float(_) | E::Bool(..) | E::Str(_) | E::Char(_) => expression,
+            E::List(elements, type_, span) => E::List(self.expressions(elements, bindings), type_.substitute(bindings), span),
+            E::Tuple(elements, type_, span) => E::Tuple(self.expressions(elements, bindings), type_.substitute(bindings), span),
+            E::StructLiteral { fields, type_, span } => E::StructLiteral {
+                fields: fields.into_iter().map(|(index, x)| (index, self.expression(x, bindings))).collect(),
+                type_,
+                span,
+            },
+            E::EnumVariant { type_, variant, tag, payload, span } => E::EnumVariant {
+                type_,
+                variant,
+                tag,
+                payload: self.expressions(payload, bindings),
+                span,
+            },
+            E::Match { scrutinee, arms, type_, span } => E::Match {
+                scrutinee: self.boxed(scrutinee, bindings),
+                arms: arms.into_iter()
+                    .map(|arm| TypedMatchArm {
+                        pattern: Self::pattern(arm.pattern, bindings),
+                        body: self.block(arm.body, bindings),
+                        span: arm.span,
+                    })
+                    .collect(),
+                type_: type_.substitute(bindings),
+                span,
+            },
+            E::FieldAccess { of, index, type_, span } => E::FieldAccess {
+                of: self.boxed(of, bindings),
+                index,
+                type_: type_.substitute(bindings),
+                span,
+            },
+            E::Index { of, index, type_, span } => E::Index {
+                of: self.boxed(of, bindings),
+                index: self.boxed(index, bindings),
+                type_: type_.substitute(bindings),
+                span,
+            },
+            E::Lambda { params, captures, body, type_, span } => E::Lambda {
+                params: params.into_iter().map(|(name, x)| (name, x.substitute(bindings))).collect(),
+                captures: captures.into_iter().map(|(name, x)| (name, x.substitute(bindings))).collect(),
+                body: self.block(body, bindings),
+                type_: type_.substitute(bindings),
+                span,
+            },
+            E::Call { callee, arguments, type_, span } => E::Call {
+                callee: self.boxed(callee, bindings),
+                arguments: self.expressions(arguments, bindings),
+                type_: type_.substitute(bindings),
+                span,
+            },
+            E::MethodCall { receiver, trait_, method, arguments, type_, span } => E::MethodCall {
+                receiver: self.boxed(receiver, bindings),
+                trait_,
+                method,
+                arguments: self.expressions(arguments, bindings),
+                type_: type_.substitute(bindings),
+                span,
+            },
+            E::Function(name, type_) => E::Function(name, type_.substitute(bindings)),
+            E::Name(name, type_) => E::Name(name, type_.substitute(bindings)),
+        }
+    }
+
+    fn pattern(pattern: TypedPattern, bindings: &HashMap<String, Type>) -> TypedPattern {
+        match pattern {
+            TypedPattern::Binding(name, type_) => TypedPattern::Binding(name, type_.substitute(bindings)),
+            TypedPattern::Variant { type_, variant, tag, fields } => TypedPattern::Variant {
+                type_,
+                variant,
+                tag,
+                fields: fields.into_iter().map(|x| Self::pattern(x, bindings)).collect(),
+            },
+            pattern => pattern
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parser::Parser;
+    use crate::testing::s_expr::SExpr;
+    use crate::testing::to_s_expr::ToSExpr;
+    use crate::typer::Typer;
+    use super::*;
+
+    fn monomorphized(text: &str) -> Vec<SExpr<String>> {
+        let parsed = Parser::new(text).unwrap().parse().unwrap();
+        let typed = Typer::type_ast(parsed).unwrap();
+        monomorphize(typed).unwrap().into_iter().map(|x| x.to_s_expr()).collect()
+    }
+
+    #[test]
+    fn mangling() {
+        assert_eq!(mangle("id", &[Type::Int]), "id.int");
+        assert_eq!(mangle("pair", &[Type::List(Box::new(Type::Bool)), Type::Struct(String::from("Point"))]), "pair.list.bool.Point");
+        assert_eq!(mangle("f", &[Type::Tuple(vec![Type::Int, Type::Char])]), "f.tuple2.int.char");
+        assert_eq!(
+            mangle("apply", &[Type::Function(vec![Type::Int], Box::new(Type::Unit))]),
+            "apply.fn1.int.unit"
+        );
+        assert_eq!(method_name("Show", "show", &Type::List(Box::new(Type::Int))), "Show.show.list.int");
+    }
+
+    #[test]
+    fn instantiations() {
+        let program = monomorphized("fn id<T>(x: T) -> T { return x; }\
+            fn twice<U>(x: U) -> (U, U) { return (id(x), id(x)); }\
+            let a: int = id(1);\
+            let b: (bool, bool) = twice(true);\
+            let c: int = id(2);");
+
+        let names = program.iter()
+            .filter_map(|x| match x {
+                SExpr::Function(kind, args) if kind == "function_define" => Some(args[0].clone()),
+                _ => None
+            })
+            .collect_vec();
+        assert_eq!(names.len(), 3);
+        for name in ["id.int", "id.bool", "twice.bool"] {
+            assert!(names.contains(&SExpr::Value(String::from(name))), "{} is missing", name);
+        }
+        // the copies of id take the place of the generic one, before twice
+        assert!(matches!(&program[2], SExpr::Function(_, args) if args[0] == SExpr::Value(String::from("twice.bool"))));
+    }
+
+    #[test]
+    fn uncalled_generics_are_dropped() {
+        let program = monomorphized("fn id<T>(x: T) -> T { return x; } let a: int = 1;");
+        assert_eq!(program.len(), 1);
+    }
+
+    #[test]
+    fn polymorphic_recursion() {
+        let parsed = Parser::new("fn f<T>(x: T, n: int) -> int {\
+                if n == 0 { return 0; }\
+                return f((x, x), n - 1);\
+            }\
+            let a: int = f(1, 3);").unwrap().parse().unwrap();
+        let error = monomorphize(Typer::type_ast(parsed).unwrap()).unwrap_err();
+        assert!(matches!(error, MonomorphizeError::TooDeep(ref name, _) if name == "f"), "{}", error);
+
+        // a generic function calling itself with the same types is copied once
+        let program = monomorphized("fn count<T>(x: T, n: int) -> int {\
+                if n == 0 { return 0; }\
+                return 1 + count(x, n - 1);\
+            }\
+            let a: int = count(true, 3);");
+        assert_eq!(program.len(), 2);
+    }
+}
@@ -0,0 +1,286 @@
+//! The lexer as it was before it streamed tokens through dispatch tables: the `run_lexer` of
+//! the original lexer, grown to cover comments, strings and integer literals in every radix.
+//! It is kept only to benchmark against, and counts its errors rather than reporting them.
+
+use crate::parser::lex_table::{KEYWORD_TABLE, LEX_TABLE};
+use crate::parser::location::Location;
+use crate::parser::span::Span;
+use crate::parser::token::Token;
+use crate::parser::token_kind::TokenKind;
+use crate::parser::token_kind::TokenKind::*;
+
+pub struct BaselineLexer {
+    text: String,
+    index: usize,
+    tokens: Vec<Token>,
+    errors: usize,
+    row: usize,
+    column: usize,
+}
+
+impl BaselineLexer {
+    pub fn new(text: impl AsRef<str>) -> Self {
+        Self {
+            text: text.as_ref().to_string(),
+            index: 0,
+            tokens: vec![],
+            errors: 0,
+            row: 1,
+            column: 1,
+        }
+    }
+    fn take_while(&self, predicate: fn(char)->bool) -> Option<String> {
+        let mut accumulator = String::from("");
+        for c in self.text[self.index..].chars() {
+            if predicate(c) {
+                accumulator.push(c)
+            }
+            else {
+                break;
+            }
+        }
+
+        if accumulator.is_empty() { None } else { Some(accumulator) }
+    }
+
+    fn take_name(&self) -> Option<String> {
+        let mut chars = self.text[self.index..].chars();
+        let first_char = chars.next().unwrap();
+        if first_char != '_' && !first_char.is_alphabetic() {
+            return None;
+        }
+
+        let mut name = String::from(first_char);
+        name.extend(chars.take_while(|x| *x == '_' || x.is_alphanumeric()));
+        Some(name)
+    }
+
+    fn location(&self) -> Location {
+        Location::new(self.index, self.row, self.column)
+    }
+
+    fn accept_token(&mut self, kind: TokenKind, lexeme: impl AsRef<str>) {
+        let lexeme = lexeme.as_ref().to_string();
+        let start = self.location();
+        self.column += lexeme.chars().count();
+        self.index += lexeme.len();
+
+        self.tokens.push(Token::new(
+            kind,
+            Span::new(start, self.location()),
+            lexeme
+        ))
+    }
+
+    /// Step over the character at the current index, keeping row and column up to date.
+    fn skip_char(&mut self) {
+        let c = self.text[self.index..].chars().next().unwrap();
+        if c == '\n' {
+            self.row += 1;
+            self.column = 1;
+        } else {
+            self.column += 1;
+        }
+        self.index += c.len_utf8();
+    }
+
+    fn skip_line_comment(&mut self) {
+        // leave the newline itself for run_lexer so it is counted like any other
+        while self.index < self.text.len() && !self.text[self.index..].starts_with('\n') {
+            self.skip_char();
+        }
+    }
+
+    /// Block comments nest, so `/* a /* b */ c */` is a single comment.
+    fn skip_block_comment(&mut self) {
+        let mut depth = 0;
+        while self.index < self.text.len() {
+            if self.text[self.index..].starts_with("/*") {
+                depth += 1;
+                self.skip_char();
+                self.skip_char();
+            } else if self.text[self.index..].starts_with("*/") {
+                depth -= 1;
+                self.skip_char();
+                self.skip_char();
+                if depth == 0 {
+                    return
+                }
+            } else {
+                self.skip_char();
+            }
+        }
+
+        self.errors += 1
+    }
+
+    /// Decode the escape sequence just after a backslash, leaving the index after it.
+    fn take_escape(&mut self) -> Option<char> {
+        let c = self.text[self.index..].chars().next()?;
+        if c == '\n' {
+            return None
+        }
+        self.skip_char();
+
+        match c {
+            'n' => Some('\n'),
+            't' => Some('\t'),
+            '"' => Some('"'),
+            '\\' => Some('\\'),
+            'u' => {
+                // \u{1F600}: one to six hex digits naming a unicode scalar value
+                let rest = &self.text[self.index..];
+                let digits = rest.strip_prefix('{')?.split('}').next()?;
+                if digits.is_empty() || digits.len() > 6 || !digits.chars().all(|x| x.is_ascii_hexdigit()) {
+                    return None
+                }
+                if !rest[digits.len() + 1..].starts_with('}') {
+                    return None
+                }
+
+                let value = char::from_u32(u32::from_str_radix(digits, 16).ok()?)?;
+                for _ in 0..digits.len() + 2 {
+                    self.skip_char();
+                }
+                Some(value)
+            }
+            _ => None
+        }
+    }
+
+    /// Lex a double-quoted string. The token's lexeme is the decoded text without its quotes.
+    fn take_string(&mut self) {
+        let start = self.location();
+        self.skip_char();
+
+        let mut value = String::new();
+        loop {
+            let c = match self.text[self.index..].chars().next() {
+                Some(c) if c != '\n' => c,
+                _ => {
+                    self.errors += 1;
+                    return
+                }
+            };
+
+            match c {
+                '"' => {
+                    self.skip_char();
+                    break
+                }
+                '\\' => {
+                    self.skip_char();
+                    match self.take_escape() {
+                        Some(escaped) => value.push(escaped),
+                        None => self.errors += 1
+                    }
+                }
+                c => {
+                    value.push(c);
+                    self.skip_char();
+                }
+            }
+        }
+
+        self.tokens.push(Token::new(Str, Span::new(start, self.location()), value))
+    }
+
+    /// Lex an integer in decimal, hex (`0x`), octal (`0o`) or binary (`0b`), with `_` allowed as
+    /// a separator. The token's lexeme is the value in decimal.
+    fn take_int(&mut self) {
+        let start = self.location();
+        let literal = self.take_while(|x| x == '_' || x.is_ascii_alphanumeric()).unwrap();
+        // every character in the literal is ASCII, so one byte each
+        for _ in 0..literal.len() {
+            self.skip_char();
+        }
+
+        let (radix, digits) = match literal.get(..2) {
+            Some("0x") => (16, &literal[2..]),
+            Some("0o") => (8, &literal[2..]),
+            Some("0b") => (2, &literal[2..]),
+            _ => (10, literal.as_str())
+        };
+
+        let mut value: Option<u128> = Some(0);
+        let mut digit_count = 0;
+        for c in digits.chars().filter(|x| *x != '_') {
+            let Some(digit) = c.to_digit(radix) else {
+                self.errors += 1;
+                return
+            };
+            digit_count += 1;
+            value = value
+                .and_then(|x| x.checked_mul(radix as u128))
+                .and_then(|x| x.checked_add(digit as u128));
+        }
+
+        if digit_count == 0 {
+            self.errors += 1;
+            return
+        }
+
+        match value {
+            Some(value) => self.tokens.push(Token::new(Int, Span::new(start, self.location()), value.to_string())),
+            None => self.errors += 1
+        }
+    }
+
+    fn run_lexer(&mut self) {
+        'outer: while self.index < self.text.len() {
+            if self.text[self.index..].starts_with("//") {
+                self.skip_line_comment();
+                continue
+            }
+
+            if self.text[self.index..].starts_with("/*") {
+                self.skip_block_comment();
+                continue
+            }
+
+            if self.text[self.index..].starts_with('"') {
+                self.take_string();
+                continue
+            }
+
+            if let Some(name) = self.take_name() {
+                let kind = KEYWORD_TABLE.iter()
+                    .find(|(rep, _)| *rep == name)
+                    .map(|(_, kind)| *kind)
+                    .unwrap_or(Name);
+                self.accept_token(kind, name);
+                continue
+            }
+
+            for (rep, kind) in LEX_TABLE {
+                if self.text[self.index..].starts_with(rep) {
+                    self.accept_token(kind, rep);
+                    continue 'outer;
+                }
+            }
+
+            if self.text[self.index..].starts_with(|x: char| x.is_ascii_digit()) {
+                self.take_int();
+                continue
+            }
+
+            let c = self.text[self.index..].chars().next().unwrap();
+            if !c.is_whitespace() {
+                self.errors += 1;
+            }
+            self.skip_char();
+        }
+
+        let end = self.location();
+        self.tokens.push(Token::new(EOI, Span::new(end, end), ""))
+    }
+
+    /// Lex the whole text, or count how many errors are in it.
+    pub fn lex(mut self) -> Result<Vec<Token>, usize> {
+        self.run_lexer();
+        if self.errors > 0 {
+            return Err(self.errors)
+        }
+        Ok(self.tokens)
+    }
+}
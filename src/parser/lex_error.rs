@@ -0,0 +1,62 @@
+use itertools::Itertools;
+use thiserror::Error;
+use crate::diagnostic::{Diagnostic, ToDiagnostic};
+use crate::parser::span::Span;
+
+/// Something the lexer could not make a token of, with the span of all of it.
+#[derive(Debug, Clone, Error, PartialEq)]
+pub enum LexError {
+    #[error("Block comment opened at ({}) is never closed.", .0.start)]
+    UnterminatedBlockComment(Span),
+
+    #[error("Unrecognized character {1:?} at ({}).", .0.start)]
+    UnrecognizedCharacter(Span, char),
+
+    #[error("String opened at ({}) is never closed.", .0.start)]
+    UnterminatedString(Span),
+
+    #[error("Invalid escape sequence at ({}).", .0.start)]
+    InvalidEscape(Span),
+
+    #[error("Character literal at ({}) must hold exactly one character.", .0.start)]
+    InvalidChar(Span),
+
+    #[error("Invalid integer literal {1} at ({}).", .0.start)]
+    InvalidInteger(Span, String),
+
+    #[error("Integer literal {1} at ({}) is too large.", .0.start)]
+    IntegerTooLarge(Span, String),
+
+    #[error("Invalid float literal {1} at ({}).", .0.start)]
+    InvalidFloat(Span, String),
+
+    #[error("Float literal {1} at ({}) is too large.", .0.start)]
+    FloatTooLarge(Span, String),
+}
+
+impl ToDiagnostic for LexError {
+    fn to_diagnostic(&self) -> Diagnostic {
+        let (span, message, label) = match self {
+            LexError::UnterminatedBlockComment(at) => (at, String::from("unterminated block comment"), "opened here"),
+            LexError::UnrecognizedCharacter(at, c) => (at, format!("unrecognized character {:?}", c), "not part of the language"),
+            LexError::UnterminatedString(at) => (at, String::from("unterminated string"), "opened here"),
+            LexError::InvalidEscape(at) => (at, String::from("invalid escape sequence"), "unknown escape"),
+            LexError::InvalidChar(at) => (at, String::from("invalid character literal"), "must hold exactly one character"),
+            LexError::InvalidInteger(at, literal) => (at, format!("invalid integer literal `{}`", literal), "not a number"),
+            LexError::IntegerTooLarge(at, literal) => (at, format!("integer literal `{}` is too large", literal), "too large"),
+            LexError::InvalidFloat(at, literal) => (at, format!("invalid float literal `{}`", literal), "not a number"),
+            LexError::FloatTooLarge(at, literal) => (at, format!("float literal `{}` is too large", literal), "too large"),
+        };
+
+        let diagnostic = Diagnostic::error(message).with_primary(*span, label);
+        match self {
+            LexError::InvalidEscape(_) => diagnostic.with_help(r#"the escapes are \n, \t, \", \', \\ and \u{...}"#),
+            _ => diagnostic
+        }
+    }
+}
+
+/// Every error found in a single pass of the lexer.
+#[derive(Debug, Clone, Error, PartialEq)]
+#[error("{}", .0.iter().join("\n"))]
+pub struct LexErrors(pub Vec<LexError>);
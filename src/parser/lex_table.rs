@@ -1,22 +1,57 @@
+use std::sync::OnceLock;
 use crate::parser::token_kind::TokenKind;
 
 type T = TokenKind;
-pub const LEX_TABLE : [(&'static str, TokenKind); 29]= [
+
+/// Words that would otherwise lex as a `Name`. These are only matched against a whole
+/// identifier, so `iffy` is a name and not `if` followed by `fy`.
+pub const KEYWORD_TABLE : [(&'static str, TokenKind); 23] = [
     ("return", T::Return),
     ("while", T::While),
+    ("for", T::For),
+    ("in", T::In),
+    ("struct", T::Struct),
+    ("enum", T::Enum),
+    ("trait", T::Trait),
+    ("impl", T::Impl),
+    ("match", T::Match),
+    ("break", T::Break),
+    ("continue", T::Continue),
     ("true", T::True),
     ("false", T::False),
     ("bool", T::BoolType),
+    ("char", T::CharType),
+    ("float", T::FloatType),
     ("list", T::ListType),
     ("else", T::Else),
     ("int", T::IntType),
+    ("str", T::StrType),
     ("let", T::Let),
     ("fn", T::Fn),
     ("if", T::If),
+];
+
+/// Punctuation and operators, matched by longest prefix.
+pub const LEX_TABLE : [(&'static str, TokenKind); 38]= [
+    ("..=", T::DotDotEquals),
+    ("&&=", T::AndAndEquals),
+    ("||=", T::PipePipeEquals),
+    ("+=", T::PlusEquals),
+    ("-=", T::MinusEquals),
+    ("*=", T::StarEquals),
+    ("/=", T::SlashEquals),
+    ("%=", T::PercentEquals),
     ("==", T::EqualsEquals),
+    ("!=", T::BangEquals),
+    ("<=", T::LAngleEquals),
+    (">=", T::RAngleEquals),
     ("||", T::PipePie),
     ("&&", T::AndAnd),
     ("->", T::Arrow),
+    ("..", T::DotDot),
+    ("::", T::ColonColon),
+    ("=>", T::FatArrow),
+    (".", T::Dot),
     ("{", T::LCurlyBrace),
     ("}", T::RCurlyBrace),
     (";", T::Semicolon),
@@ -26,9 +61,63 @@ pub const LEX_TABLE : [(&'static str, TokenKind); 29]= [
     (")", T::RParen),
     ("+", T::Plus),
     (">", T::RAngleBracket),
+    ("<", T::LAngleBracket),
     ("-", T::Minus),
+    ("*", T::Star),
+    ("/", T::Slash),
+    ("%", T::Percent),
     ("[", T::LBracket),
     ("]", T::RBracket),
     (",", T::Comma),
     ("!", T::Bang),
-];
\ No newline at end of file
+    ("|", T::Pipe),
+];
+
+/// A table grouped by the first byte of each entry, longest entries first, so a lookup only
+/// has to look at the few entries that could possibly match.
+pub struct Dispatch {
+    buckets: Vec<Vec<(&'static str, TokenKind)>>
+}
+
+impl Dispatch {
+    fn new(table: &[(&'static str, TokenKind)]) -> Self {
+        let mut buckets = vec![vec![]; 128];
+        for &(rep, kind) in table {
+            buckets[rep.as_bytes()[0] as usize].push((rep, kind));
+        }
+        for bucket in buckets.iter_mut() {
+            bucket.sort_by_key(|(rep, _)| std::cmp::Reverse(rep.len()));
+        }
+
+        Self {
+            buckets,
+        }
+    }
+
+    fn bucket(&self, text: &str) -> &[(&'static str, TokenKind)] {
+        match text.as_bytes().first() {
+            Some(&first) if first.is_ascii() => &self.buckets[first as usize],
+            _ => &[]
+        }
+    }
+
+    /// The longest entry that `text` starts with.
+    pub fn longest_prefix(&self, text: &str) -> Option<(&'static str, TokenKind)> {
+        self.bucket(text).iter().find(|(rep, _)| text.starts_with(rep)).copied()
+    }
+
+    /// The entry that is exactly `text`.
+    pub fn exact(&self, text: &str) -> Option<TokenKind> {
+        self.bucket(text).iter().find(|(rep, _)| *rep == text).map(|(_, kind)| *kind)
+    }
+}
+
+pub fn keywords() -> &'static Dispatch {
+    static KEYWORDS: OnceLock<Dispatch> = OnceLock::new();
+    KEYWORDS.get_or_init(|| Dispatch::new(&KEYWORD_TABLE))
+}
+
+pub fn symbols() -> &'static Dispatch {
+    static SYMBOLS: OnceLock<Dispatch> = OnceLock::new();
+    SYMBOLS.get_or_init(|| Dispatch::new(&LEX_TABLE))
+}
\ No newline at end of file
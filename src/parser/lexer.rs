@@ -1,132 +1,468 @@
-use crate::parser::lex_table::LEX_TABLE;
+use std::borrow::Cow;
+use crate::parser::lex_error::{LexError, LexErrors};
+use crate::parser::lex_table::{keywords, symbols};
 use crate::parser::location::Location;
-use crate::parser::token::Token;
+use crate::parser::span::Span;
+use crate::parser::token::{BorrowedToken, Token};
 use crate::parser::token_kind::TokenKind;
 use crate::parser::token_kind::TokenKind::*;
 
-pub struct Lexer {
-    text: String,
+/// Lexes lazily: every call to `next` produces one token, ending with a single `EOI`. Bad
+/// input is skipped over and recorded in `errors` so one pass finds all of it.
+pub struct Lexer<'a> {
+    text: &'a str,
     index: usize,
-    tokens: Vec<Token>,
+    errors: Vec<LexError>,
     row: usize,
     column: usize,
+    finished: bool,
 }
 
-impl Lexer {
-    pub fn new(text: impl AsRef<str>) -> Self {
+impl<'a> Lexer<'a> {
+    pub fn new(text: &'a str) -> Self {
         Self {
-            text: text.as_ref().to_string(),
+            text,
             index: 0,
-            tokens: vec![],
+            errors: vec![],
             row: 1,
             column: 1,
+            finished: false,
         }
     }
-    fn take_while(&self, predicate: fn(char)->bool) -> Option<String> {
-        let mut accumulator = String::from("");
-        for c in self.text[self.index..].chars() {
-            if predicate(c) {
-                accumulator.push(c)
+
+    fn peek_while(&self, predicate: fn(char)->bool) -> &'a str {
+        let rest = &self.text[self.index..];
+        let end = rest.find(|x| !predicate(x)).unwrap_or(rest.len());
+        &rest[..end]
+    }
+
+    fn take_name(&self) -> &'a str {
+        self.peek_while(|x| x == '_' || x.is_alphanumeric())
+    }
+
+    fn location(&self) -> Location {
+        Location::new(self.index, self.row, self.column)
+    }
+
+    /// Accept `lexeme`, which must be the text at the current index and contain no newlines.
+    fn accept_token(&mut self, kind: TokenKind, lexeme: &'a str) -> BorrowedToken<'a> {
+        let start = self.location();
+        self.column += lexeme.chars().count();
+        self.index += lexeme.len();
+
+        BorrowedToken::new(kind, Span::new(start, self.location()), Cow::Borrowed(lexeme))
+    }
+
+    /// Step over the character at the current index, keeping row and column up to date.
+    fn skip_char(&mut self) {
+        let c = self.text[self.index..].chars().next().unwrap();
+        if c == '\n' {
+            self.row += 1;
+            self.column = 1;
+        } else {
+            self.column += 1;
+        }
+        self.index += c.len_utf8();
+    }
+
+    fn skip_line_comment(&mut self) {
+        // leave the newline itself to be skipped like any other whitespace
+        let comment = self.peek_while(|x| x != '\n');
+        self.column += comment.chars().count();
+        self.index += comment.len();
+    }
+
+    /// Block comments nest, so `/* a /* b */ c */` is a single comment.
+    fn skip_block_comment(&mut self) {
+        let opened_at = self.location();
+        let mut depth = 0;
+        while self.index < self.text.len() {
+            if self.text[self.index..].starts_with("/*") {
+                depth += 1;
+                self.skip_char();
+                self.skip_char();
+            } else if self.text[self.index..].starts_with("*/") {
+                depth -= 1;
+                self.skip_char();
+                self.skip_char();
+                if depth == 0 {
+                    return
+                }
+            } else {
+                self.skip_char();
+            }
+        }
+
+        self.errors.push(LexError::UnterminatedBlockComment(Span::new(opened_at, self.location())))
+    }
+
+    /// Decode the escape sequence just after a backslash, leaving the index after it.
+    fn take_escape(&mut self) -> Option<char> {
+        let c = self.text[self.index..].chars().next()?;
+        if c == '\n' {
+            return None
+        }
+        self.skip_char();
+
+        match c {
+            'n' => Some('\n'),
+            't' => Some('\t'),
+            '"' => Some('"'),
+            '\'' => Some('\''),
+            '\\' => Some('\\'),
+            'u' => {
+                // \u{1F600}: one to six hex digits naming a unicode scalar value
+                let braced = self.text[self.index..].strip_prefix('{')?;
+                let digits = &braced[..braced.find(|x: char| !x.is_ascii_hexdigit()).unwrap_or(braced.len())];
+                let closed = braced[digits.len()..].starts_with('}');
+                // skip a bad escape whole too, so that all of it is reported
+                for _ in 0..digits.len() + 1 + closed as usize {
+                    self.skip_char();
+                }
+                if !closed || digits.is_empty() || digits.len() > 6 {
+                    return None
+                }
+                char::from_u32(u32::from_str_radix(digits, 16).ok()?)
+            }
+            _ => None
+        }
+    }
+
+    /// Lex a double-quoted string. The token's lexeme is the decoded text without its quotes,
+    /// which is only borrowed when there were no escapes to decode.
+    fn take_string(&mut self) -> Option<BorrowedToken<'a>> {
+        let start = self.location();
+        self.skip_char();
+
+        let body = self.peek_while(|x| x != '"' && x != '\\' && x != '\n');
+        self.column += body.chars().count();
+        self.index += body.len();
+
+        let mut value = Cow::Borrowed(body);
+        loop {
+            let c = match self.text[self.index..].chars().next() {
+                Some(c) if c != '\n' => c,
+                _ => {
+                    self.errors.push(LexError::UnterminatedString(Span::new(start, self.location())));
+                    return None
+                }
+            };
+
+            match c {
+                '"' => {
+                    self.skip_char();
+                    break
+                }
+                '\\' => {
+                    let escape_start = self.location();
+                    self.skip_char();
+                    match self.take_escape() {
+                        Some(escaped) => value.to_mut().push(escaped),
+                        None => self.errors.push(LexError::InvalidEscape(Span::new(escape_start, self.location())))
+                    }
+                }
+                c => {
+                    value.to_mut().push(c);
+                    self.skip_char();
+                }
+            }
+        }
+
+        Some(BorrowedToken::new(Str, Span::new(start, self.location()), value))
+    }
+
+    /// Lex a single-quoted character. The token's lexeme is the decoded character, which is
+    /// only borrowed when it was not escaped.
+    fn take_char(&mut self) -> Option<BorrowedToken<'a>> {
+        let start = self.location();
+        self.skip_char();
+
+        let mut reported = false;
+        let value = match self.text[self.index..].chars().next() {
+            Some('\\') => {
+                let escape_start = self.location();
+                self.skip_char();
+                match self.take_escape() {
+                    Some(escaped) => Some(Cow::Owned(escaped.to_string())),
+                    None => {
+                        self.errors.push(LexError::InvalidEscape(Span::new(escape_start, self.location())));
+                        reported = true;
+                        None
+                    }
+                }
             }
-            else {
-                break;
+            Some(c) if c != '\n' && c != '\'' => {
+                let lexeme = &self.text[self.index..self.index + c.len_utf8()];
+                self.skip_char();
+                Some(Cow::Borrowed(lexeme))
             }
+            _ => None
+        };
+
+        if self.text[self.index..].starts_with('\'') {
+            self.skip_char();
+            if let Some(value) = value {
+                return Some(BorrowedToken::new(Char, Span::new(start, self.location()), value))
+            }
+            if !reported {
+                self.errors.push(LexError::InvalidChar(Span::new(start, self.location())));
+            }
+            return None
         }
 
-        return if accumulator.is_empty() { None } else {Some(accumulator)}
+        // empty or too long, so skip to the closing quote if this line has one
+        let rest = self.peek_while(|x| x != '\'' && x != '\n');
+        self.column += rest.chars().count();
+        self.index += rest.len();
+        if self.text[self.index..].starts_with('\'') {
+            self.skip_char();
+        }
+        self.errors.push(LexError::InvalidChar(Span::new(start, self.location())));
+        None
+    }
+
+    /// The length of the loop label at the start of `text`, like `'outer`, or None if it does
+    /// not start with one. A name in quotes with a quote after it is a char instead.
+    fn label_length(text: &str) -> Option<usize> {
+        let name = text.strip_prefix('\'')?;
+        let length = name.find(|x: char| x != '_' && !x.is_alphanumeric()).unwrap_or(name.len());
+        if length == 0 || !name.starts_with(|x: char| x == '_' || x.is_alphabetic()) || name[length..].starts_with('\'') {
+            return None
+        }
+        Some(1 + length)
     }
 
-    fn take_name(&mut self) -> Option<String> {
-        let first_char = self.text[self.index..].chars().next().unwrap();
-        if first_char != '_' && !first_char.is_alphabetic() {
-            return None;
+    /// The length of the float literal at the start of `text`, or None if it does not start
+    /// with one. A float is decimal digits followed by a fraction (`.5`), an exponent (`e10`,
+    /// `E-3`) or both.
+    fn float_length(text: &str) -> Option<usize> {
+        let bytes = text.as_bytes();
+        let digits_from = |start: usize| start + bytes[start..].iter().take_while(|x| **x == b'_' || x.is_ascii_digit()).count();
+        if matches!(text.get(..2), Some("0x" | "0o" | "0b")) {
+            return None
         }
-        let first_char = String::from(first_char);
-        self.index += 1;
 
-        let next_chars = self.take_while(|x| x == '_' || x.is_alphanumeric());
-        // roll back index b/c it will get incremented by accept_token
-        self.index -= 1;
-        if next_chars.is_none() {
+        let mut end = digits_from(0);
+        let mut is_float = false;
+        if bytes.get(end) == Some(&b'.') && bytes.get(end + 1).is_some_and(|x| x.is_ascii_digit()) {
+            end = digits_from(end + 1);
+            is_float = true;
+        }
 
-            return Some(first_char);
+        if matches!(bytes.get(end), Some(b'e' | b'E')) {
+            let sign = matches!(bytes.get(end + 1), Some(b'+' | b'-')) as usize;
+            if bytes.get(end + 1 + sign).is_some_and(|x| x.is_ascii_digit()) {
+                end = digits_from(end + 1 + sign);
+                is_float = true;
+            }
         }
 
-        return Some(format!("{}{}", first_char, next_chars.unwrap()))
+        if !is_float {
+            return None
+        }
+        // anything glued on the end makes the whole literal invalid, as with integers
+        Some(end + text[end..].find(|x: char| x != '_' && !x.is_ascii_alphanumeric()).unwrap_or(text.len() - end))
     }
 
-    fn accept_token(&mut self, kind: TokenKind, lexeme: impl AsRef<str>) {
-        let lexeme = lexeme.as_ref().to_string();
-        let start_column = self.column;
-        self.column += lexeme.len();
-        self.index += lexeme.len();
+    /// Lex a float literal of `length` bytes. The token's lexeme is the literal without `_`
+    /// separators.
+    fn take_float(&mut self, length: usize) -> Option<BorrowedToken<'a>> {
+        let start = self.location();
+        let literal = &self.text[self.index..self.index + length];
+        // every character in the literal is ASCII, so one column per byte
+        self.column += literal.len();
+        self.index += literal.len();
+        let span = Span::new(start, self.location());
 
-        self.tokens.push(Token::new(
-            kind,
-            Location::new(self.row, start_column),
-            lexeme
-        ))
+        let lexeme = if literal.contains('_') { Cow::Owned(literal.replace('_', "")) } else { Cow::Borrowed(literal) };
+        match lexeme.parse::<f64>() {
+            Ok(value) if value.is_finite() => Some(BorrowedToken::new(Float, span, lexeme)),
+            Ok(_) => {
+                self.errors.push(LexError::FloatTooLarge(span, literal.to_string()));
+                None
+            }
+            Err(_) => {
+                self.errors.push(LexError::InvalidFloat(span, literal.to_string()));
+                None
+            }
+        }
     }
 
-    fn run_lexer(&mut self) {
-        'outer: while self.index < self.text.len() {
-            for (rep, kind) in LEX_TABLE {
-                if self.text[self.index..].starts_with(rep) {
-                    self.accept_token(kind, rep);
-                    continue 'outer;
-                }
+    /// Lex an integer in decimal, hex (`0x`), octal (`0o`) or binary (`0b`), with `_` allowed as
+    /// a separator. The token's lexeme is the value in decimal.
+    fn take_int(&mut self) -> Option<BorrowedToken<'a>> {
+        let start = self.location();
+        let literal = self.peek_while(|x| x == '_' || x.is_ascii_alphanumeric());
+        // every character in the literal is ASCII, so one column per byte
+        self.column += literal.len();
+        self.index += literal.len();
+        let span = Span::new(start, self.location());
+
+        let (radix, digits) = match literal.get(..2) {
+            Some("0x") => (16, &literal[2..]),
+            Some("0o") => (8, &literal[2..]),
+            Some("0b") => (2, &literal[2..]),
+            _ => (10, literal)
+        };
+
+        let mut value: Option<u128> = Some(0);
+        let mut digit_count = 0;
+        for c in digits.chars().filter(|x| *x != '_') {
+            let Some(digit) = c.to_digit(radix) else {
+                self.errors.push(LexError::InvalidInteger(span, literal.to_string()));
+                return None
+            };
+            digit_count += 1;
+            value = value
+                .and_then(|x| x.checked_mul(radix as u128))
+                .and_then(|x| x.checked_add(digit as u128));
+        }
+
+        if digit_count == 0 {
+            self.errors.push(LexError::InvalidInteger(span, literal.to_string()));
+            return None
+        }
+
+        let Some(value) = value else {
+            self.errors.push(LexError::IntegerTooLarge(span, literal.to_string()));
+            return None
+        };
+
+        // plain decimal literals are already normalized, so there is nothing to allocate
+        let already_decimal = radix == 10 && digit_count == literal.len() && (literal.len() == 1 || !literal.starts_with('0'));
+        let lexeme = if already_decimal { Cow::Borrowed(literal) } else { Cow::Owned(value.to_string()) };
+        Some(BorrowedToken::new(Int, span, lexeme))
+    }
+
+    /// Lex the next token, borrowing its lexeme from the text where possible. Produces `EOI`
+    /// once the text runs out and None after that.
+    pub fn next_borrowed(&mut self) -> Option<BorrowedToken<'a>> {
+        while self.index < self.text.len() {
+            let rest = &self.text[self.index..];
+            let c = rest.chars().next().unwrap();
+
+            if c.is_whitespace() {
+                self.skip_char();
+                continue
             }
 
-            if let Some(int) = self.take_while(|x| x.is_numeric()){
-                self.accept_token(Int, int);
+            if rest.starts_with("//") {
+                self.skip_line_comment();
                 continue
             }
 
-            if let Some(name) = self.take_name() {
-                self.accept_token(Name, name);
+            if rest.starts_with("/*") {
+                self.skip_block_comment();
                 continue
             }
 
-            if self.text[self.index..].starts_with("\n") {
-                self.row += 1;
-                self.column = 1;
+            if c == '"' {
+                match self.take_string() {
+                    Some(token) => return Some(token),
+                    None => continue
+                }
+            }
+
+            if let Some(length) = Self::label_length(rest) {
+                return Some(self.accept_token(Label, &rest[..length]))
             }
-            self.index += 1;
+
+            if c == '\'' {
+                match self.take_char() {
+                    Some(token) => return Some(token),
+                    None => continue
+                }
+            }
+
+            if c.is_ascii_digit() {
+                let token = match Self::float_length(rest) {
+                    Some(length) => self.take_float(length),
+                    None => self.take_int()
+                };
+                match token {
+                    Some(token) => return Some(token),
+                    None => continue
+                }
+            }
+
+            if c == '_' || c.is_alphabetic() {
+                let name = self.take_name();
+                let kind = keywords().exact(name).unwrap_or(Name);
+                return Some(self.accept_token(kind, name))
+            }
+
+            if let Some((rep, kind)) = symbols().longest_prefix(rest) {
+                return Some(self.accept_token(kind, rep))
+            }
+
+            let start = self.location();
+            self.skip_char();
+            self.errors.push(LexError::UnrecognizedCharacter(Span::new(start, self.location()), c));
         }
 
-        self.tokens.push(Token::new(EOI, Location::new(self.row, self.column + 1), ""))
+        if self.finished {
+            return None
+        }
+        self.finished = true;
+
+        let end = self.location();
+        Some(BorrowedToken::new(EOI, Span::new(end, end), Cow::Borrowed("")))
     }
 
-    pub fn lex(mut self) -> Vec<Token> {
-        self.run_lexer();
-        self.tokens
+    /// Everything wrong with the text lexed so far.
+    #[cfg(test)]
+    pub fn errors(&self) -> &[LexError] {
+        &self.errors
+    }
+
+    /// Lex the whole text, reporting every bad character rather than stopping at the first.
+    pub fn lex(mut self) -> Result<Vec<Token>, LexErrors> {
+        let tokens: Vec<Token> = self.by_ref().collect();
+        if !self.errors.is_empty() {
+            return Err(LexErrors(self.errors))
+        }
+        Ok(tokens)
+    }
+}
+
+impl Iterator for Lexer<'_> {
+    type Item = Token;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_borrowed().map(BorrowedToken::into_owned)
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use itertools::Itertools;
+    use crate::parser::baseline_lexer::BaselineLexer;
+    use crate::parser::lex_table::{KEYWORD_TABLE, LEX_TABLE};
     use super::*;
 
+    /// The span of `length` ASCII characters from `offset`, all on one row.
+    fn span(offset: usize, row: usize, column: usize, length: usize) -> Span {
+        Span::new(Location::new(offset, row, column), Location::new(offset + length, row, column + length))
+    }
+
     #[test]
     fn number() {
         let text = "1234";
         let mut lexer = Lexer::new(text);
-        lexer.run_lexer();
-        assert!(lexer.tokens[0].content_equal(&Token::un_located(Int, text)))
+        assert!(lexer.next().unwrap().content_equal(&Token::un_located(Int, text)))
     }
 
     #[test]
     fn name() {
         let text = "cat";
         let mut lexer = Lexer::new(text);
-        lexer.run_lexer();
-        assert!(lexer.tokens[0].content_equal(&Token::un_located(Name, text)))
+        assert!(lexer.next().unwrap().content_equal(&Token::un_located(Name, text)))
     }
 
     #[test]
     fn name_and_number() {
         let text = "cat 123";
-        let tokens = Lexer::new(text).lex();
+        let tokens = Lexer::new(text).lex().unwrap();
         assert!(
             tokens[0].content_equal(&Token::un_located(Name, "cat")) &&
             tokens[1].content_equal(&Token::un_located(Int, "123"))
@@ -136,7 +472,7 @@ mod tests {
     #[test]
     fn number_and_name() {
         let text = "123 cat";
-        let tokens = Lexer::new(text).lex();
+        let tokens = Lexer::new(text).lex().unwrap();
         assert!(
             tokens[0].content_equal(&Token::un_located(Int, "123")) &&
             tokens[1].content_equal(&Token::un_located(Name, "cat"))
@@ -146,7 +482,7 @@ mod tests {
     #[test]
     fn simple_math() {
         let text = "123 + 456 - sam";
-        let tokens = Lexer::new(text).lex();
+        let tokens = Lexer::new(text).lex().unwrap();
         assert!(tokens[0].content_equal(&Token::un_located(Int, "123")));
         assert!(tokens[1].content_equal(&Token::un_located(Plus, "+")));
         assert!(tokens[2].content_equal(&Token::un_located(Int, "456")));
@@ -157,17 +493,17 @@ mod tests {
     #[test]
     fn new_line() {
         let text = "fn\nfn";
-        let tokens = Lexer::new(text).lex();
+        let tokens = Lexer::new(text).lex().unwrap();
         assert_eq!(tokens, vec![
-            Token::new(TokenKind::Fn, Location::new(1, 1), "fn"),
-            Token::new(TokenKind::Fn, Location::new(2, 1), "fn"),
-            Token::new(EOI, Location::new(2, 4), "")
+            Token::new(TokenKind::Fn, Span::new(Location::new(0, 1, 1), Location::new(2, 1, 3)), "fn"),
+            Token::new(TokenKind::Fn, Span::new(Location::new(3, 2, 1), Location::new(5, 2, 3)), "fn"),
+            Token::new(EOI, Span::new(Location::new(5, 2, 3), Location::new(5, 2, 3)), "")
         ])
     }
     #[test]
     fn x_colon() {
         let text = "x:";
-        let tokens = Lexer::new(text).lex();
+        let tokens = Lexer::new(text).lex().unwrap();
         assert_eq!(tokens.len(), 3);
 
         assert!(tokens[0].content_equal(&Token::un_located(Name, "x")));
@@ -178,7 +514,7 @@ mod tests {
     #[test]
     fn one_colon() {
         let text = "1:";
-        let tokens = Lexer::new(text).lex();
+        let tokens = Lexer::new(text).lex().unwrap();
         assert_eq!(tokens.len(), 3);
 
         assert!(tokens[0].content_equal(&Token::un_located(Int, "1")));
@@ -189,7 +525,7 @@ mod tests {
     #[test]
     fn var_decl() {
         let text = "let x: int = 4;";
-        let tokens = Lexer::new(text).lex();
+        let tokens = Lexer::new(text).lex().unwrap();
         assert_eq!(tokens.len(), 8);
 
         assert!(tokens[0].content_equal(&Token::un_located(Let, "let")));
@@ -205,7 +541,7 @@ mod tests {
     #[test]
     fn func_call() {
         let text = "cat();";
-        let tokens = Lexer::new(text).lex();
+        let tokens = Lexer::new(text).lex().unwrap();
         assert_eq!(tokens.len(), 5);
         assert!(tokens[0].content_equal(&Token::un_located(Name, "cat")));
         assert!(tokens[1].content_equal(&Token::un_located(LParen, "(")));
@@ -217,7 +553,7 @@ mod tests {
     #[test]
     fn int_call() {
         let text = "123();";
-        let tokens = Lexer::new(text).lex();
+        let tokens = Lexer::new(text).lex().unwrap();
         assert_eq!(tokens.len(), 5);
         assert!(tokens[0].content_equal(&Token::un_located(Int, "123")));
         assert!(tokens[1].content_equal(&Token::un_located(LParen, "(")));
@@ -229,7 +565,7 @@ mod tests {
     #[test]
     fn func_def_1() {
         let text = "fn func(a:int, b:bool) -> int {}";
-        let tokens = Lexer::new(text).lex();
+        let tokens = Lexer::new(text).lex().unwrap();
 
         assert_eq!(tokens.len(), 16);
         assert!(tokens[0].content_equal(&Token::un_located(Fn, "fn")));
@@ -253,7 +589,7 @@ mod tests {
     #[test]
     fn and() {
         let text = "42 && 0";
-        let tokens = Lexer::new(text).lex();
+        let tokens = Lexer::new(text).lex().unwrap();
         assert_eq!(tokens.len(), 4);
 
         assert!(tokens[0].content_equal(&Token::un_located(Int, "42")));
@@ -265,7 +601,7 @@ mod tests {
     #[test]
     fn double_def() {
         let text = "fn universe() -> int {} fn main() -> int {}";
-        let tokens = Lexer::new(text).lex();
+        let tokens = Lexer::new(text).lex().unwrap();
         assert_eq!(tokens.len(), 17);
 
         assert!(tokens[0].content_equal(&Token::un_located(Fn, "fn")));
@@ -287,4 +623,408 @@ mod tests {
         assert!(tokens[15].content_equal(&Token::un_located(RCurlyBrace, "}")));
         assert!(tokens[16].content_equal(&Token::un_located(EOI, "")));
     }
+
+    #[test]
+    fn line_comment() {
+        let text = "let // a comment\nx";
+        let tokens = Lexer::new(text).lex().unwrap();
+        assert_eq!(tokens, vec![
+            Token::new(Let, Span::new(Location::new(0, 1, 1), Location::new(3, 1, 4)), "let"),
+            Token::new(Name, Span::new(Location::new(17, 2, 1), Location::new(18, 2, 2)), "x"),
+            Token::new(EOI, Span::new(Location::new(18, 2, 2), Location::new(18, 2, 2)), "")
+        ])
+    }
+
+    #[test]
+    fn line_comment_at_end() {
+        let text = "x // no newline after me";
+        let tokens = Lexer::new(text).lex().unwrap();
+        assert_eq!(tokens.len(), 2);
+        assert!(tokens[0].content_equal(&Token::un_located(Name, "x")));
+        assert!(tokens[1].content_equal(&Token::un_located(EOI, "")));
+    }
+
+    #[test]
+    fn block_comment() {
+        let text = "a /* b\n c */ d";
+        let tokens = Lexer::new(text).lex().unwrap();
+        assert_eq!(tokens[0].location(), Location::new(0, 1, 1));
+        assert_eq!(tokens[1].location(), Location::new(13, 2, 7));
+    }
+
+    #[test]
+    fn nested_block_comment() {
+        let text = "a /* b /* c */ still a comment */ d";
+        let tokens = Lexer::new(text).lex().unwrap();
+        assert_eq!(tokens.len(), 3);
+        assert!(tokens[0].content_equal(&Token::un_located(Name, "a")));
+        assert!(tokens[1].content_equal(&Token::un_located(Name, "d")));
+    }
+
+    #[test]
+    fn unterminated_block_comment() {
+        let text = "a\n  /* b /* c */";
+        let errors = Lexer::new(text).lex().unwrap_err();
+        assert_eq!(errors, LexErrors(vec![LexError::UnterminatedBlockComment(Span::new(Location::new(4, 2, 3), Location::new(16, 2, 15)))]));
+    }
+
+    #[test]
+    fn keywords() {
+        for (rep, kind) in KEYWORD_TABLE {
+            let tokens = Lexer::new(rep).lex().unwrap();
+            assert_eq!(tokens.len(), 2, "{}", rep);
+            assert!(tokens[0].content_equal(&Token::un_located(kind, rep)), "{}", rep);
+        }
+    }
+
+    #[test]
+    fn names_starting_with_keywords() {
+        for (rep, _) in KEYWORD_TABLE {
+            for name in [format!("{}y", rep), format!("{}_", rep), format!("{}1", rep), format!("{}{}", rep, rep)] {
+                let tokens = Lexer::new(&name).lex().unwrap();
+                assert_eq!(tokens.len(), 2, "{}", name);
+                assert!(tokens[0].content_equal(&Token::un_located(Name, &name)), "{}", name);
+            }
+        }
+    }
+
+    #[test]
+    fn names_ending_with_keywords() {
+        for (rep, _) in KEYWORD_TABLE {
+            let name = format!("x{}", rep);
+            let tokens = Lexer::new(&name).lex().unwrap();
+            assert_eq!(tokens.len(), 2, "{}", name);
+            assert!(tokens[0].content_equal(&Token::un_located(Name, &name)), "{}", name);
+        }
+    }
+
+    #[test]
+    fn keyword_prefixed_declaration() {
+        let text = "let letter: int = iffy;";
+        let tokens = Lexer::new(text).lex().unwrap();
+        assert_eq!(tokens.len(), 8);
+
+        assert!(tokens[0].content_equal(&Token::un_located(Let, "let")));
+        assert!(tokens[1].content_equal(&Token::un_located(Name, "letter")));
+        assert!(tokens[2].content_equal(&Token::un_located(Colon, ":")));
+        assert!(tokens[3].content_equal(&Token::un_located(IntType, "int")));
+        assert!(tokens[4].content_equal(&Token::un_located(Equals, "=")));
+        assert!(tokens[5].content_equal(&Token::un_located(Name, "iffy")));
+        assert!(tokens[6].content_equal(&Token::un_located(Semicolon, ";")));
+    }
+
+    #[test]
+    fn unrecognized_character() {
+        let text = "let x: int = 4 @ 2;";
+        let errors = Lexer::new(text).lex().unwrap_err();
+        assert_eq!(errors, LexErrors(vec![LexError::UnrecognizedCharacter(span(15, 1, 16, 1), '@')]));
+    }
+
+    #[test]
+    fn every_unrecognized_character() {
+        let text = "$a\nb # c ~";
+        let errors = Lexer::new(text).lex().unwrap_err();
+        assert_eq!(errors, LexErrors(vec![
+            LexError::UnrecognizedCharacter(span(0, 1, 1, 1), '$'),
+            LexError::UnrecognizedCharacter(span(5, 2, 3, 1), '#'),
+            LexError::UnrecognizedCharacter(span(9, 2, 7, 1), '~'),
+        ]));
+    }
+
+    #[test]
+    fn spans() {
+        let text = "let x == 42;";
+        let tokens = Lexer::new(text).lex().unwrap();
+        let ranges: Vec<_> = tokens.iter().map(|x| x.span().byte_range()).collect();
+        assert_eq!(ranges, vec![0..3, 4..5, 6..8, 9..11, 11..12, 12..12]);
+        for token in &tokens {
+            assert_eq!(&text[token.span().byte_range()], token.lexeme());
+        }
+    }
+
+    #[test]
+    fn string() {
+        let text = r#"let s: str = "hi there";"#;
+        let tokens = Lexer::new(text).lex().unwrap();
+        assert_eq!(tokens.len(), 8);
+        assert!(tokens[3].content_equal(&Token::un_located(StrType, "str")));
+        assert!(tokens[5].content_equal(&Token::un_located(Str, "hi there")));
+        assert_eq!(tokens[5].span().byte_range(), 13..23);
+    }
+
+    #[test]
+    fn string_escapes() {
+        let text = r#""a\n\t\"\\\u{48}\u{1F600}""#;
+        let tokens = Lexer::new(text).lex().unwrap();
+        assert!(tokens[0].content_equal(&Token::un_located(Str, "a\n\t\"\\H\u{1F600}")));
+        assert_eq!(tokens[0].span().byte_range(), 0..text.len());
+    }
+
+    #[test]
+    fn empty_string() {
+        let tokens = Lexer::new(r#""""#).lex().unwrap();
+        assert!(tokens[0].content_equal(&Token::un_located(Str, "")));
+    }
+
+    #[test]
+    fn bad_escapes() {
+        let text = r#""\q \u{} \u{110000} \u{41""#;
+        let errors = Lexer::new(text).lex().unwrap_err();
+        assert_eq!(errors, LexErrors(vec![
+            LexError::InvalidEscape(span(1, 1, 2, 2)),
+            LexError::InvalidEscape(span(4, 1, 5, 4)),
+            LexError::InvalidEscape(span(9, 1, 10, 10)),
+            LexError::InvalidEscape(span(20, 1, 21, 5)),
+        ]));
+    }
+
+    #[test]
+    fn unterminated_string() {
+        let text = "\"abc\nx";
+        let errors = Lexer::new(text).lex().unwrap_err();
+        assert_eq!(errors, LexErrors(vec![LexError::UnterminatedString(span(0, 1, 1, 4))]));
+    }
+
+    #[test]
+    fn chars() {
+        let text = r"'a' '\n' '\'' '\u{1F600}' 'é'";
+        let tokens = Lexer::new(text).lex().unwrap();
+        let values: Vec<&str> = tokens.iter().map(|x| x.lexeme()).collect();
+        assert_eq!(values, vec!["a", "\n", "'", "\u{1F600}", "é", ""]);
+        assert!(tokens[..5].iter().all(|x| x.kind() == Char));
+        assert_eq!(tokens[3].span().byte_range(), 14..25);
+        assert_eq!(tokens[4].location(), Location::new(26, 1, 27));
+    }
+
+    #[test]
+    fn labels() {
+        let text = "'outer: 'a' '_x 'a";
+        let tokens = Lexer::new(text).lex().unwrap();
+        let kinds: Vec<TokenKind> = tokens.iter().map(|x| x.kind()).collect();
+        assert_eq!(kinds, vec![Label, Colon, Char, Label, Label, EOI]);
+        assert_eq!(tokens[0].lexeme(), "'outer");
+        assert_eq!(tokens[3].span().byte_range(), 12..15);
+    }
+
+    #[test]
+    fn invalid_chars() {
+        let text = "'' 'ab' '\\q' x '\ny";
+        let errors = Lexer::new(text).lex().unwrap_err();
+        assert_eq!(errors, LexErrors(vec![
+            LexError::InvalidChar(span(0, 1, 1, 2)),
+            LexError::InvalidChar(span(3, 1, 4, 4)),
+            LexError::InvalidEscape(span(9, 1, 10, 2)),
+            LexError::InvalidChar(span(15, 1, 16, 1)),
+        ]));
+
+        // lexing picks up again after the bad literal
+        let mut lexer = Lexer::new("'ab' x");
+        assert!(lexer.next().unwrap().content_equal(&Token::un_located(Name, "x")));
+    }
+
+    #[test]
+    fn integer_bases() {
+        let text = "0xFF 0o17 0b1010 1_000_000 0x_dead_BEEF";
+        let tokens = Lexer::new(text).lex().unwrap();
+        let values: Vec<&str> = tokens.iter().map(|x| x.lexeme()).collect();
+        assert_eq!(values, vec!["255", "15", "10", "1000000", "3735928559", ""]);
+        assert_eq!(tokens[2].span().byte_range(), 10..16);
+    }
+
+    #[test]
+    fn floats() {
+        let text = "1.5 2e10 3E-2 0.000_1 1_0.5e+3 7";
+        let tokens = Lexer::new(text).lex().unwrap();
+        let values: Vec<(TokenKind, &str)> = tokens.iter().map(|x| (x.kind(), x.lexeme())).collect();
+        assert_eq!(values, vec![
+            (Float, "1.5"), (Float, "2e10"), (Float, "3E-2"), (Float, "0.0001"), (Float, "10.5e+3"),
+            (Int, "7"), (EOI, ""),
+        ]);
+        assert_eq!(tokens[4].span().byte_range(), 22..30);
+    }
+
+    #[test]
+    fn invalid_floats() {
+        let text = "1.5f 2e5x 1e999";
+        let errors = Lexer::new(text).lex().unwrap_err();
+        assert_eq!(errors, LexErrors(vec![
+            LexError::InvalidFloat(span(0, 1, 1, 4), String::from("1.5f")),
+            LexError::InvalidFloat(span(5, 1, 6, 4), String::from("2e5x")),
+            LexError::FloatTooLarge(span(10, 1, 11, 5), String::from("1e999")),
+        ]));
+    }
+
+    #[test]
+    fn invalid_integers() {
+        let text = "0x 0b102 12ab 0o8";
+        let errors = Lexer::new(text).lex().unwrap_err();
+        assert_eq!(errors, LexErrors(vec![
+            LexError::InvalidInteger(span(0, 1, 1, 2), String::from("0x")),
+            LexError::InvalidInteger(span(3, 1, 4, 5), String::from("0b102")),
+            LexError::InvalidInteger(span(9, 1, 10, 4), String::from("12ab")),
+            LexError::InvalidInteger(span(14, 1, 15, 3), String::from("0o8")),
+        ]));
+    }
+
+    #[test]
+    fn integer_too_large() {
+        let text = "0xFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFF";
+        let errors = Lexer::new(text).lex().unwrap_err();
+        assert_eq!(errors, LexErrors(vec![LexError::IntegerTooLarge(span(0, 1, 1, text.len()), String::from(text))]));
+    }
+
+    #[test]
+    fn non_ascii_digits() {
+        let errors = Lexer::new("\u{0663}").lex().unwrap_err();
+        assert_eq!(errors, LexErrors(vec![LexError::UnrecognizedCharacter(Span::new(Location::new(0, 1, 1), Location::new(2, 1, 2)), '\u{0663}')]));
+    }
+
+    #[test]
+    fn multi_byte_names() {
+        let text = "let café: int = naïve;";
+        let tokens = Lexer::new(text).lex().unwrap();
+        assert!(tokens[1].content_equal(&Token::un_located(Name, "café")));
+        assert!(tokens[5].content_equal(&Token::un_located(Name, "naïve")));
+
+        assert_eq!(tokens[1].span(), Span::new(Location::new(4, 1, 5), Location::new(9, 1, 9)));
+        assert_eq!(tokens[2].location(), Location::new(9, 1, 9));
+        assert_eq!(tokens[5].span(), Span::new(Location::new(17, 1, 17), Location::new(23, 1, 22)));
+        assert_eq!(&text[tokens[5].span().byte_range()], "naïve");
+    }
+
+    #[test]
+    fn multi_byte_first_character() {
+        let text = "émigré 日本 x";
+        let tokens = Lexer::new(text).lex().unwrap();
+        assert_eq!(tokens.len(), 4);
+        assert!(tokens[0].content_equal(&Token::un_located(Name, "émigré")));
+        assert!(tokens[1].content_equal(&Token::un_located(Name, "日本")));
+        assert_eq!(tokens[1].location(), Location::new(9, 1, 8));
+        assert_eq!(tokens[2].location(), Location::new(16, 1, 11));
+    }
+
+    #[test]
+    fn columns_after_strings_and_comments() {
+        let text = "\"😀\" /* ü */ x";
+        let tokens = Lexer::new(text).lex().unwrap();
+        assert_eq!(tokens[0].span().end, Location::new(6, 1, 4));
+        assert_eq!(tokens[1].location(), Location::new(16, 1, 13));
+        assert_eq!(tokens[1].location().utf16_column(text), 14);
+    }
+
+    #[test]
+    fn tabs() {
+        let text = "\tx\n\t\ty";
+        let tokens = Lexer::new(text).lex().unwrap();
+        assert_eq!(tokens[0].location(), Location::new(1, 1, 2));
+        assert_eq!(tokens[1].location(), Location::new(5, 2, 3));
+    }
+
+    /// The best time of a few runs of `f`, so a slow first run doesn't count.
+    fn best_of<T>(f: impl std::ops::Fn() -> T) -> (T, std::time::Duration) {
+        let mut best = None;
+        for _ in 0..5 {
+            let start = std::time::Instant::now();
+            let result = std::hint::black_box(f());
+            let elapsed = start.elapsed();
+            if best.as_ref().is_none_or(|(_, time)| elapsed < *time) {
+                best = Some((result, elapsed));
+            }
+        }
+        best.unwrap()
+    }
+
+    /// Compares looking up keywords and symbols through `Dispatch` with scanning the tables
+    /// one entry at a time, which is how the lexer matched them before, and lexing the whole
+    /// file with `Lexer` and with `BaselineLexer`, the lexer from before. The numbers are kept
+    /// in benches/lexer.md. Run with `cargo test --release -- --ignored --nocapture lex_100k_lines`.
+    #[test]
+    #[ignore]
+    fn lex_100k_lines() {
+        let function = r#"fn function_name(argument: int, flag: bool) -> int {
+    // count up to the argument
+    let counter: int = 0;
+    let message: str = "still counting\n";
+    while argument > counter {
+        if flag && counter == 0x10 || !flag {
+            counter = counter + 1_000;
+        } else {
+            counter = counter + -1;
+        }
+    }
+    /* block comments /* nest */ too */
+    return counter;
+}
+"#;
+        let text = function.repeat(100_000usize.div_ceil(function.lines().count()));
+        assert!(text.lines().count() >= 100_000);
+
+        // every word and symbol in the text: a word is looked up whole, a symbol by the
+        // longest entry the rest of the text starts with
+        let mut lookups = vec![];
+        let mut lexer = Lexer::new(&text);
+        while let Some(token) = lexer.next_borrowed() {
+            let rest = &text[token.span().start.offset()..];
+            match rest.chars().next() {
+                Some(c) if c == '_' || c.is_alphabetic() => lookups.push((true, &rest[..token.lexeme().len()])),
+                Some(c) if c.is_ascii_punctuation() && c != '"' && c != '\'' => lookups.push((false, rest)),
+                _ => {}
+            }
+        }
+        assert!(lexer.errors().is_empty());
+
+        let (scanned, scan_time) = best_of(|| lookups.iter()
+            .map(|&(word, rest)| match word {
+                true => KEYWORD_TABLE.iter().find(|(rep, _)| *rep == rest).map(|(_, kind)| *kind),
+                false => LEX_TABLE.iter().find(|(rep, _)| rest.starts_with(rep)).map(|(_, kind)| *kind),
+            })
+            .collect_vec());
+        let (dispatched, dispatch_time) = best_of(|| lookups.iter()
+            .map(|&(word, rest)| match word {
+                true => crate::parser::lex_table::keywords().exact(rest),
+                false => symbols().longest_prefix(rest).map(|(_, kind)| kind),
+            })
+            .collect_vec());
+        assert_eq!(scanned, dispatched);
+        println!("{} lookups: table scan {:?}, dispatch {:?} ({:.1}x)",
+                 lookups.len(), scan_time, dispatch_time, scan_time.as_secs_f64() / dispatch_time.as_secs_f64());
+
+        // end to end, against the lexer from before the dispatch tables
+        let (baseline, baseline_time) = best_of(|| BaselineLexer::new(&text).lex().unwrap());
+        let (tokens, owned_time) = best_of(|| Lexer::new(&text).lex().unwrap());
+        let (borrowed, borrowed_time) = best_of(|| {
+            let mut lexer = Lexer::new(&text);
+            std::iter::from_fn(|| lexer.next_borrowed()).count()
+        });
+        assert_eq!(baseline, tokens);
+        assert_eq!(tokens.len(), borrowed);
+        println!("{} lines, {} tokens: baseline {:?}, owned {:?} ({:.1}x), borrowed {:?} ({:.1}x)",
+                 text.lines().count(), tokens.len(), baseline_time,
+                 owned_time, baseline_time.as_secs_f64() / owned_time.as_secs_f64(),
+                 borrowed_time, baseline_time.as_secs_f64() / borrowed_time.as_secs_f64());
+    }
+
+    #[test]
+    fn streaming() {
+        let text = "let x: int = 1; y";
+        let mut lexer = Lexer::new(text);
+        assert!(lexer.next().unwrap().content_equal(&Token::un_located(Let, "let")));
+        assert!(lexer.next().unwrap().content_equal(&Token::un_located(Name, "x")));
+
+        let rest: Vec<TokenKind> = lexer.map(|x| x.kind()).collect();
+        assert_eq!(rest, vec![Colon, IntType, Equals, Int, Semicolon, Name, EOI]);
+    }
+
+    #[test]
+    fn borrowed_lexemes() {
+        let text = r#"name 42 0x2A "plain" "esc\n""#;
+        let mut lexer = Lexer::new(text);
+        let mut borrowed = vec![];
+        while let Some(token) = lexer.next_borrowed() {
+            let range = text.as_bytes().as_ptr_range();
+            borrowed.push(token.lexeme().is_empty() || range.contains(&token.lexeme().as_ptr()));
+        }
+        // only the hex literal and the string with an escape had to be rewritten
+        assert_eq!(borrowed, vec![true, true, false, true, false, true]);
+    }
 }
\ No newline at end of file
@@ -1,20 +1,29 @@
 use std::fmt::{Display, Formatter};
 
-#[derive(Debug, Copy, Clone, PartialOrd, PartialEq, Eq, Ord)]
+/// A point in the source text. `offset` counts UTF-8 bytes from the start of the text, while
+/// `row` and `column` are 1-based and count lines and `char`s, so a tab or an emoji is one column.
+#[derive(Debug, Copy, Clone, Default, PartialOrd, PartialEq, Eq, Ord)]
 pub struct Location {
+    offset: usize,
     row: usize,
     column: usize
 }
 
 #[allow(dead_code)]
 impl Location {
-    pub fn new(row: usize, column: usize) -> Self {
+    pub fn new(offset: usize, row: usize, column: usize) -> Self {
         Self {
+            offset,
             row,
             column,
         }
     }
 
+    /// Byte offset into the source text.
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
     pub fn row(&self) -> usize {
         self.row
     }
@@ -22,10 +31,16 @@ impl Location {
     pub fn column(&self) -> usize {
         self.column
     }
+
+    /// The column counted in UTF-16 code units, which is what most editors and LSP clients use.
+    pub fn utf16_column(&self, text: &str) -> usize {
+        let line_start = text[..self.offset].rfind('\n').map(|x| x + 1).unwrap_or(0);
+        text[line_start..self.offset].encode_utf16().count() + 1
+    }
 }
 
 impl Display for Location {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         write!(f, "{}, {}", self.row, self.column)
     }
-}
\ No newline at end of file
+}
@@ -1,14 +1,18 @@
-use itertools::Itertools;
-use crate::ast::binary_operator::BinaryOperator;
-use crate::ast::binary_operator::BinaryOperator::{Add, GreaterThan};
+use itertools::{Either, Itertools};
 use crate::ast::Block;
 use crate::ast::expression::Expression;
 use crate::ast::expression::Expression::{BinaryOperation, List, UnaryOperation};
-use crate::ast::statement::{FunctionDefinition, Statement};
-use crate::ast::statement::Statement::{Assignment, FunctionDefinitionStatement};
+use crate::ast::pattern::{MatchArm, Pattern};
+use crate::ast::statement::{EnumDefinition, ForIterable, FunctionDefinition, ImplBlock, MethodSignature, Statement, StructDefinition, TraitDefinition, TypeParam};
+use crate::ast::statement::Statement::{Assignment, EnumDefinitionStatement, FunctionDefinitionStatement, ImplStatement, StructDefinitionStatement, TraitDefinitionStatement};
 use crate::ast::unary_operator::UnaryOperator;
+use crate::parser::lex_error::LexErrors;
 use crate::parser::lexer::Lexer;
-use crate::parser::parser_error::{ParserError, ParserErrorKind};
+use crate::parser::operator_table::{Associativity, Fixity};
+use crate::parser::parser_error::{ParserError, ParserErrorKind, ParserErrors};
+use crate::parser::location::Location;
+use crate::parser::span::Span;
+use crate::parser::token::Token;
 use crate::parser::token_holder::TokenHolder;
 use crate::parser::token_kind::TokenKind;
 use crate::parser::token_kind::TokenKind::*;
@@ -17,32 +21,109 @@ use anyhow::Context;
 use crate::typed_ast::r#type::Type;
 
 pub mod lexer;
+pub mod lex_error;
 mod lex_table;
+mod operator_table;
 pub mod location;
+pub mod span;
 pub mod token;
 pub mod token_kind;
 mod token_holder;
+#[cfg(test)]
+mod baseline_lexer;
 pub mod parser_error;
 
 pub struct Parser {
-    tokens: TokenHolder
+    tokens: TokenHolder,
+    errors: Vec<ParserError>,
+    /// Whether `Name {` starts a struct literal, which it can't where a block could follow.
+    struct_literals: bool,
+}
+
+/// Everything about a function but its body.
+struct FunctionHeader {
+    name: Token,
+    type_params: Vec<TypeParam>,
+    arg_list: Vec<(Token, Type)>,
+    type_: Type,
 }
 
 impl Parser {
-    pub fn new(text: impl AsRef<str>) -> Self {
-        Self {
-            tokens: TokenHolder::new(Lexer::new(text).lex())
+    /// Lex all of `text` up front, so that every lex error is reported before any parsing
+    /// starts. Only the lexer streams; the parser still works from the whole `Vec<Token>`.
+    pub fn new(text: impl AsRef<str>) -> Result<Self, LexErrors> {
+        Ok(Self {
+            tokens: TokenHolder::new(Lexer::new(text.as_ref()).lex()?),
+            errors: vec![],
+            struct_literals: true,
+        })
+    }
+
+    /// Parse a statement, or record why it could not be parsed and skip past it. Statements
+    /// are skipped up to the next `;` or whole `{ }` block, or to the `}` or `fn` that follows
+    /// them.
+    fn parse_statement_recovering(&mut self) -> Option<Statement> {
+        let start = self.tokens.position();
+        match self.parse_statement() {
+            Ok(statement) => Some(statement),
+            Err(error) => {
+                let error = ParserError::from_anyhow(error, self.tokens.current());
+                self.errors.push(error);
+                self.synchronize(start);
+                None
+            }
+        }
+    }
+
+    fn synchronize(&mut self, statement_start: usize) {
+        // a statement that went wrong inside its own `{ }`, like a match arm, ends at the `}`
+        // closing it, not at the first one after the error
+        let mut depth = self.tokens.since(statement_start).iter().fold(0usize, |depth, token| match token.kind() {
+            LCurlyBrace => depth + 1,
+            RCurlyBrace => depth.saturating_sub(1),
+            _ => depth,
+        });
+        loop {
+            match self.tokens.current().kind() {
+                Semicolon if depth == 0 => {
+                    self.tokens.next();
+                    return
+                }
+                RCurlyBrace | Fn if depth == 0 => break,
+                EOI => break,
+                LCurlyBrace => {
+                    depth += 1;
+                    self.tokens.next();
+                }
+                RCurlyBrace => {
+                    depth -= 1;
+                    self.tokens.next();
+                    if depth == 0 {
+                        // `let x: int = match y { .. };` still has its `;`
+                        self.tokens.t_match(Semicolon);
+                        return
+                    }
+                }
+                _ => {
+                    self.tokens.next();
+                }
+            }
+        }
+
+        // always make progress, or a bad token at the start of a statement would stall us
+        if self.tokens.position() == statement_start && !self.tokens.empty() {
+            self.tokens.next();
         }
     }
 
     fn unexpected_token(&self, expected: Option<TokenKind>) -> ParserError {
         let expected = expected.map(|x| format!("Expected {:?}", x));
-        ParserError::new(ParserErrorKind::UnexpectedToken, self.tokens.current(), expected)
+        ParserError::new(ParserErrorKind::UnexpectedToken, self.tokens.current().clone(), expected)
     }
 
     fn unexpected_token_alternates(&self, expected: Vec<TokenKind>) -> ParserError {
         let expected = expected.iter().map(|x| format!("{:?}", x)).join(", ");
-        ParserError::new(ParserErrorKind::UnexpectedToken, self.tokens.current(), Some(format!("Expected on of: {}", expected)))
+        ParserError::new(ParserErrorKind::UnexpectedToken, self.tokens.current().clone(), Some(format!("Expected on of: {}", expected)))
     }
 
     fn parse_list<T>(
@@ -62,8 +143,22 @@ impl Parser {
         Ok(insides)
     }
 
+    /// Run `parse` with struct literals allowed or not. They are not allowed where a `{` could
+    /// open a block instead, like after the condition of an `if`, unless they are in brackets.
+    fn with_struct_literals<T>(&mut self, allowed: bool, parse: impl FnOnce(&mut Self) -> anyhow::Result<T>) -> anyhow::Result<T> {
+        let outer = std::mem::replace(&mut self.struct_literals, allowed);
+        let result = parse(self);
+        self.struct_literals = outer;
+        result
+    }
+
+    /// Span from `start` up to and including the last token consumed.
+    fn span_from(&self, start: Span) -> Span {
+        start.to(self.tokens.previous().span())
+    }
+
     fn invalid_name(&self) -> ParserError {
-        ParserError::new(ParserErrorKind::InvalidName, self.tokens.current(), Some(String::from("Expected a name!")))
+        ParserError::new(ParserErrorKind::InvalidName, self.tokens.current().clone(), Some(String::from("Expected a name!")))
     }
 
     fn parse_type(&mut self) -> anyhow::Result<Type> {
@@ -71,24 +166,75 @@ impl Parser {
             return Ok(Type::Int)
         }
 
+        if self.tokens.t_match(FloatType).is_some() {
+            return Ok(Type::Float)
+        }
+
         if self.tokens.t_match(BoolType).is_some() {
             return Ok(Type::Bool)
         }
 
+        if self.tokens.t_match(StrType).is_some() {
+            return Ok(Type::Str)
+        }
+
+        if self.tokens.t_match(CharType).is_some() {
+            return Ok(Type::Char)
+        }
+
         if self.tokens.t_match(ListType).is_some() {
             return Ok(Type::List(Box::new(self.parse_type()?)))
         }
 
-        return Err(self.unexpected_token_alternates(vec![IntType, BoolType, ListType]))
+        if let Some(name) = self.tokens.t_match(Name) {
+            return Ok(Type::Named(name.lexeme().to_string()))
+        }
+
+        if self.tokens.t_match(Fn).is_some() {
+            if self.tokens.t_match(LParen).is_none() {
+                return Err(self.unexpected_token(Some(LParen))).context("Expected a ( after fn in a function type.")
+            }
+            let params = self.parse_list(Self::parse_type, RParen)?;
+            if self.tokens.t_match(Arrow).is_none() {
+                return Err(self.unexpected_token(Some(Arrow))).context("A function type needs an arrow before its return type.")
+            }
+            return Ok(Type::Function(params, Box::new(self.parse_type()?)))
+        }
+
+        // `(int, bool)` is a tuple, `(int)` is just an int in brackets and `(int,)` is a tuple of one
+        if self.tokens.t_match(LParen).is_some() {
+            let first = self.parse_type()?;
+            if self.tokens.t_match(RParen).is_some() {
+                return Ok(first)
+            }
+            if self.tokens.t_match(Comma).is_none() {
+                return Err(self.unexpected_token(Some(Comma))).context("Error parsing a tuple type.")
+            }
+            let rest = self.parse_list(Self::parse_type, RParen)?;
+            return Ok(Type::Tuple([first].into_iter().chain(rest).collect()))
+        }
+
+        return Err(self.unexpected_token_alternates(vec![IntType, FloatType, BoolType, StrType, CharType, ListType, Name, LParen, Fn]))
             .context("Error parsing a type!")
     }
 
     fn parse_var_declaration(&mut self) -> anyhow::Result<Statement> {
-        let name = self.parse_atom()?;
-        let name = match name {
-            Expression::Name(t) => {t}
-            _ => {return Err(self.invalid_name())
-                .context("Expected a name to start a variable declaration.")}
+        let start = self.tokens.previous().span();
+        let name = match self.parse_atom()? {
+            Expression::Name(t) => Some(Either::Left(t)),
+            // `let (a, b): ...` takes a tuple apart
+            Expression::Tuple(elements, _) => elements.into_iter()
+                .map(|x| match x {
+                    Expression::Name(t) => Some(t),
+                    _ => None
+                })
+                .collect::<Option<Vec<Token>>>()
+                .map(Either::Right),
+            _ => None
+        };
+        let Some(name) = name else {
+            return Err(self.invalid_name())
+                .context("Expected a name or a tuple of names to start a variable declaration.")
         };
 
         
@@ -106,10 +252,135 @@ impl Parser {
 
         let value = self.parse_expr()?;
 
-        return Ok(Statement::VariableDeclaration {
+        return Ok(match name {
+            Either::Left(name) => Statement::VariableDeclaration {
+                name,
+                type_: ast_type,
+                value,
+                span: self.span_from(start),
+            },
+            Either::Right(names) => Statement::TupleDeclaration {
+                names,
+                type_: ast_type,
+                value,
+                span: self.span_from(start),
+            }
+        })
+    }
+
+    /// `name: type` in the parameters of a function or lambda.
+    fn parse_parameter(&mut self) -> anyhow::Result<(Token, Type)> {
+        let Some(name) = self.tokens.t_match(Name) else {
+            return Err(self.invalid_name()).context("Expected a name for a function argument.")
+        };
+
+        if self.tokens.t_match(Colon).is_none() {
+            return Err(self.unexpected_token(Some(Colon)))
+                .context("Expected a colon in a variable declaration")
+        }
+
+        let type_ = self.parse_type()?;
+
+        return Ok((name, type_))
+    }
+
+    /// The rest of a function after `fn`. A method has no type parameters and starts its
+    /// arguments with `self`.
+    fn parse_function(&mut self, start: Span, method: bool) -> anyhow::Result<FunctionDefinition> {
+        let FunctionHeader { name, type_params, arg_list, type_ } = self.parse_function_header(method)?;
+        let body = self.parse_block()?;
+
+        return Ok(FunctionDefinition {
             name,
-            type_: ast_type,
-            value,
+            type_params,
+            type_,
+            arg_list,
+            body,
+            span: self.span_from(start),
+        })
+    }
+
+    /// `name<T: Trait>(arg: type, ...) -> type`.
+    fn parse_function_header(&mut self, method: bool) -> anyhow::Result<FunctionHeader> {
+        let name = if let Some(name) = self.tokens.t_match(Name) {
+            name
+        } else {
+            return Err(self.invalid_name()).context("Invalid name for a function.")
+        };
+
+        let type_params = if !method && self.tokens.t_match(LAngleBracket).is_some() {
+            self.parse_list(Self::parse_type_param, RAngleBracket)?
+        } else {
+            vec![]
+        };
+
+        if self.tokens.t_match(LParen).is_none() {
+            return Err(self.unexpected_token(Some(LParen)))
+                .context("Expected a LParen after a name in function call.")
+        }
+
+        let mut arg_list = vec![];
+        if method {
+            let Some(receiver) = self.tokens.expect(Name).filter(|x| x.lexeme() == "self").cloned() else {
+                return Err(self.invalid_name()).context("A method takes `self` first.")
+            };
+            self.tokens.next();
+            arg_list.push((receiver, Type::Named(String::from("Self"))));
+
+            if self.tokens.t_match(Comma).is_none() && self.tokens.expect(RParen).is_none() {
+                return Err(self.unexpected_token(Some(Comma)))
+                    .context("Error parsing list.");
+            }
+        }
+        arg_list.extend(self.parse_list(Self::parse_parameter, RParen)?);
+
+        if self.tokens.t_match(Arrow).is_none() {
+            return Err(self.unexpected_token(Some(Arrow)))
+                .context("A function declaration needs an arrow to delimit type!")
+        }
+
+        let type_ = self.parse_type()?;
+
+        return Ok(FunctionHeader { name, type_params, arg_list, type_ })
+    }
+
+    /// `T`, or `T: Show + Eq` when it has to implement some traits.
+    fn parse_type_param(&mut self) -> anyhow::Result<TypeParam> {
+        let Some(name) = self.tokens.t_match(Name) else {
+            return Err(self.invalid_name()).context("Expected the name of a type parameter.")
+        };
+
+        let mut bounds = vec![];
+        if self.tokens.t_match(Colon).is_some() {
+            loop {
+                let Some(bound) = self.tokens.t_match(Name) else {
+                    return Err(self.invalid_name()).context("Expected the name of a trait.")
+                };
+                bounds.push(bound);
+                if self.tokens.t_match(Plus).is_none() {
+                    break
+                }
+            }
+        }
+
+        return Ok(TypeParam { name, bounds })
+    }
+
+    /// The rest of `|x: int| -> int { x + 1 }` after the opening `|`, or after the `||` of a
+    /// lambda with no parameters.
+    fn parse_lambda(&mut self, start: Span, params: Vec<(Token, Type)>) -> anyhow::Result<Expression> {
+        if self.tokens.t_match(Arrow).is_none() {
+            return Err(self.unexpected_token(Some(Arrow)))
+                .context("A lambda needs an arrow to delimit its type!")
+        }
+
+        let return_type = self.parse_type()?;
+        let body = self.parse_block()?;
+        Ok(Expression::Lambda {
+            params,
+            return_type,
+            body,
+            span: self.span_from(start),
         })
     }
 
@@ -125,86 +396,243 @@ impl Parser {
     }
     
     fn parse_statement(&mut self) -> anyhow::Result<Statement> {
+        let start = self.tokens.current().span();
         if self.tokens.t_match(Let).is_some() {
 
             let var_decl = self.parse_var_declaration()?;
             self.eat_semicolon()?;
             return Ok(var_decl);
         }
+        let label = self.tokens.t_match(Label);
+        if label.is_some() {
+            if self.tokens.t_match(Colon).is_none() {
+                return Err(self.unexpected_token(Some(Colon)))
+                    .context("Expected a colon after a loop label.")
+            }
+            if !matches!(self.tokens.current().kind(), While | For) {
+                return Err(self.unexpected_token(Some(While)))
+                    .context("Only loops can be labelled.")
+            }
+        }
         if self.tokens.t_match(While).is_some() {
             return Ok(Statement::While {
-                condition: self.parse_expr()?,
+                label,
+                condition: self.with_struct_literals(false, Self::parse_expr)?,
+                body: self.parse_block()?,
+                span: self.span_from(start),
+            })
+        }
+        if self.tokens.t_match(For).is_some() {
+            let Some(variable) = self.tokens.t_match(Name) else {
+                return Err(self.invalid_name()).context("Expected a name for the variable of a for loop.")
+            };
+
+            if self.tokens.t_match(In).is_none() {
+                return Err(self.unexpected_token(Some(In)))
+                    .context("Expected `in` after the variable of a for loop.")
+            }
+
+            let iterable = self.with_struct_literals(false, |parser| {
+                let first = parser.parse_expr()?;
+                Ok(if parser.tokens.t_match(DotDot).is_some() {
+                    ForIterable::Range { start: Box::new(first), end: Box::new(parser.parse_expr()?), inclusive: false }
+                } else if parser.tokens.t_match(DotDotEquals).is_some() {
+                    ForIterable::Range { start: Box::new(first), end: Box::new(parser.parse_expr()?), inclusive: true }
+                } else {
+                    ForIterable::List(Box::new(first))
+                })
+            })?;
+
+            return Ok(Statement::For {
+                label,
+                variable,
+                iterable,
                 body: self.parse_block()?,
+                span: self.span_from(start),
             })
         }
         if self.tokens.t_match(Fn).is_some() {
-            let name = if let Some(name) = self.tokens.t_match(Name) {
-                name
-            } else {
-                return Err(self.invalid_name()).context("Invalid name for a function.")
+            return Ok(FunctionDefinitionStatement(self.parse_function(start, false)?))
+        }
+        if self.tokens.t_match(Break).is_some() {
+            let ret = Statement::Break(self.tokens.t_match(Label), self.span_from(start));
+            self.eat_semicolon()?;
+            return Ok(ret)
+        }
+        if self.tokens.t_match(Continue).is_some() {
+            let ret = Statement::Continue(self.tokens.t_match(Label), self.span_from(start));
+            self.eat_semicolon()?;
+            return Ok(ret)
+        }
+        if self.tokens.t_match(Return).is_some() {
+            let ret = Statement::Return(self.parse_expr()?, self.span_from(start));
+            self.eat_semicolon()?;
+            return Ok(ret)
+        }
+
+        if self.tokens.t_match(Struct).is_some() {
+            let Some(name) = self.tokens.t_match(Name) else {
+                return Err(self.invalid_name()).context("Invalid name for a struct.")
             };
 
-            if self.tokens.t_match(LParen).is_none() {
-                return Err(self.unexpected_token(Some(LParen)))
-                    .context("Expected a LParen after a name in function call.")
+            if self.tokens.t_match(LCurlyBrace).is_none() {
+                return Err(self.unexpected_token(Some(LCurlyBrace)))
+                    .context("Expected a { after the name of a struct.")
             }
 
-            let args = self.parse_list(|parser| {
-                let name = parser.parse_atom()?;
-                let name = match name {
-                    Expression::Name(t) => t,
-                    _ => {return Err(parser.invalid_name())
-                        .context("Expected a name for a function argument.")}
+            let fields = self.parse_list(|parser| {
+                let Some(name) = parser.tokens.t_match(Name) else {
+                    return Err(parser.invalid_name()).context("Expected a name for a struct field.")
                 };
 
                 if parser.tokens.t_match(Colon).is_none() {
                     return Err(parser.unexpected_token(Some(Colon)))
-                        .context("Expected a colon in a variable declaration")
+                        .context("Expected a colon after the name of a struct field.")
                 }
 
-                let type_ = parser.parse_type()?;
+                Ok((name, parser.parse_type()?))
+            }, RCurlyBrace)?;
 
-                return Ok((name, type_))
-            }, RParen)?;
+            return Ok(StructDefinitionStatement(StructDefinition {
+                name,
+                fields,
+                span: self.span_from(start),
+            }))
+        }
 
-            if self.tokens.t_match(Arrow).is_none() {
-                return Err(self.unexpected_token(Some(Arrow)))
-                    .context("A function declaration needs an arrow to delimit type!")
+        if self.tokens.t_match(Trait).is_some() {
+            let Some(name) = self.tokens.t_match(Name) else {
+                return Err(self.invalid_name()).context("Invalid name for a trait.")
+            };
+
+            if self.tokens.t_match(LCurlyBrace).is_none() {
+                return Err(self.unexpected_token(Some(LCurlyBrace)))
+                    .context("Expected a { after the name of a trait.")
+            }
+
+            let mut methods = vec![];
+            while self.tokens.t_match(RCurlyBrace).is_none() {
+                if self.tokens.t_match(Fn).is_none() {
+                    return Err(self.unexpected_token(Some(Fn)))
+                        .context("A trait can only hold method signatures.")
+                }
+                let FunctionHeader { name, arg_list, type_, .. } = self.parse_function_header(true)?;
+                if self.tokens.t_match(Semicolon).is_none() {
+                    return Err(self.unexpected_token(Some(Semicolon)))
+                        .context("Expected a semi-colon after a method signature.")
+                }
+                methods.push(MethodSignature { name, arg_list, type_ });
+            }
+
+            return Ok(TraitDefinitionStatement(TraitDefinition {
+                name,
+                methods,
+                span: self.span_from(start),
+            }))
+        }
+
+        if self.tokens.t_match(Impl).is_some() {
+            let Some(trait_) = self.tokens.t_match(Name) else {
+                return Err(self.invalid_name()).context("Expected the name of a trait after impl.")
+            };
+
+            if self.tokens.t_match(For).is_none() {
+                return Err(self.unexpected_token(Some(For)))
+                    .context("Expected `for` after the name of the trait being implemented.")
             }
 
             let type_ = self.parse_type()?;
-            let block = self.parse_block()?;
 
+            if self.tokens.t_match(LCurlyBrace).is_none() {
+                return Err(self.unexpected_token(Some(LCurlyBrace)))
+                    .context("Expected a { after the type of an impl.")
+            }
 
-            return Ok(FunctionDefinitionStatement(FunctionDefinition {
-                name,
+            let mut methods = vec![];
+            while self.tokens.t_match(RCurlyBrace).is_none() {
+                let method_start = self.tokens.current().span();
+                if self.tokens.t_match(Fn).is_none() {
+                    return Err(self.unexpected_token(Some(Fn)))
+                        .context("An impl can only hold methods.")
+                }
+                methods.push(self.parse_function(method_start, true)?);
+            }
+
+            return Ok(ImplStatement(ImplBlock {
+                trait_,
                 type_,
-                arg_list: args,
-                body: block,
+                methods,
+                span: self.span_from(start),
             }))
         }
-        if self.tokens.t_match(Return).is_some() {
-            let ret = Statement::Return(self.parse_expr()?);
-            self.eat_semicolon()?;
-            return Ok(ret)
+
+        if self.tokens.t_match(Enum).is_some() {
+            let Some(name) = self.tokens.t_match(Name) else {
+                return Err(self.invalid_name()).context("Invalid name for an enum.")
+            };
+
+            if self.tokens.t_match(LCurlyBrace).is_none() {
+                return Err(self.unexpected_token(Some(LCurlyBrace)))
+                    .context("Expected a { after the name of an enum.")
+            }
+
+            let variants = self.parse_list(|parser| {
+                let Some(name) = parser.tokens.t_match(Name) else {
+                    return Err(parser.invalid_name()).context("Expected a name for an enum variant.")
+                };
+
+                let payload = if parser.tokens.t_match(LParen).is_some() {
+                    parser.parse_list(Self::parse_type, RParen)?
+                } else {
+                    vec![]
+                };
+
+                Ok((name, payload))
+            }, RCurlyBrace)?;
+
+            return Ok(EnumDefinitionStatement(EnumDefinition {
+                name,
+                variants,
+                span: self.span_from(start),
+            }))
         }
 
         let expr = self.parse_expr()?;
 
-        match expr {
-            Expression::Name(ref t) => {
-                if self.tokens.t_match(Equals).is_some() {
-                    let value = self.parse_expr()?;
-                    self.eat_semicolon()?;
-
-                    return Ok(Assignment {
-                        to: t.clone(),
+        let target = Self::assignment_target(&expr);
+        if target.is_some() || matches!(expr, Expression::Index { .. }) {
+            let operator = operator_table::compound_assignment(self.tokens.current().kind());
+            if operator.is_some() || self.tokens.t_match(Equals).is_some() {
+                if operator.is_some() {
+                    self.tokens.next();
+                }
+                let value = self.parse_expr()?;
+                let span = self.span_from(start);
+                self.eat_semicolon()?;
+
+                if let Expression::Index { of, index, span: _ } = expr {
+                    return Ok(Statement::IndexAssignment {
+                        list: of,
+                        index,
+                        operator,
                         value,
+                        span,
                     })
                 }
+                let (to, fields) = target.unwrap();
+                return Ok(Assignment {
+                    to,
+                    fields,
+                    operator,
+                    value,
+                    span,
+                })
             }
+        }
+
+        match expr {
             // rember that you can have an if and allow it not to have a semicolon on it.
-            Expression::If { .. } => {
+            Expression::If { .. } | Expression::Match { .. } => {
                 // match a semicolon if it exists
                 self.tokens.t_match(Semicolon);
                 return Ok(Statement::Expression(expr))
@@ -217,123 +645,257 @@ impl Parser {
         return Ok(Statement::Expression(expr))
     }
     
+    /// The variable and the fields an assignment to `expr` writes, like `p` and `[x]` for `p.x`.
+    fn assignment_target(expr: &Expression) -> Option<(Token, Vec<Token>)> {
+        match expr {
+            Expression::Name(name) => Some((name.clone(), vec![])),
+            Expression::FieldAccess { of, field, span: _ } => {
+                let (to, mut fields) = Self::assignment_target(of)?;
+                fields.push(field.clone());
+                Some((to, fields))
+            }
+            _ => None
+        }
+    }
+
     fn parse_block(&mut self) -> anyhow::Result<Block> {
         if self.tokens.t_match(LCurlyBrace).is_none() { 
             return Err(self.unexpected_token(Some(LCurlyBrace)))
                 .context("Blocks must start with a {")
         }
 
+        self.with_struct_literals(true, Self::parse_block_statements)
+    }
+
+    fn parse_block_statements(&mut self) -> anyhow::Result<Block> {
         let mut block = vec![];
         while self.tokens.t_match(RCurlyBrace).is_none() {
-            block.push(self.parse_statement()?)
+            if self.tokens.empty() {
+                return Err(self.unexpected_token(Some(RCurlyBrace)))
+                    .context("Blocks must end with a }")
+            }
+            if let Some(statement) = self.parse_statement_recovering() {
+                block.push(statement)
+            }
         }
         
         return Ok(block)
     }
 
     fn parse_expr(&mut self) -> anyhow::Result<Expression> {
-        return self.parse_logical_binary_operations()
+        return self.parse_operators(0)
     }
 
-    fn parse_logical_binary_operations(&mut self) -> anyhow::Result<Expression> {
-        let mut lhs = self.parse_logical_negation()?;
+    /// Precedence climbing over `OPERATOR_TABLE`: parse an operand and then every operator
+    /// after it that binds at least as tightly as `min_precedence`.
+    fn parse_operators(&mut self, min_precedence: u8) -> anyhow::Result<Expression> {
+        let start = self.tokens.current().span();
+        let mut lhs = match operator_table::prefix(self.tokens.current().kind()) {
+            Some((operator, precedence)) => {
+                self.tokens.next();
+                let rhs = self.parse_operators(precedence)?;
+                UnaryOperation {
+                    operator,
+                    rhs: Box::new(rhs),
+                    span: self.span_from(start),
+                }
+            }
+            None => self.parse_postfix()?
+        };
+
         loop {
-            let operator = if self.tokens.t_match(PipePie).is_some() {
-                BinaryOperator::Or
-            } else if self.tokens.t_match(AndAnd).is_some() {
-                BinaryOperator::And
-            } else {
+            let Some((fixity, precedence)) = operator_table::after_operand(self.tokens.current().kind()) else {
                 break
             };
-
-            let rhs = self.parse_logical_negation()?;
-            lhs = BinaryOperation {
-                lhs: Box::new(lhs),
-                operator,
-                rhs: Box::new(rhs),
+            if precedence < min_precedence {
+                break
+            }
+            self.tokens.next();
+
+            lhs = match fixity {
+                Fixity::Infix(operator, associativity) => {
+                    let rhs = match associativity {
+                        Associativity::Left => self.parse_operators(precedence + 1)?,
+                        Associativity::Right => self.parse_operators(precedence)?,
+                    };
+                    BinaryOperation {
+                        span: self.span_from(start),
+                        lhs: Box::new(lhs),
+                        operator,
+                        rhs: Box::new(rhs),
+                    }
+                }
+                Fixity::Postfix(operator) => UnaryOperation {
+                    operator,
+                    rhs: Box::new(lhs),
+                    span: self.span_from(start),
+                },
+                Fixity::Prefix(_) => unreachable!("after_operand never returns a prefix operator")
             }
         }
         return Ok(lhs)
     }
 
-    fn parse_logical_negation(&mut self) -> anyhow::Result<Expression>{
-        if self.tokens.t_match(Bang).is_some() {
-            return Ok(UnaryOperation {
-                operator: UnaryOperator::Not,
-                rhs: Box::new(self.parse_equality()?),
-            })
-        }
-
-        self.parse_equality()
-    }
+    /// `pattern => body` arms up to the closing `}` of a `match`. The body is a block or a
+    /// single expression, which needs a comma after it unless it is the last arm.
+    fn parse_match_arms(&mut self) -> anyhow::Result<Vec<MatchArm>> {
+        let mut arms = vec![];
+        while self.tokens.t_match(RCurlyBrace).is_none() {
+            let start = self.tokens.current().span();
+            let pattern = self.parse_pattern()?;
 
-    fn parse_equality(&mut self) -> anyhow::Result<Expression> {
-        let mut lhs = self.parse_comparison()?;
-        loop {
-            if !self.tokens.t_match(EqualsEquals).is_some() {
-                break
+            if self.tokens.t_match(FatArrow).is_none() {
+                return Err(self.unexpected_token(Some(FatArrow)))
+                    .context("Expected => after the pattern of a match arm.")
             }
 
-            let rhs = self.parse_comparison()?;
-            lhs = BinaryOperation {
-                lhs: Box::new(lhs),
-                operator: BinaryOperator::Equals,
-                rhs: Box::new(rhs),
-            }
+            let body = if self.tokens.expect(LCurlyBrace).is_some() {
+                let body = self.parse_block()?;
+                self.tokens.t_match(Comma);
+                body
+            } else {
+                let body = vec![Statement::Expression(self.parse_expr()?)];
+                if self.tokens.t_match(Comma).is_none() && self.tokens.expect(RCurlyBrace).is_none() {
+                    return Err(self.unexpected_token(Some(Comma)))
+                        .context("Expected a comma after the body of a match arm.")
+                }
+                body
+            };
+
+            arms.push(MatchArm { pattern, body, span: self.span_from(start) });
         }
-        return Ok(lhs)
+        Ok(arms)
     }
 
-    fn parse_comparison(&mut self) -> anyhow::Result<Expression> {
-        let mut lhs = self.parse_add()?;
-        loop {
-            if !self.tokens.t_match(RAngleBracket).is_some() {
-                break
+    fn parse_pattern(&mut self) -> anyhow::Result<Pattern> {
+        let start = self.tokens.current().span();
+        let token = self.tokens.next().unwrap();
+        match token.kind() {
+            Int => Ok(Pattern::Literal(Expression::Int(token))),
+            True => Ok(Pattern::Literal(Expression::Bool(true, token))),
+            False => Ok(Pattern::Literal(Expression::Bool(false, token))),
+            Char => Ok(Pattern::Literal(Expression::Char(token))),
+            Minus if self.tokens.expect(Int).is_some() => {
+                let int = self.tokens.next().unwrap();
+                Ok(Pattern::Literal(UnaryOperation {
+                    operator: UnaryOperator::Sub,
+                    rhs: Box::new(Expression::Int(int)),
+                    span: self.span_from(start),
+                }))
             }
-
-            let rhs = self.parse_add()?;
-            lhs = BinaryOperation {
-                lhs: Box::new(lhs),
-                operator: GreaterThan,
-                rhs: Box::new(rhs),
+            Name if token.lexeme() == "_" => Ok(Pattern::Wildcard(token)),
+            Name if self.tokens.t_match(ColonColon).is_some() => {
+                let Some(variant) = self.tokens.t_match(Name) else {
+                    return Err(self.invalid_name()).context("Expected the name of a variant after ::.")
+                };
+                let fields = if self.tokens.t_match(LParen).is_some() {
+                    self.parse_list(Self::parse_pattern, RParen)?
+                } else {
+                    vec![]
+                };
+                Ok(Pattern::Variant { enum_name: token, variant, fields, span: self.span_from(start) })
+            }
+            Name => Ok(Pattern::Binding(token)),
+            _ => {
+                self.tokens.rewind();
+                Err(self.unexpected_token(None)).context("Expected a pattern.")
             }
         }
-        return Ok(lhs)
     }
 
-    fn parse_add(&mut self) -> anyhow::Result<Expression> {
-        let mut lhs = self.parse_unary_sub()?;
+    /// An atom followed by any field accesses on it, like `a.b.c`, tuple elements, like `t.0`,
+    /// indices, like `xs[i]`, calls, like `f(1)(2)`, or method calls, like `p.show()`.
+    fn parse_postfix(&mut self) -> anyhow::Result<Expression> {
+        let start = self.tokens.current().span();
+        let mut expr = self.parse_atom()?;
         loop {
-            if !self.tokens.t_match(Plus).is_some() {
+            if self.tokens.t_match(LBracket).is_some() {
+                let index = self.with_struct_literals(true, Self::parse_expr)?;
+                if self.tokens.t_match(RBracket).is_none() {
+                    return Err(self.unexpected_token(Some(RBracket)))
+                        .context("Expected a ] after an index.")
+                }
+                expr = Expression::Index {
+                    of: Box::new(expr),
+                    index: Box::new(index),
+                    span: self.span_from(start),
+                };
+                continue
+            }
+            if self.tokens.t_match(LParen).is_some() {
+                let arguments = self.with_struct_literals(true, |parser| parser.parse_list(Self::parse_expr, RParen))?;
+                expr = Expression::Call {
+                    callee: Box::new(expr),
+                    arguments,
+                    span: self.span_from(start),
+                };
+                continue
+            }
+            if self.tokens.t_match(Dot).is_none() {
                 break
             }
 
-            let rhs = self.parse_unary_sub()?;
-            lhs = BinaryOperation {
-                lhs: Box::new(lhs),
-                operator: Add,
-                rhs: Box::new(rhs),
+            if let Some(method) = self.tokens.t_match(Name) {
+                if self.tokens.t_match(LParen).is_some() {
+                    let arguments = self.with_struct_literals(true, |parser| parser.parse_list(Self::parse_expr, RParen))?;
+                    expr = Expression::MethodCall {
+                        receiver: Box::new(expr),
+                        method,
+                        arguments,
+                        span: self.span_from(start),
+                    };
+                    continue
+                }
+                self.tokens.rewind();
+            }
+
+            let fields = if let Some(field) = self.tokens.t_match(Name).or_else(|| self.tokens.t_match(Int)) {
+                vec![field]
+            } else if let Some(fields) = self.tokens.expect(Float).and_then(|x| Self::split_tuple_indices(x)) {
+                self.tokens.next();
+                fields
+            } else {
+                return Err(self.invalid_name()).context("Expected the name of a field or the index of an element after a dot.")
+            };
+
+            for field in fields {
+                expr = Expression::FieldAccess {
+                    of: Box::new(expr),
+                    span: start.to(field.span()),
+                    field,
+                };
             }
         }
-        return Ok(lhs)
+        Ok(expr)
     }
 
-    fn parse_unary_sub(&mut self) -> anyhow::Result<Expression> {
-        if self.tokens.t_match(Minus).is_some() {
-            return Ok(UnaryOperation {
-                operator: UnaryOperator::Sub,
-                rhs: Box::new(self.parse_atom()?),
-            })
+    /// `t.0.1` lexes as `t`, `.` and the float `0.1`, which is really two tuple indices.
+    fn split_tuple_indices(float: &Token) -> Option<Vec<Token>> {
+        let (first, second) = float.lexeme().split_once('.')?;
+        let digits = |x: &str| !x.is_empty() && x.bytes().all(|x| x.is_ascii_digit());
+        if !digits(first) || !digits(second) {
+            return None
         }
 
-        self.parse_atom()
+        let start = float.span().start;
+        let dot = Location::new(start.offset() + first.len(), start.row(), start.column() + first.len());
+        let after_dot = Location::new(dot.offset() + 1, dot.row(), dot.column() + 1);
+        Some(vec![
+            Token::new(Int, Span::new(start, dot), first),
+            Token::new(Int, Span::new(after_dot, float.span().end), second),
+        ])
     }
 
     fn parse_atom(&mut self) -> anyhow::Result<Expression> {
         let token = self.tokens.next().unwrap();
         return match token.kind() {
             LParen => {
-                let inside = self.parse_expr()?;
+                let inside = self.with_struct_literals(true, Self::parse_expr)?;
+                if self.tokens.t_match(Comma).is_some() {
+                    let rest = self.with_struct_literals(true, |parser| parser.parse_list(Self::parse_expr, RParen))?;
+                    let elements = [inside].into_iter().chain(rest).collect();
+                    return Ok(Expression::Tuple(elements, self.span_from(token.span())))
+                }
                 if self.tokens.t_match(RParen).is_none() {
                     return Err(self.unexpected_token(Some(RParen)))
                         .context("Expected a closing parentheses to an expression.")
@@ -341,30 +903,88 @@ impl Parser {
                 Ok(inside)
             }
             LBracket => {
-                let insides = self.parse_list(Self::parse_expr, RBracket)?;
+                let insides = self.with_struct_literals(true, |parser| parser.parse_list(Self::parse_expr, RBracket))?;
 
-                Ok(List(insides))
+                Ok(List(insides, self.span_from(token.span())))
             }
             Int => {
                 Ok(Expression::Int(token))
             }
+            Float => {
+                Ok(Expression::Float(token))
+            }
             True => {
                 Ok(Expression::Bool(true, token))
             }
             False => {
                 Ok(Expression::Bool(false, token))
             }
+            Str => {
+                Ok(Expression::Str(token))
+            }
+            Char => {
+                Ok(Expression::Char(token))
+            }
             Name => {
                 let name = token;
                 if self.tokens.t_match(LParen).is_some() {
-                    let arguments = self.parse_list(Self::parse_expr, RParen)?;
-                    return Ok(Expression::FunctionCall { name, arguments })
+                    let arguments = self.with_struct_literals(true, |parser| parser.parse_list(Self::parse_expr, RParen))?;
+                    let span = self.span_from(name.span());
+                    return Ok(Expression::FunctionCall { name, arguments, span })
+                }
+                if self.tokens.t_match(ColonColon).is_some() {
+                    let Some(variant) = self.tokens.t_match(Name) else {
+                        return Err(self.invalid_name()).context("Expected the name of a variant after ::.")
+                    };
+                    let payload = if self.tokens.t_match(LParen).is_some() {
+                        self.with_struct_literals(true, |parser| parser.parse_list(Self::parse_expr, RParen))?
+                    } else {
+                        vec![]
+                    };
+                    let span = self.span_from(name.span());
+                    return Ok(Expression::EnumVariant { enum_name: name, variant, payload, span })
+                }
+                if self.struct_literals && self.tokens.t_match(LCurlyBrace).is_some() {
+                    let fields = self.parse_list(|parser| {
+                        let Some(field) = parser.tokens.t_match(Name) else {
+                            return Err(parser.invalid_name()).context("Expected the name of a field in a struct literal.")
+                        };
+
+                        if parser.tokens.t_match(Colon).is_none() {
+                            return Err(parser.unexpected_token(Some(Colon)))
+                                .context("Expected a colon after the name of a field in a struct literal.")
+                        }
+
+                        Ok((field, parser.parse_expr()?))
+                    }, RCurlyBrace)?;
+                    let span = self.span_from(name.span());
+                    return Ok(Expression::StructLiteral { name, fields, span })
                 }
 
                 Ok(Expression::Name(name))
             },
+            Pipe => {
+                let params = self.parse_list(Self::parse_parameter, Pipe)?;
+                self.parse_lambda(token.span(), params)
+            }
+            PipePie => self.parse_lambda(token.span(), vec![]),
+            Match => {
+                let scrutinee = self.with_struct_literals(false, Self::parse_expr)?;
+
+                if self.tokens.t_match(LCurlyBrace).is_none() {
+                    return Err(self.unexpected_token(Some(LCurlyBrace)))
+                        .context("Expected a { after the value of a match.")
+                }
+
+                let arms = self.with_struct_literals(true, Self::parse_match_arms)?;
+                Ok(Expression::Match {
+                    scrutinee: Box::new(scrutinee),
+                    arms,
+                    span: self.span_from(token.span()),
+                })
+            }
             If => {
-                let condition = self.parse_expr()?;
+                let condition = self.with_struct_literals(false, Self::parse_expr)?;
 
                 let true_block = self.parse_block()?;
 
@@ -374,6 +994,7 @@ impl Parser {
                         condition: Box::new(condition),
                         true_block,
                         else_block: None,
+                        span: self.span_from(token.span()),
                     });
                 }
 
@@ -384,16 +1005,21 @@ impl Parser {
                         condition: Box::new(condition),
                         true_block,
                         else_block: Some(else_block),
+                        span: self.span_from(token.span()),
                     })
                 } else {
+                    let else_block = self.parse_block()?;
                     Ok(Expression::If {
                         condition: Box::new(condition),
                         true_block,
-                        else_block: Some(self.parse_block()?),
+                        else_block: Some(else_block),
+                        span: self.span_from(token.span()),
                     })
                 }
             }
             _ => {
+                // leave the token for error recovery to look at
+                self.tokens.rewind();
                 Err(self.unexpected_token(None))
                 .context(
                     format!(
@@ -406,13 +1032,32 @@ impl Parser {
         }
     }
 
-    pub fn parse(mut self) -> anyhow::Result<Vec<Statement>>{
+    pub fn parse(self) -> anyhow::Result<Vec<Statement>>{
+        let (statements, errors) = self.parse_recovering();
+        if !errors.is_empty() {
+            return Err(ParserErrors(errors).into())
+        }
+
+        return Ok(statements)
+    }
+
+    /// Parse as much of the program as possible, returning every statement that parsed along
+    /// with every syntax error found on the way.
+    pub fn parse_recovering(mut self) -> (Vec<Statement>, Vec<ParserError>) {
         let mut statements = vec![];
         while !self.tokens.empty() {
-            statements.push(self.parse_statement()?);
+            // a stray } at the top level has no block to close
+            if self.tokens.expect(RCurlyBrace).is_some() {
+                self.errors.push(self.unexpected_token(None));
+                self.tokens.next();
+                continue
+            }
+            if let Some(statement) = self.parse_statement_recovering() {
+                statements.push(statement)
+            }
         }
 
-        return Ok(statements)
+        return (statements, self.errors)
     }
 }
 
@@ -429,175 +1074,222 @@ mod tests {
     #[test]
     fn one() {
         let text = "1;";
-        let ast = Parser::new(text).parse().unwrap();
+        let ast = Parser::new(text).unwrap().parse().unwrap();
         assert_eq!(to_s_expr(ast), vec![SExpr::parse("1")])
     }
 
     #[test]
     fn one_element_list() {
         let text = "[1];";
-        let ast = Parser::new(text).parse().unwrap();
+        let ast = Parser::new(text).unwrap().parse().unwrap();
         assert_eq!(to_s_expr(ast), vec![SExpr::parse("(list 1)")])
     }
 
     #[test]
     fn two_element_list() {
         let text = "[1, 2];";
-        let ast = Parser::new(text).parse().unwrap();
+        let ast = Parser::new(text).unwrap().parse().unwrap();
         assert_eq!(to_s_expr(ast), vec![SExpr::parse("(list 1 2)")])
     }
 
     #[test]
     fn two_element_list_trailing_comma() {
         let text = "[1, 2, ];";
-        let ast = Parser::new(text).parse().unwrap();
+        let ast = Parser::new(text).unwrap().parse().unwrap();
         assert_eq!(to_s_expr(ast), vec![SExpr::parse("(list 1 2)")])
     }
 
     #[test]
     fn expr_list() {
         let text = "[1, 2, 3 + 4];";
-        let ast = Parser::new(text).parse().unwrap();
+        let ast = Parser::new(text).unwrap().parse().unwrap();
         assert_eq!(to_s_expr(ast), vec![SExpr::parse("(list 1 2 (+ 3 4))")])
     }
 
     #[test]
     fn expr_list_after() {
         let text = "[1, 2, 3 + 4] + 5;";
-        let ast = Parser::new(text).parse().unwrap();
+        let ast = Parser::new(text).unwrap().parse().unwrap();
         assert_eq!(to_s_expr(ast), vec![SExpr::parse("(+ (list 1 2 (+ 3 4)) 5)")])
     }
 
     #[test]
     fn neg_one() {
         let text = "-1;";
-        let ast = Parser::new(text).parse().unwrap();
+        let ast = Parser::new(text).unwrap().parse().unwrap();
         assert_eq!(to_s_expr(ast), vec![SExpr::parse("(- 1)")])
     }
 
     #[test]
     fn plus_neg() {
         let text = "20 + -1;";
-        let ast = Parser::new(text).parse().unwrap();
+        let ast = Parser::new(text).unwrap().parse().unwrap();
         assert_eq!(to_s_expr(ast), vec![SExpr::parse("(+ 20 (- 1))")])
     }
 
     #[test]
     fn equals() {
         let text = "20 == 4;";
-        let ast = Parser::new(text).parse().unwrap();
+        let ast = Parser::new(text).unwrap().parse().unwrap();
         assert_eq!(to_s_expr(ast), vec![SExpr::parse("(== 20 4)")])
     }
 
     #[test]
     fn and() {
         let text = "42 && 13;";
-        let ast = Parser::new(text).parse().unwrap();
+        let ast = Parser::new(text).unwrap().parse().unwrap();
         assert_eq!(to_s_expr(ast), vec![SExpr::parse("(&& 42 13)")])
     }
 
     #[test]
     fn return_and() {
         let text = "return 42 && 13;";
-        let ast = Parser::new(text).parse().unwrap();
+        let ast = Parser::new(text).unwrap().parse().unwrap();
         assert_eq!(to_s_expr(ast), vec![SExpr::parse("(return (&& 42 13))")])
     }
 
     #[test]
     fn simple_math() {
         let text = "1 + 2;";
-        let ast = Parser::new(text).parse().unwrap();
+        let ast = Parser::new(text).unwrap().parse().unwrap();
         assert_eq!(to_s_expr(ast), vec![SExpr::parse("(+ 1 2)")])
     }
 
     #[test]
     fn ordered_math() {
         let text = "1 + 2 + 3;";
-        let ast = Parser::new(text).parse().unwrap();
+        let ast = Parser::new(text).unwrap().parse().unwrap();
         assert_eq!(to_s_expr(ast), vec![SExpr::parse("(+ (+ 1 2) 3)")])
     }
 
     #[test]
     fn ordered_math_long() {
         let text = "1 + 2 + 3 + 4;";
-        let ast = Parser::new(text).parse().unwrap();
+        let ast = Parser::new(text).unwrap().parse().unwrap();
         assert_eq!(to_s_expr(ast), vec![SExpr::parse("(+ (+ (+ 1 2) 3) 4)")])
     }
 
+    #[test]
+    fn arithmetic_precedence() {
+        let text = "1 - 2 * 3 + 4 / -5 % 6 > 7 - 8;";
+        let ast = Parser::new(text).unwrap().parse().unwrap();
+        assert_eq!(to_s_expr(ast), vec![SExpr::parse("(> (+ (- 1 (* 2 3)) (% (/ 4 (- 5)) 6)) (- 7 8))")])
+    }
+
+    #[test]
+    fn subtraction_is_left_associative() {
+        let text = "10 - 4 - 3; 8 / 4 / 2;";
+        let ast = Parser::new(text).unwrap().parse().unwrap();
+        assert_eq!(to_s_expr(ast), vec![SExpr::parse("(- (- 10 4) 3)"), SExpr::parse("(/ (/ 8 4) 2)")])
+    }
+
+    #[test]
+    fn comparison_operators() {
+        let text = "1 < 2 != 3 >= 4 == 5 <= 6 > 7;";
+        let ast = Parser::new(text).unwrap().parse().unwrap();
+        assert_eq!(to_s_expr(ast), vec![SExpr::parse("(== (!= (< 1 2) (>= 3 4)) (> (<= 5 6) 7))")])
+    }
+
+    #[test]
+    fn and_binds_tighter_than_or() {
+        let text = "a || b && c || d && e;";
+        let ast = Parser::new(text).unwrap().parse().unwrap();
+        assert_eq!(to_s_expr(ast), vec![SExpr::parse("(|| (|| a (&& b c)) (&& d e))")])
+    }
+
+    #[test]
+    fn unary_binds_tightly() {
+        let text = "!a == b; -x * -y; - -1; !!a && b;";
+        let ast = Parser::new(text).unwrap().parse().unwrap();
+        assert_eq!(to_s_expr(ast), vec![
+            SExpr::parse("(== (! a) b)"),
+            SExpr::parse("(* (- x) (- y))"),
+            SExpr::parse("(- (- 1))"),
+            SExpr::parse("(&& (! (! a)) b)"),
+        ])
+    }
+
+    #[test]
+    fn parenthesised_operators() {
+        let text = "!(a == b) && (1 + 2) * 3 > 4;";
+        let ast = Parser::new(text).unwrap().parse().unwrap();
+        assert_eq!(to_s_expr(ast), vec![SExpr::parse("(&& (! (== a b)) (> (* (+ 1 2) 3) 4))")])
+    }
+
     #[test]
     fn var_decl() {
         let text = "let x: int = 4;";
-        let ast = Parser::new(text).parse().unwrap();
+        let ast = Parser::new(text).unwrap().parse().unwrap();
         assert_eq!(to_s_expr(ast), vec![SExpr::parse("(variable_declaration x:int 4)")])
     }
 
     #[test]
     fn var_assign() {
         let text = "x = 4;";
-        let ast = Parser::new(text).parse().unwrap();
+        let ast = Parser::new(text).unwrap().parse().unwrap();
         assert_eq!(to_s_expr(ast), vec![SExpr::parse("(= x 4)")])
     }
 
     #[test]
     fn while_2() {
         let text = "while x > 2 { 2; }";
-        let ast = Parser::new(text).parse().unwrap();
+        let ast = Parser::new(text).unwrap().parse().unwrap();
         assert_eq!(to_s_expr(ast), vec![SExpr::parse("(while (> x 2) (2))")])
     }
 
     #[test]
     fn nested_while_2() {
         let text = "while x > 2 { while y > 2 { 2 } }";
-        let ast = Parser::new(text).parse().unwrap();
+        let ast = Parser::new(text).unwrap().parse().unwrap();
         assert_eq!(to_s_expr(ast), vec![SExpr::parse("(while (> x 2) (while (> y 2) (2)))")])
     }
 
     #[test]
     fn while_2_semicolon_elided() {
         let text = "while x > 2 { 2 }";
-        let ast = Parser::new(text).parse().unwrap();
+        let ast = Parser::new(text).unwrap().parse().unwrap();
         assert_eq!(to_s_expr(ast), vec![SExpr::parse("(while (> x 2) (2))")])
     }
 
     #[test]
     fn return_2() {
         let text = "return 2;";
-        let ast = Parser::new(text).parse().unwrap();
+        let ast = Parser::new(text).unwrap().parse().unwrap();
         assert_eq!(to_s_expr(ast), vec![SExpr::parse("(return 2)")])
     }
 
     #[test]
     fn elided_semicolon_if() {
         let text = "if x { y; }";
-        let ast = Parser::new(text).parse().unwrap();
+        let ast = Parser::new(text).unwrap().parse().unwrap();
         assert_eq!(to_s_expr(ast), vec![SExpr::parse("(if x (y))")])
     }
 
     #[test]
     fn double_elided_semicolon() {
         let text = "if x { y }";
-        let ast = Parser::new(text).parse().unwrap();
+        let ast = Parser::new(text).unwrap().parse().unwrap();
         assert_eq!(to_s_expr(ast), vec![SExpr::parse("(if x (y))")])
     }
 
     #[test]
     fn nested_if() {
         let text = "if x { if y { z } }";
-        let ast = Parser::new(text).parse().unwrap();
+        let ast = Parser::new(text).unwrap().parse().unwrap();
         assert_eq!(to_s_expr(ast), vec![SExpr::parse("(if x (if y (z)))")])
     }
 
     #[test]
     fn paren_expr() {
         let text = "(1 + 2 + 3) + (1 + 2);";
-        let ast = Parser::new(text).parse().unwrap();
+        let ast = Parser::new(text).unwrap().parse().unwrap();
         assert_eq!(to_s_expr(ast), vec![SExpr::parse("(+ (+ (+ 1 2) 3) (+ 1 2))")])
     }
 
     #[test]
     fn two_statements() {
         let text = "2; 3;";
-        let ast = Parser::new(text).parse().unwrap();
+        let ast = Parser::new(text).unwrap().parse().unwrap();
         assert_eq!(to_s_expr(ast), vec![
             SExpr::parse("2"),
             SExpr::parse("3")
@@ -607,7 +1299,7 @@ mod tests {
     #[test]
     fn ret_ret() {
         let text = "return 2; return 3;";
-        let ast = Parser::new(text).parse().unwrap();
+        let ast = Parser::new(text).unwrap().parse().unwrap();
         assert_eq!(to_s_expr(ast), vec![
             SExpr::parse("(return 2)"),
             SExpr::parse("(return 3)")
@@ -617,28 +1309,28 @@ mod tests {
     #[test]
     fn func_call() {
         let text = "cat();";
-        let ast = Parser::new(text).parse().unwrap();
+        let ast = Parser::new(text).unwrap().parse().unwrap();
         assert_eq!(to_s_expr(ast), vec![SExpr::parse("(cat)")])
     }
 
     #[test]
     fn func_def_1() {
         let text = "fn func(a:int, b:bool) -> int {}";
-        let ast = Parser::new(text).parse().unwrap();
+        let ast = Parser::new(text).unwrap().parse().unwrap();
         assert_eq!(to_s_expr(ast), vec![SExpr::parse("(function_define func a:int b:bool (empty_block) ->int)")])
     }
 
     #[test]
     fn func_def_2() {
         let text = "fn func(a:int, b:bool) -> int { return a + b; }";
-        let ast = Parser::new(text).parse().unwrap();
+        let ast = Parser::new(text).unwrap().parse().unwrap();
         assert_eq!(to_s_expr(ast), vec![SExpr::parse("(function_define func a:int b:bool (return (+ a b)) ->int)")])
     }
 
     #[test]
     fn two_func_def_2() {
         let text = "fn func() -> int { return a; } fn func2() -> int { return a; }";
-        let ast = Parser::new(text).parse().unwrap();
+        let ast = Parser::new(text).unwrap().parse().unwrap();
         assert_eq!(to_s_expr(ast), vec![
             SExpr::parse("(function_define func (return a) ->int)"),
             SExpr::parse("(function_define func2 (return a) ->int)")
@@ -648,9 +1340,321 @@ mod tests {
     #[test]
     fn two_call() {
         let text = "return universe(20, 22);";
-        let ast = Parser::new(text).parse().unwrap();
+        let ast = Parser::new(text).unwrap().parse().unwrap();
         assert_eq!(to_s_expr(ast), vec![
             SExpr::parse("(return (universe 20 22))"),
         ]);
     }
+
+    #[test]
+    fn lex_errors_reported() {
+        let text = "let x: int = 4 @ 2; $";
+        let errors = Parser::new(text).err().unwrap();
+        assert_eq!(errors.0.len(), 2);
+        assert_eq!(errors.to_string(), "Unrecognized character '@' at (1, 16).\nUnrecognized character '$' at (1, 21).");
+    }
+
+    #[test]
+    fn expression_spans() {
+        let text = "return (1 + 2) + -foo(3, [4]);";
+        let ast = Parser::new(text).unwrap().parse().unwrap();
+        assert_eq!(ast[0].span().byte_range(), 0..29);
+
+        let Statement::Return(expr, _) = &ast[0] else { panic!() };
+        assert_eq!(&text[expr.span().byte_range()], "(1 + 2) + -foo(3, [4])");
+
+        let Expression::BinaryOperation { lhs, rhs, .. } = expr else { panic!() };
+        assert_eq!(&text[lhs.span().byte_range()], "1 + 2");
+        assert_eq!(&text[rhs.span().byte_range()], "-foo(3, [4])");
+    }
+
+    #[test]
+    fn statement_spans() {
+        let text = "fn f(a: int) -> int {\n    let x: int = a;\n    x = if a > 1 { 2 } else { 3 };\n    while x > 0 { x = x + -1; }\n}";
+        let ast = Parser::new(text).unwrap().parse().unwrap();
+        assert_eq!(ast[0].span().byte_range(), 0..text.len());
+
+        let Statement::FunctionDefinitionStatement(def) = &ast[0] else { panic!() };
+        let spanned: Vec<&str> = def.body.iter().map(|x| &text[x.span().byte_range()]).collect();
+        assert_eq!(spanned, vec![
+            "let x: int = a",
+            "x = if a > 1 { 2 } else { 3 }",
+            "while x > 0 { x = x + -1; }",
+        ]);
+        assert_eq!(def.body[1].span().start.row(), 3);
+        assert_eq!(def.body[1].span().start.column(), 5);
+    }
+
+    #[test]
+    fn str_decl() {
+        let text = r#"let s: str = "hi" + "!";"#;
+        let ast = Parser::new(text).unwrap().parse().unwrap();
+        assert_eq!(to_s_expr(ast), vec![SExpr::parse(r#"(variable_declaration s:str (+ "hi" "!"))"#)])
+    }
+
+    #[test]
+    fn float_decl() {
+        let text = "let f: float = 1.5 + -2e10;";
+        let ast = Parser::new(text).unwrap().parse().unwrap();
+        assert_eq!(to_s_expr(ast), vec![SExpr::parse("(variable_declaration f:float (+ 1.5 (- 2e10)))")])
+    }
+
+    #[test]
+    fn char_decl() {
+        let text = r"let c: char = chr(ord('\n') + 1) == 'b';";
+        let ast = Parser::new(text).unwrap().parse().unwrap();
+        assert_eq!(to_s_expr(ast), vec![SExpr::parse(r"(variable_declaration c:char (== (chr (+ (ord '\n') 1)) 'b'))")])
+    }
+
+    #[test]
+    fn for_loops() {
+        let text = "for i in 0..n + 1 { x = x + i; } for i in 1..=3 {} for c in cs { f(c); }";
+        let ast = Parser::new(text).unwrap().parse().unwrap();
+        assert_eq!(to_s_expr(ast), vec![
+            SExpr::parse("(for i (.. 0 (+ n 1)) (= x (+ x i)))"),
+            SExpr::parse("(for i (..= 1 3) (empty_block))"),
+            SExpr::parse("(for c cs (f c))"),
+        ])
+    }
+
+    #[test]
+    fn loop_control() {
+        let text = "'outer: while true { for i in 0..3 { if i == 1 { continue; } break 'outer; } continue 'outer; }";
+        let ast = Parser::new(text).unwrap().parse().unwrap();
+        assert_eq!(to_s_expr(ast), vec![
+            SExpr::parse("(while 'outer true (for i (.. 0 3) (if (== i 1) (continue) (break 'outer)) (continue 'outer)))"),
+        ]);
+
+        let errors = Parser::new("'outer: x = 1;").unwrap().parse_recovering().1;
+        assert!(errors[0].to_string().contains("Only loops can be labelled."));
+    }
+
+    #[test]
+    fn compound_assignment() {
+        let text = "x += 1; x -= 2 * y; x *= 3; x /= 4; x %= 5; b &&= c; b ||= !c;";
+        let ast = Parser::new(text).unwrap().parse().unwrap();
+        assert_eq!(to_s_expr(ast), vec![
+            SExpr::parse("(+= x 1)"),
+            SExpr::parse("(-= x (* 2 y))"),
+            SExpr::parse("(*= x 3)"),
+            SExpr::parse("(/= x 4)"),
+            SExpr::parse("(%= x 5)"),
+            SExpr::parse("(&&= b c)"),
+            SExpr::parse("(||= b (! c))"),
+        ]);
+    }
+
+    #[test]
+    fn structs() {
+        let text = "struct Point { x: int, y: Point } let p: Point = Point { x: 1, y: q.y }; p.y.x = -p.x; p.x += 1;";
+        let ast = Parser::new(text).unwrap().parse().unwrap();
+        assert_eq!(to_s_expr(ast), vec![
+            SExpr::parse("(struct Point x:int y:Point)"),
+            SExpr::parse("(variable_declaration p:Point (Point (x 1) (y (. q y))))"),
+            SExpr::parse("(= p.y.x (- (. p x)))"),
+            SExpr::parse("(+= p.x 1)"),
+        ]);
+    }
+
+    #[test]
+    fn no_struct_literals_before_blocks() {
+        let text = "if p { x = 1; } while p.x { } for i in xs { } if (P { x: 1 }).x == f(P { x: 2 }) { }";
+        let ast = Parser::new(text).unwrap().parse().unwrap();
+        assert_eq!(to_s_expr(ast), vec![
+            SExpr::parse("(if p (= x 1))"),
+            SExpr::parse("(while (. p x) (empty_block))"),
+            SExpr::parse("(for i xs (empty_block))"),
+            SExpr::parse("(if (== (. (P (x 1)) x) (f (P (x 2)))) (empty_block))"),
+        ]);
+    }
+
+    #[test]
+    fn enums_and_match() {
+        let text = "enum Shape { Circle(int), Rect(int, int), Empty }
+let s: Shape = Shape::Rect(1, 2);
+match s { Shape::Circle(r) => r * r, Shape::Rect(w, _) => { w; } _ => 0 }
+match x { -1 => true, 'a' => false, y => y == 1, }";
+        let ast = Parser::new(text).unwrap().parse().unwrap();
+        assert_eq!(to_s_expr(ast), vec![
+            SExpr::parse("(enum Shape (Circle int) (Rect int int) (Empty))"),
+            SExpr::parse("(variable_declaration s:Shape (Shape::Rect 1 2))"),
+            SExpr::parse("(match s (=> (Shape::Circle r) (* r r)) (=> (Shape::Rect w _) (w)) (=> _ (0)))"),
+            SExpr::parse("(match x (=> (- 1) (true)) (=> 'a' (false)) (=> y (== y 1)))"),
+        ]);
+    }
+
+    #[test]
+    fn tuples() {
+        let text = "let (q, _): (int, (bool, char)) = (1, (true, 'a'),); t.1.0 = t.0; (1); (1,); f((1, 2)).1;";
+        let ast = Parser::new(text).unwrap().parse().unwrap();
+        let SExpr::Function(_, declaration) = ast[0].clone().to_s_expr() else { panic!() };
+        assert_eq!(declaration[0], SExpr::Value(String::from("(q, _):(int, (bool, char))")));
+        assert_eq!(to_s_expr(ast[1..].to_vec()), vec![
+            SExpr::parse("(= t.1.0 (. t 0))"),
+            SExpr::parse("1"),
+            SExpr::parse("(tuple 1)"),
+            SExpr::parse("(. (f (tuple 1 2)) 1)"),
+        ]);
+
+        // the float `1.0` is two indices, each keeping its own place in the source
+        let Statement::Expression(Expression::FieldAccess { of, field, span }) = Parser::new("t.1.0;").unwrap().parse().unwrap().remove(0) else { panic!() };
+        let Expression::FieldAccess { field: first, .. } = *of else { panic!() };
+        assert_eq!((first.lexeme(), first.span().byte_range()), ("1", 2..3));
+        assert_eq!((field.lexeme(), field.span().byte_range()), ("0", 4..5));
+        assert_eq!(span.byte_range(), 0..5);
+
+        assert!(Parser::new("let (a, 1): (int, int) = (1, 2);").unwrap().parse().is_err());
+        assert!(Parser::new("t.1e5;").unwrap().parse().is_err());
+    }
+
+    #[test]
+    fn indexing() {
+        let text = "xs[0]; xs[i + 1][j].a; f(x)[2]; xs[i] = 3; xs[0] += 1; grid[0][1] = 2;";
+        let ast = Parser::new(text).unwrap().parse().unwrap();
+        assert_eq!(to_s_expr(ast), vec![
+            SExpr::parse("(index xs 0)"),
+            SExpr::parse("(. (index (index xs (+ i 1)) j) a)"),
+            SExpr::parse("(index (f x) 2)"),
+            SExpr::parse("(= (index xs i) 3)"),
+            SExpr::parse("(+= (index xs 0) 1)"),
+            SExpr::parse("(= (index (index grid 0) 1) 2)"),
+        ]);
+
+        assert!(Parser::new("xs[0;").unwrap().parse().is_err());
+        assert!(Parser::new("xs[] = 1;").unwrap().parse().is_err());
+    }
+
+    #[test]
+    fn lambdas() {
+        let text = "let f: fn(int, bool) -> fn() -> int = |x: int, b: bool| -> fn() -> int { || -> int { x } }; f(1, true)(); (|x: int| -> int { x })(2);";
+        let ast = Parser::new(text).unwrap().parse().unwrap();
+        let Statement::VariableDeclaration { type_, value, .. } = &ast[0] else { panic!() };
+        assert_eq!(type_.to_string(), "fn(int, bool) -> fn() -> int");
+        let Expression::Lambda { params, return_type, body, .. } = value else { panic!() };
+        assert_eq!(params.iter().map(|(name, type_)| format!("{}:{}", name.lexeme(), type_)).collect_vec(), vec!["x:int", "b:bool"]);
+        assert_eq!(return_type.to_string(), "fn() -> int");
+        assert!(matches!(&body[0], Statement::Expression(Expression::Lambda { params, .. }) if params.is_empty()));
+        assert_eq!(to_s_expr(ast[1..].to_vec()), vec![
+            SExpr::parse("(call (f 1 true))"),
+            SExpr::parse("(call (lambda x:int (x) ->int) 2)"),
+        ]);
+
+        assert!(Parser::new("|x| -> int { x };").unwrap().parse().is_err());
+        assert!(Parser::new("|x: int| { x };").unwrap().parse().is_err());
+        assert!(Parser::new("let f: fn(int) = 1;").unwrap().parse().is_err());
+    }
+
+    #[test]
+    fn generics() {
+        let text = "fn first<T, U>(a: T, b: U) -> T { return a; } fn id<T>(x: list T) -> list T { return x; } first(1, true);";
+        let ast = Parser::new(text).unwrap().parse().unwrap();
+        assert_eq!(to_s_expr(ast), vec![
+            SExpr::parse("(function_define first<T,U> a:T b:U (return a) ->T)"),
+            SExpr::parse("(function_define id<T> x:list<T> (return x) ->list<T>)"),
+            SExpr::parse("(first 1 true)"),
+        ]);
+
+        assert!(Parser::new("fn f<>(x: int) -> int { return x; }").unwrap().parse().is_ok());
+        assert!(Parser::new("fn f<1>(x: int) -> int { return x; }").unwrap().parse().is_err());
+        assert!(Parser::new("fn f<T(x: T) -> T { return x; }").unwrap().parse().is_err());
+    }
+
+    #[test]
+    fn traits() {
+        let text = "trait Show { fn show(self) -> int; fn same(self, other: Self) -> bool; }\
+            impl Show for Point { fn show(self) -> int { return self.x; } fn same(self, other: Self,) -> bool { return true; } }\
+            fn f<T: Show + Eq, U>(x: T) -> int { return x.show(); }\
+            p.show().same(1);";
+        let ast = Parser::new(text).unwrap().parse().unwrap();
+        assert_eq!(to_s_expr(ast), vec![
+            SExpr::parse("(trait Show (show self:Self ->int) (same self:Self other:Self ->bool))"),
+            SExpr::parse("(impl Show Point (function_define show self:Self (return (. self x)) ->int) (function_define same self:Self other:Self (return true) ->bool))"),
+            SExpr::parse("(function_define f<T:Show+Eq,U> x:T (return (method_call x show)) ->int)"),
+            SExpr::parse("(method_call (method_call p show) same 1)"),
+        ]);
+
+        assert!(Parser::new("trait A { fn f(x: int) -> int; }").unwrap().parse().is_err());
+        assert!(Parser::new("trait A { fn f(self) -> int { return 1; } }").unwrap().parse().is_err());
+        assert!(Parser::new("trait A { fn f<T>(self) -> int; }").unwrap().parse().is_err());
+        assert!(Parser::new("impl A Point { }").unwrap().parse().is_err());
+        assert!(Parser::new("impl A for Point { let x: int = 1; }").unwrap().parse().is_err());
+        assert!(Parser::new("fn f<T: >(x: T) -> T { return x; }").unwrap().parse().is_err());
+    }
+
+    #[test]
+    fn match_arms_need_commas() {
+        let text = "match x { 1 => 2 3 => 4 }";
+        let (_, errors) = Parser::new(text).unwrap().parse_recovering();
+        assert_eq!(error_locations(&errors)[0], (1, 18));
+    }
+
+    fn error_locations(errors: &[ParserError]) -> Vec<(usize, usize)> {
+        errors.iter()
+            .map(|x| (x.offending_token().location().row(), x.offending_token().location().column()))
+            .collect()
+    }
+
+    #[test]
+    fn several_errors_in_one_run() {
+        let text = "fn main() -> int {
+    let x: int = ;
+    let y: int 2;
+    return x + y;
+}
+
+fn broken( -> int { return 1; }
+
+fn other() -> bool {
+    return 1 +;
+}";
+        let (ast, errors) = Parser::new(text).unwrap().parse_recovering();
+        assert_eq!(error_locations(&errors), vec![(2, 18), (3, 16), (7, 12), (10, 15)]);
+
+        // the functions around the errors still parse, minus their broken statements
+        assert_eq!(to_s_expr(ast), vec![
+            SExpr::parse("(function_define main (return (+ x y)) ->int)"),
+            SExpr::parse("(function_define other (empty_block) ->bool)"),
+        ]);
+    }
+
+    #[test]
+    fn recovery_at_top_level() {
+        let text = "let a: int = 1; ); let b: = 2; } let c: int = 3; fn";
+        let (ast, errors) = Parser::new(text).unwrap().parse_recovering();
+        assert_eq!(error_locations(&errors), vec![(1, 17), (1, 27), (1, 32), (1, 52)]);
+        assert_eq!(to_s_expr(ast), vec![
+            SExpr::parse("(variable_declaration a:int 1)"),
+            SExpr::parse("(variable_declaration c:int 3)"),
+        ]);
+    }
+
+    #[test]
+    fn recovery_inside_braces() {
+        let text = "fn main() -> int {
+    let p: int = match 1 { 1 => 2 3, _ => 0 };
+    let q: int = ;
+    return p;
+}";
+        let (_, errors) = Parser::new(text).unwrap().parse_recovering();
+        assert_eq!(error_locations(&errors), vec![(2, 35), (3, 18)]);
+
+        let (ast, errors) = Parser::new("struct P { x int, y: int } let a: int = 1;").unwrap().parse_recovering();
+        assert_eq!(error_locations(&errors), vec![(1, 14)]);
+        assert_eq!(to_s_expr(ast), vec![SExpr::parse("(variable_declaration a:int 1)")]);
+    }
+
+    #[test]
+    fn unclosed_block() {
+        let text = "fn main() -> int { return 1;";
+        let (_, errors) = Parser::new(text).unwrap().parse_recovering();
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].to_string().contains("Blocks must end with a }"));
+    }
+
+    #[test]
+    fn parse_reports_every_error() {
+        let error = Parser::new("1 +; 2 +;").unwrap().parse().unwrap_err();
+        let errors = error.downcast::<ParserErrors>().unwrap();
+        assert_eq!(error_locations(&errors.0), vec![(1, 4), (1, 9)]);
+    }
 }
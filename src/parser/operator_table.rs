@@ -0,0 +1,74 @@
+use crate::ast::binary_operator::BinaryOperator;
+use crate::ast::unary_operator::UnaryOperator;
+use crate::parser::token_kind::TokenKind;
+
+type T = TokenKind;
+type B = BinaryOperator;
+type U = UnaryOperator;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[allow(dead_code)] // every infix operator so far is left associative
+pub enum Associativity {
+    Left,
+    Right,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[allow(dead_code)] // nothing is postfix yet
+pub enum Fixity {
+    Prefix(UnaryOperator),
+    Infix(BinaryOperator, Associativity),
+    Postfix(UnaryOperator),
+}
+
+/// Every operator in an expression with how tightly it binds, higher binding tighter. A token
+/// may appear once as a prefix operator and once as an infix or postfix one, like `-`.
+pub const OPERATOR_TABLE: [(TokenKind, Fixity, u8); 15] = [
+    (T::PipePie, Fixity::Infix(B::Or, Associativity::Left), 1),
+    (T::AndAnd, Fixity::Infix(B::And, Associativity::Left), 2),
+    (T::EqualsEquals, Fixity::Infix(B::Equals, Associativity::Left), 3),
+    (T::BangEquals, Fixity::Infix(B::NotEquals, Associativity::Left), 3),
+    (T::RAngleBracket, Fixity::Infix(B::GreaterThan, Associativity::Left), 4),
+    (T::RAngleEquals, Fixity::Infix(B::GreaterEquals, Associativity::Left), 4),
+    (T::LAngleBracket, Fixity::Infix(B::LessThan, Associativity::Left), 4),
+    (T::LAngleEquals, Fixity::Infix(B::LessEquals, Associativity::Left), 4),
+    (T::Plus, Fixity::Infix(B::Add, Associativity::Left), 5),
+    (T::Minus, Fixity::Infix(B::Sub, Associativity::Left), 5),
+    (T::Star, Fixity::Infix(B::Mul, Associativity::Left), 6),
+    (T::Slash, Fixity::Infix(B::Div, Associativity::Left), 6),
+    (T::Percent, Fixity::Infix(B::Mod, Associativity::Left), 6),
+    (T::Minus, Fixity::Prefix(U::Sub), 7),
+    (T::Bang, Fixity::Prefix(U::Not), 7),
+];
+
+/// The prefix operator `kind` starts, and how tightly it binds.
+pub fn prefix(kind: TokenKind) -> Option<(UnaryOperator, u8)> {
+    OPERATOR_TABLE.iter().find_map(|(token, fixity, precedence)| match fixity {
+        Fixity::Prefix(operator) if *token == kind => Some((*operator, *precedence)),
+        _ => None
+    })
+}
+
+/// The infix or postfix operator `kind` is when it follows an operand.
+pub fn after_operand(kind: TokenKind) -> Option<(Fixity, u8)> {
+    OPERATOR_TABLE.iter().find_map(|(token, fixity, precedence)| match fixity {
+        Fixity::Infix(..) | Fixity::Postfix(_) if *token == kind => Some((*fixity, *precedence)),
+        _ => None
+    })
+}
+
+/// Assignments like `x += 1` that apply an operator to the variable and the value.
+pub const COMPOUND_ASSIGNMENT_TABLE: [(TokenKind, BinaryOperator); 7] = [
+    (T::PlusEquals, B::Add),
+    (T::MinusEquals, B::Sub),
+    (T::StarEquals, B::Mul),
+    (T::SlashEquals, B::Div),
+    (T::PercentEquals, B::Mod),
+    (T::AndAndEquals, B::And),
+    (T::PipePipeEquals, B::Or),
+];
+
+/// The operator a compound assignment token applies.
+pub fn compound_assignment(kind: TokenKind) -> Option<BinaryOperator> {
+    COMPOUND_ASSIGNMENT_TABLE.iter().find_map(|(token, operator)| (*token == kind).then_some(*operator))
+}
@@ -1,6 +1,10 @@
 use std::error::Error;
 use std::fmt::{Display, Formatter};
+use itertools::Itertools;
+use thiserror::Error;
+use crate::diagnostic::{Diagnostic, ToDiagnostic};
 use crate::parser::token::Token;
+use crate::parser::token_kind::TokenKind;
 
 #[derive(Debug, Clone)]
 pub enum ParserErrorKind {
@@ -21,7 +25,8 @@ impl Display for ParserErrorKind {
 pub struct ParserError {
     kind: ParserErrorKind,
     offending_token: Token,
-    message: Option<String>
+    message: Option<String>,
+    context: Vec<String>
 }
 
 impl ParserError {
@@ -34,8 +39,25 @@ impl ParserError {
             kind,
             offending_token,
             message,
+            context: vec![],
         }
     }
+
+    pub fn offending_token(&self) -> &Token {
+        &self.offending_token
+    }
+
+    /// Recover the ParserError at the root of an error from the parser, keeping the context
+    /// it was given on the way up, outermost first. Any other error is reported at `current`,
+    /// the token the parser stopped on.
+    pub fn from_anyhow(error: anyhow::Error, current: &Token) -> Self {
+        let mut context = error.chain().map(|x| x.to_string()).collect_vec();
+        let root = context.pop();
+        let mut parser_error = error.downcast::<ParserError>()
+            .unwrap_or_else(|_| ParserError::new(ParserErrorKind::UnexpectedToken, current.clone(), root));
+        parser_error.context = context;
+        parser_error
+    }
 }
 
 impl Display for ParserError {
@@ -45,14 +67,68 @@ impl Display for ParserError {
             .map(|x| format!("\n\t{}", x))
             .unwrap_or_else(|| String::from(""));
 
-        write!(f, "[ERROR {}]: {} at ({}, {}){}",
+        let context = self.context
+            .iter()
+            .map(|x| format!("\n\t{}", x))
+            .join("");
+
+        write!(f, "[ERROR {}]: {} at ({}, {}){}{}",
                self.kind,
                self.offending_token.lexeme(),
                self.offending_token.location().row(),
                self.offending_token.location().column(),
+               context,
                message
         )
     }
 }
 
-impl Error for ParserError {}
\ No newline at end of file
+impl Error for ParserError {}
+
+impl ToDiagnostic for ParserError {
+    fn to_diagnostic(&self) -> Diagnostic {
+        let token = self.offending_token();
+        let found = match token.kind() {
+            TokenKind::EOI => String::from("end of input"),
+            _ => format!("`{}`", token.lexeme())
+        };
+
+        let diagnostic = match self.kind {
+            ParserErrorKind::UnexpectedToken => Diagnostic::error(format!("unexpected {}", found)),
+            ParserErrorKind::InvalidName => Diagnostic::error(format!("expected a name, found {}", found)),
+        };
+
+        let mut diagnostic = diagnostic.with_primary(token.span(), self.message.clone().unwrap_or_default());
+        for context in &self.context {
+            diagnostic = diagnostic.with_note(context);
+        }
+        diagnostic
+    }
+}
+
+/// Every syntax error found in a single pass of the parser.
+#[derive(Debug, Clone, Error)]
+#[error("{}", .0.iter().join("\n"))]
+pub struct ParserErrors(pub Vec<ParserError>);
+#[cfg(test)]
+mod tests {
+    use anyhow::Context;
+    use crate::parser::span::Span;
+    use super::*;
+
+    #[test]
+    fn from_anyhow() {
+        let token = Token::new(TokenKind::Semicolon, Span::default(), ";");
+        let error = ParserError::new(ParserErrorKind::InvalidName, token.clone(), None);
+        let error = ParserError::from_anyhow(Err::<(), _>(error).context("outer").unwrap_err(), &token);
+        assert!(matches!(error.kind, ParserErrorKind::InvalidName));
+        assert_eq!(error.context, vec!["outer"]);
+
+        // an error from outside the parser is put on the current token
+        let error = ParserError::from_anyhow(anyhow::anyhow!("inner").context("outer"), &token);
+        assert!(matches!(error.kind, ParserErrorKind::UnexpectedToken));
+        assert_eq!(error.offending_token().lexeme(), ";");
+        assert_eq!(error.message.as_deref(), Some("inner"));
+        assert_eq!(error.context, vec!["outer"]);
+    }
+}
@@ -0,0 +1,41 @@
+use std::fmt::{Display, Formatter};
+use std::ops::Range;
+use crate::parser::location::Location;
+
+/// A half-open stretch of source text: `start` is the first character covered and `end` is
+/// just past the last one.
+#[derive(Debug, Copy, Clone, Default, PartialOrd, PartialEq, Eq, Ord)]
+pub struct Span {
+    pub start: Location,
+    pub end: Location
+}
+
+#[allow(dead_code)]
+impl Span {
+    pub fn new(start: Location, end: Location) -> Self {
+        Self {
+            start,
+            end,
+        }
+    }
+
+    /// The smallest span covering both `self` and `other`.
+    pub fn to(self, other: Span) -> Self {
+        Self::new(self.start.min(other.start), self.end.max(other.end))
+    }
+
+    pub fn byte_range(&self) -> Range<usize> {
+        self.start.offset()..self.end.offset()
+    }
+
+    /// Is the byte `offset` inside this span? Used to map a cursor back to a node.
+    pub fn contains(&self, offset: usize) -> bool {
+        self.byte_range().contains(&offset)
+    }
+}
+
+impl Display for Span {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "({})-({})", self.start, self.end)
+    }
+}
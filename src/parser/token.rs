@@ -1,11 +1,13 @@
+use std::borrow::Cow;
 use std::fmt::{Display, Formatter};
 use crate::parser::location::Location;
+use crate::parser::span::Span;
 use crate::parser::token_kind::TokenKind;
 
 #[derive(Debug, Clone, PartialOrd, PartialEq, Eq, Ord)]
 pub struct Token {
     kind: TokenKind,
-    location: Location,
+    span: Span,
     lexeme: String
 }
 
@@ -16,15 +18,18 @@ impl Token {
         self.kind
     }
     pub fn location(&self) -> Location {
-        self.location
+        self.span.start
+    }
+    pub fn span(&self) -> Span {
+        self.span
     }
     pub fn lexeme(&self) -> &str {
         self.lexeme.as_str()
     }
-    pub fn new(kind: TokenKind, location: Location, lexeme: impl AsRef<str>) -> Self {
+    pub fn new(kind: TokenKind, span: Span, lexeme: impl AsRef<str>) -> Self {
         Self {
             kind,
-            location,
+            span,
             lexeme: lexeme.as_ref().to_string(),
         }
     }
@@ -32,7 +37,7 @@ impl Token {
     pub fn un_located(kind: TokenKind, lexeme: impl AsRef<str>) -> Self {
         Self {
             kind,
-            location: Location::new(0, 0),
+            span: Span::default(),
             lexeme: lexeme.as_ref().to_string(),
         }
     }
@@ -44,6 +49,44 @@ impl Token {
 
 impl Display for Token {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{:?}({})@{}", self.kind, self.lexeme, self.location)
+        write!(f, "{:?}({})@{}", self.kind, self.lexeme, self.location())
+    }
+}
+
+/// A token whose lexeme still points into the source text where it can. The lexer produces
+/// these and they only become a `Token` when something needs to keep them.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BorrowedToken<'a> {
+    kind: TokenKind,
+    span: Span,
+    lexeme: Cow<'a, str>
+}
+
+#[allow(dead_code)]
+impl<'a> BorrowedToken<'a> {
+    pub fn new(kind: TokenKind, span: Span, lexeme: Cow<'a, str>) -> Self {
+        Self {
+            kind,
+            span,
+            lexeme,
+        }
+    }
+
+    pub fn kind(&self) -> TokenKind {
+        self.kind
+    }
+    pub fn span(&self) -> Span {
+        self.span
+    }
+    pub fn lexeme(&self) -> &str {
+        &self.lexeme
+    }
+
+    pub fn into_owned(self) -> Token {
+        Token {
+            kind: self.kind,
+            span: self.span,
+            lexeme: self.lexeme.into_owned(),
+        }
     }
 }
\ No newline at end of file
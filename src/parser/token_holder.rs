@@ -2,6 +2,9 @@ use crate::parser::token::Token;
 use crate::parser::token_kind::TokenKind;
 use crate::parser::token_kind::TokenKind::EOI;
 
+/// Every token of the text, lexed before parsing starts, with the parser's place among them.
+/// Peeking with `current` and `expect` borrows; consuming a token with `t_match` or `next`
+/// clones it, since the holder keeps it for `previous` and `since`.
 #[derive(Debug)]
 pub struct TokenHolder {
     tokens: Vec<Token>,
@@ -9,19 +12,20 @@ pub struct TokenHolder {
 }
 #[allow(dead_code)]
 impl TokenHolder {
-    pub fn new(tokens: Vec<Token>) -> TokenHolder {
+    pub fn new(mut tokens: Vec<Token>) -> TokenHolder {
+        if tokens.last().map(|x| x.kind()) != Some(EOI) {
+            tokens.push(Token::un_located(EOI, ""));
+        }
+
         Self {
             tokens,
             index: 0,
         }
     }
 
-    pub fn current(&self) -> Token {
-        if let Some(t) = self.tokens.get(self.index) {
-            t.clone()
-        } else {
-            Token::un_located(EOI, "")
-        }
+    /// Peek at the current token. Past the end this keeps returning the final `EOI`.
+    pub fn current(&self) -> &Token {
+        self.tokens.get(self.index).unwrap_or_else(|| self.tokens.last().unwrap())
     }
 
     pub fn empty(&self) -> bool {
@@ -30,16 +34,27 @@ impl TokenHolder {
             _ => false
         }
     }
-    pub fn previous(&self) -> Token {self.tokens[self.index - 1].clone()}
+    pub fn previous(&self) -> &Token {&self.tokens[self.index - 1]}
+
+    /// How many tokens have been consumed.
+    pub fn position(&self) -> usize {
+        self.index
+    }
+
+    /// The tokens consumed since `position`.
+    pub fn since(&self, position: usize) -> &[Token] {
+        &self.tokens[position..self.index.min(self.tokens.len())]
+    }
 
     pub fn rewind(&mut self) {
         self.index -= 1;
     }
 
     /// Test if the current token is a specified kind.
-    pub fn expect(&mut self, kind: TokenKind) -> Option<Token> {
-        if self.tokens[self.index].kind() == kind {
-            return Some(self.tokens[self.index].clone());
+    pub fn expect(&self, kind: TokenKind) -> Option<&Token> {
+        let current = self.current();
+        if current.kind() == kind {
+            return Some(current);
         }
 
         None
@@ -48,6 +63,7 @@ impl TokenHolder {
     /// Called t_match b/c match is a reserved keyword.
     pub fn t_match(&mut self, kind: TokenKind) -> Option<Token> {
         if let Some(t) = self.expect(kind) {
+            let t = t.clone();
             self.index += 1;
             return Some(t);
         }
@@ -60,9 +76,8 @@ impl Iterator for TokenHolder {
     type Item = Token;
 
     fn next(&mut self) -> Option<Self::Item> {
-        let ret = self.current();
+        let ret = self.current().clone();
         self.index += 1;
         return Some(ret);
     }
 }
-
@@ -4,26 +4,44 @@ pub enum TokenKind {
     Semicolon,
     Equals,
     While,
+    For, In,
+    Break, Continue,
     Let,
     Colon,
     Fn,
+    Struct, Dot,
+    Enum, ColonColon,
+    Trait, Impl,
+    Match, FatArrow,
     LParen, RParen,
     Arrow,
     If, Else,
     Return,
 
     // exprs and the like
-    Plus, EqualsEquals, RAngleBracket, Minus, PipePie, AndAnd, Bang,
+    Plus, EqualsEquals, BangEquals, RAngleBracket, LAngleBracket, RAngleEquals, LAngleEquals, Minus, Star, Slash, Percent, PipePie, AndAnd, Bang,
+
+    // compound assignment
+    PlusEquals, MinusEquals, StarEquals, SlashEquals, PercentEquals, AndAndEquals, PipePipeEquals,
+
+    // closures
+    Pipe,
 
     // lists
     LBracket, RBracket, Comma,
 
+    // ranges
+    DotDot, DotDotEquals,
+
     // type stuff
     IntType,
     BoolType,
     ListType,
+    StrType,
+    CharType,
+    FloatType,
 
-    Name, Int,
+    Name, Int, Float, Str, Char, Label,
     True, False,
 
     //end of input marker,
@@ -1,3 +1,6 @@
+// the programs are only compiled by the end to end tests in main.rs
+#![cfg_attr(not(test), allow(dead_code))]
+
 pub const THE_UNIVERSE: &'static str = "fn main() -> int { return 42; }";
 pub const THE_UNIVERSE_BY_ADDITION: &'static str = r"fn main() -> int {
     return 20 + 22;
@@ -161,4 +164,452 @@ fn main() -> int {
 }";
 
 pub const TRUE: &'static str = "fn main() -> bool { return true; }";
-pub const FALSE: &'static str = "fn main() -> bool { return false; }";
\ No newline at end of file
+pub const FALSE: &'static str = "fn main() -> bool { return false; }";
+
+pub const STR_LEN: &'static str = r#"fn main() -> int {
+    let s: str = "hello, " + "world";
+    return len(s) + len("\u{1F600}\n") + 25;
+}"#;
+
+pub const STR_EQ: &'static str = r#"fn greeting(name: str) -> str {
+    return "hello, " + name;
+}
+
+fn main() -> bool {
+    return greeting("world") == "hello, world";
+}"#;
+
+pub const STR_EQ_FALSE: &'static str = r#"fn main() -> bool {
+    return "abc" == "abd";
+}"#;
+
+pub const INT_BASES: &'static str = r"fn main() -> int {
+    return 0x10 + 0o20 + 0b1000 + 0b1_0 + -2147483648 + 2147483647 + 1;
+}";
+
+pub const CHAR_SHIFT: &'static str = r"fn shift(c: char, by: int) -> char {
+    return chr(ord(c) + by);
+}
+
+fn main() -> int {
+    let c: char = shift('\n', 1);
+    return ord(c) + ord(shift(c, 20));
+}";
+
+pub const INVALID_CHAR: &'static str = r"fn main() -> int {
+    let c: char = chr(ord('a') - 98);
+    return ord(c);
+}";
+
+pub const CHAR_CMP: &'static str = r"fn main() -> bool {
+    let c: char = 'é';
+    return c > 'z' && c == chr(233) && 'b' > 'a';
+}";
+
+pub const FLOAT_CMP: &'static str = r"fn half(x: float) -> float {
+    return x + -0.5e0;
+}
+
+fn main() -> bool {
+    let x: float = 1.5 + 2e1;
+    return x == 21.5 && 0.1 + 0.2 > 0.3 && half(1.0) == 0.5 && 1_000.25 > 1E3;
+}";
+
+pub const ARITHMETIC: &'static str = r"fn main() -> int {
+    let x: int = 100 - 7 * 8 - 2;
+    let divisor: int = -5;
+    return x / divisor * -5 + x % 5 - 20 / 3 % 4 + 22 % -4;
+}";
+
+pub const DIVISION_BY_ZERO: &'static str = r"fn divide(a: int, b: int) -> int {
+    return a / b;
+}
+
+fn main() -> int {
+    return divide(42, 0);
+}";
+
+pub const FOR_RANGE: &'static str = r#"fn main() -> int {
+    let total: int = 0;
+    for i in 0..5 {
+        total = total + i;
+    }
+    for i in 1..=4 {
+        for j in i..i + 2 {
+            total = total + j;
+        }
+    }
+    for i in 10..0 { total = total + 100; }
+    for i in 2147483640..=2147483647 { total = total + 1; }
+    return total;
+}"#;
+
+pub const REDECLARED_LOCALS: &'static str = r#"fn main() -> int {
+    let s: int = 0;
+    for i in 0..2 {
+        let y: int = i;
+        s += y;
+    }
+    for i in 0..2 {
+        let y: int = 2;
+        s += y;
+    }
+    s += if s > 0 {
+        let y: int = 1;
+        y
+    } else {
+        let y: int = 2;
+        y
+    };
+    let (a, b): (int, int) = (1, 2);
+    let (a, b): (int, int) = (a, 30);
+    return s + a + b + 5;
+}"#;
+
+pub const NESTED_LOOPS: &'static str = r#"fn main() -> int {
+    let s: int = 0;
+    let xs: list int = [1];
+    for i in 0..10000000 {
+        for j in 0..1 { s += j + 1; }
+    }
+    for i in 0..10000000 {
+        for x in xs { s += x; }
+    }
+    return s / 1000000 + 22;
+}"#;
+
+pub const LOOP_CONTROL: &'static str = r#"fn main() -> int {
+    let total: int = 0;
+    let n: int = 0;
+    while true {
+        n = n + 1;
+        if n % 2 == 0 { continue; }
+        if n > 9 { break; }
+        total = total + n;
+    }
+    'rows: for row in 0..10 {
+        for column in 0..10 {
+            if column > row { continue 'rows; }
+            if row == 3 { break 'rows; }
+            total = total + 1;
+        }
+    }
+    return total + n;
+}"#;
+
+pub const COMPOUND_ASSIGNMENT: &'static str = r#"fn main() -> int {
+    let x: int = 10;
+    x += 5;
+    x *= 4;
+    x -= 4;
+    x /= 7;
+    x %= 5;
+    let done: bool = false;
+    done ||= x == 3;
+    done &&= true;
+    let total: int = 0;
+    for i in 0..7 { total += i; }
+    if done { total += 21; }
+    return total;
+}"#;
+
+pub const STRUCTS: &'static str = r#"struct Point { x: int, y: int }
+struct Rect { min: Point, max: Point }
+
+fn area(r: Rect) -> int {
+    return (r.max.x - r.min.x) * (r.max.y - r.min.y);
+}
+
+fn grow(r: Rect, by: int) -> Rect {
+    r.max.x += by;
+    r.max.y = r.max.y + by;
+    return r;
+}
+
+fn main() -> int {
+    let r: Rect = Rect { min: Point { x: 1, y: 2 }, max: Point { y: 5, x: 4 } };
+    let bigger: Rect = grow(r, 3);
+    return area(bigger) + area(r) - r.min.x - r.min.y;
+}"#;
+
+pub const COMPARISONS: &'static str = r#"fn main() -> int {
+    let count: int = 0;
+    if 1 < 2 { count = count + 1; }
+    if 2 <= 2 { count = count + 1; }
+    if 3 >= 4 { count = count + 100; }
+    if 3 != 3 { count = count + 100; }
+    if 'a' < 'b' && 1.5 >= 1.5 && 0.5 != 0.25 { count = count + 10; }
+    if "abc" != "abd" && (1 < 2) == (3 < 4) && true != false { count = count + 30; }
+    return count;
+}"#;
+
+pub const ENUMS: &'static str = r#"enum Shape { Circle(int), Rect(int, int), Empty }
+enum Reading { Missing, Value(Shape, bool) }
+
+fn area(s: Shape) -> int {
+    return match s {
+        Shape::Circle(r) => 3 * r * r,
+        Shape::Rect(w, h) => w * h,
+        Shape::Empty => 0,
+    };
+}
+
+fn score(r: Reading) -> int {
+    return match r {
+        Reading::Value(Shape::Rect(1, h), true) => h,
+        Reading::Value(s, false) => area(s),
+        Reading::Value(_, true) => 100,
+        Reading::Missing => 1,
+    };
+}
+
+fn main() -> int {
+    let total: int = area(Shape::Circle(2)) + area(Shape::Rect(3, 4)) + area(Shape::Empty);
+    total += score(Reading::Value(Shape::Rect(1, 7), true));
+    total += score(Reading::Value(Shape::Rect(2, 5), false));
+    total += score(Reading::Missing);
+    return match total { 42 => 42, _ => 0 };
+}"#;
+
+pub const ENUMS_IN_LOOP: &'static str = r#"enum Shape { Circle(int), Rect(int, int) }
+
+fn main() -> int {
+    let total: int = 0;
+    for i in 0..10000000 {
+        let shape: Shape = Shape::Rect(i % 2, 1);
+        total += match shape {
+            Shape::Rect(w, h) => w * h,
+            Shape::Circle(r) => r,
+        };
+    }
+    return total / 1000000 + 37;
+}"#;
+
+pub const TUPLES: &'static str = r#"fn divmod(a: int, b: int) -> (int, int) {
+    return (a / b, a % b);
+}
+
+fn swap(pair: (int, bool)) -> (bool, int) {
+    return (pair.1, pair.0);
+}
+
+fn main() -> int {
+    let (q, r): (int, int) = divmod(47, 10);
+    let nested: ((int, int), (bool, int)) = (divmod(9, 4), swap((5, true)));
+    nested.0.1 += 30;
+    let (_, last): (bool, int) = nested.1;
+    if nested.1.0 {
+        return q * 10 - r + nested.0.1 - last + nested.0.0 - 19;
+    }
+    return 0;
+}"#;
+
+pub const LISTS: &'static str = r#"fn squares(n: int) -> list int {
+    let xs: list int = [];
+    for i in 0..n {
+        push(xs, i * i);
+    }
+    return xs;
+}
+
+fn sum(xs: list int) -> int {
+    let total: int = 0;
+    for x in xs {
+        total += x;
+    }
+    return total;
+}
+
+fn main() -> int {
+    let xs: list int = squares(6);
+    xs[0] = 7;
+    xs[1] += xs[len(xs) - 1];
+    let grid: list list int = [[1, 2], [3, 4]];
+    push(grid[1], 5);
+    return (sum(xs) - grid[1][2] * len(grid[1]) - len(grid) * 15) * len([grid[0][0]]);
+}"#;
+
+pub const INDEX_OUT_OF_BOUNDS: &'static str = r"fn main() -> int {
+    let xs: list int = [1, 2, 3];
+    return xs[len(xs)];
+}";
+
+pub const INDEX_COMPOUND_ASSIGNMENT: &'static str = r#"fn grow(c: list int) -> int {
+    push(c, 1);
+    return 0;
+}
+
+fn main() -> int {
+    let xs: list int = [40];
+    xs[grow(xs)] += 1;
+    let names: list str = ["a"];
+    names[grow(xs)] += "b";
+    return xs[0] + len(xs) + len(names[0]) - 4;
+}"#;
+
+pub const CLOSURES: &'static str = r#"fn apply_twice(f: fn(int) -> int, x: int) -> int {
+    return f(f(x));
+}
+
+fn make_adder(n: int) -> fn(int) -> int {
+    return |x: int| -> int { x + n };
+}
+
+fn fold(xs: list int, start: int, f: fn(int, int) -> int) -> int {
+    let total: int = start;
+    for x in xs {
+        total = f(total, x);
+    }
+    return total;
+}
+
+fn double(x: int) -> int {
+    return x * 2;
+}
+
+fn main() -> int {
+    let add_three: fn(int) -> int = make_adder(3);
+    let count: int = 0;
+    let three: fn() -> int = || -> int {
+        let local: int = count;
+        local += 3;
+        local
+    };
+    let sum: int = fold([1, 2, 3], count, |a: int, b: int| -> int { a + b });
+    return apply_twice(double, 5) + apply_twice(add_three, 1) + sum + three() + make_adder(4)(2);
+}"#;
+
+pub const GENERICS: &'static str = r#"fn first<T>(xs: list T) -> T {
+    return xs[0];
+}
+
+fn last<T>(xs: list T) -> T {
+    return xs[len(xs) - 1];
+}
+
+fn map<T, U>(xs: list T, f: fn(T) -> U) -> list U {
+    let out: list U = [];
+    for x in xs {
+        push(out, f(x));
+    }
+    return out;
+}
+
+fn count<T>(xs: list T, keep: fn(T) -> bool) -> int {
+    let n: int = 0;
+    for x in xs {
+        if keep(x) {
+            n += 1;
+        }
+    }
+    return n;
+}
+
+fn pick<T>(condition: bool, a: T, b: T) -> T {
+    if condition {
+        return a;
+    }
+    return b;
+}
+
+fn main() -> int {
+    let words: list str = ["a", "bb", "ccc"];
+    let lengths: list int = map(words, |w: str| -> int { len(w) });
+    let long: list bool = map(lengths, |n: int| -> bool { n > 1 });
+    let c: char = pick(first(long), 'x', 'y');
+    let bonus: int = pick(c == 'y', 30, 0);
+    return last(lengths) + count(long, |b: bool| -> bool { b }) + bonus + first(lengths) * 7;
+}"#;
+
+pub const TRAITS: &'static str = r#"struct Rect { w: int, h: int }
+enum Shape { Square(int), Circle(int) }
+
+trait Area {
+    fn area(self) -> int;
+}
+
+trait Same {
+    fn same(self, other: Self) -> bool;
+}
+
+impl Area for Rect {
+    fn area(self) -> int {
+        return self.w * self.h;
+    }
+}
+
+impl Area for Shape {
+    fn area(self) -> int {
+        return match self {
+            Shape::Square(side) => side * side,
+            Shape::Circle(r) => 3 * r * r,
+        };
+    }
+}
+
+impl Same for Rect {
+    fn same(self, other: Self) -> bool {
+        return self.w == other.w && self.h == other.h;
+    }
+}
+
+impl Same for int {
+    fn same(self, other: int) -> bool {
+        return self == other;
+    }
+}
+
+fn total_area<T: Area>(items: list T) -> int {
+    let total: int = 0;
+    for item in items {
+        total += item.area();
+    }
+    return total;
+}
+
+fn count_same<T: Same>(items: list T, target: T) -> int {
+    let n: int = 0;
+    for item in items {
+        if item.same(target) {
+            n += 1;
+        }
+    }
+    return n;
+}
+
+fn main() -> int {
+    let rects: list Rect = [Rect { w: 2, h: 3 }, Rect { w: 1, h: 4 }, Rect { w: 2, h: 3 }];
+    let shapes: list Shape = [Shape::Square(3), Shape::Circle(1)];
+    let sizes: list int = [6, 4, 6];
+    return total_area(rects) + total_area(shapes) + count_same(rects, rects[0]) * 5 + count_same(sizes, 4) * 4;
+}"#;
+
+pub const SHADOWING: &'static str = r#"fn main() -> int {
+    let s: int = 0;
+    for i in 0..3 {
+        let i: int = 10;
+        s += i;
+    }
+    for i in 0..1 {
+        if true {
+            let i: int = 100;
+        }
+        s += i;
+    }
+    for x in [1, 2] {
+        let x: int = 2;
+        s += x;
+    }
+    let pick: fn() -> int = || -> int {
+        let s: int = 3;
+        s
+    };
+    s += pick();
+    s += match s {
+        k => {
+            let k: int = 5;
+            k
+        }
+    };
+    return s;
+}"#;
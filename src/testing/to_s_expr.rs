@@ -1,5 +1,7 @@
 use crate::testing::s_expr::SExpr;
 
+/// Only the tests look at trees as s-expressions.
+#[cfg_attr(not(test), allow(dead_code))]
 pub trait ToSExpr {
     fn to_s_expr(self) -> SExpr<String>;
 }
\ No newline at end of file
@@ -7,6 +7,7 @@ use crate::typed_ast::typed_statement::TypedStatement;
 pub mod r#type;
 pub mod typed_statement;
 pub mod typed_expression;
+pub mod typed_pattern;
 
 #[derive(Debug, Clone)]
 pub struct TypedBlock {
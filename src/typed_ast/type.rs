@@ -1,10 +1,25 @@
+use std::collections::HashMap;
 use std::fmt::{Display, Formatter};
 
 #[derive(Debug, Clone, Ord, PartialOrd, Eq, PartialEq)]
 pub enum Type {
     Int,
+    Float,
     Bool,
+    Str,
+    Char,
     List(Box<Type>),
+    /// `(int, bool)`, with at least one element.
+    Tuple(Vec<Type>),
+    /// A struct, known by its name alone.
+    Struct(String),
+    Enum(String),
+    /// A struct or enum as written in the source, before the typer looks up which it is.
+    Named(String),
+    /// `fn(int, bool) -> int`, which named functions and lambdas both have.
+    Function(Vec<Type>, Box<Type>),
+    /// A type parameter of a generic function, like the `T` in `fn id<T>(x: T) -> T`.
+    Param(String),
     Unit,
 }
 
@@ -12,19 +27,57 @@ impl Type {
     pub fn llvm_type(&self) -> String {
         match self {
             Type::Int => String::from("i32"),
+            Type::Float => String::from("double"),
             Type::Bool => String::from("i1"),
-            Type::List(_) => unimplemented!(),
+            // length in bytes, then a pointer to the (not null terminated) UTF-8 data
+            Type::Str => String::from("{ i32, i8* }"),
+            // a unicode scalar value
+            Type::Char => String::from("i32"),
+            // data, length and capacity, behind a pointer so that copies share them
+            Type::List(_) => String::from("{ i8*, i32, i32 }*"),
+            // an anonymous struct, so it is passed around by value like any other struct
+            Type::Tuple(elements) => format!("{{ {} }}", elements.iter().map(|x| x.llvm_type()).collect::<Vec<_>>().join(", ")),
+            Type::Struct(name) => format!("%struct.{}", name),
+            // the tag, then room for the largest payload
+            Type::Enum(name) => format!("%enum.{}", name),
+            // the code to run, then the values it captured, or null when there are none
+            Type::Function(_, _) => String::from("{ i8*, i8* }"),
+            Type::Named(name) => unreachable!("the typer resolves {} to a struct or enum", name),
+            Type::Param(name) => unreachable!("{} is replaced by a real type before codegen", name),
             Type::Unit => String::from(""),
         }
     }
+
+    /// This type with each type parameter in `bindings` replaced by what it is bound to.
+    pub fn substitute(&self, bindings: &HashMap<String, Type>) -> Type {
+        match self {
+            Type::Param(name) => bindings.get(name).cloned().unwrap_or(self.clone()),
+            Type::List(element) => Type::List(Box::new(element.substitute(bindings))),
+            Type::Tuple(elements) => Type::Tuple(elements.iter().map(|x| x.substitute(bindings)).collect()),
+            Type::Function(params, return_type) => Type::Function(
+                params.iter().map(|x| x.substitute(bindings)).collect(),
+                Box::new(return_type.substitute(bindings))
+            ),
+            _ => self.clone()
+        }
+    }
 }
 
 impl Display for Type {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         write!(f, "{}", match self {
             Type::Int => String::from("int"),
+            Type::Float => String::from("float"),
             Type::Bool => String::from("bool"),
+            Type::Str => String::from("str"),
+            Type::Char => String::from("char"),
             Type::List(t) => format!("list<{}>", t),
+            Type::Tuple(elements) if elements.len() == 1 => format!("({},)", elements[0]),
+            Type::Tuple(elements) => format!("({})", elements.iter().map(|x| x.to_string()).collect::<Vec<_>>().join(", ")),
+            Type::Struct(name) | Type::Enum(name) | Type::Named(name) | Type::Param(name) => name.clone(),
+            Type::Function(params, return_type) => {
+                format!("fn({}) -> {}", params.iter().map(|x| x.to_string()).collect::<Vec<_>>().join(", "), return_type)
+            }
             Type::Unit => String::from("unit"),
         })
     }
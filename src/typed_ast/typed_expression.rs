@@ -1,9 +1,11 @@
 use crate::ast::binary_operator::BinaryOperator;
 use crate::ast::unary_operator::UnaryOperator;
+use crate::parser::span::Span;
 use crate::parser::token::Token;
 use crate::testing::s_expr::SExpr;
 use crate::testing::to_s_expr::ToSExpr;
 use crate::typed_ast::r#type::Type;
+use crate::typed_ast::typed_pattern::TypedMatchArm;
 use crate::typed_ast::TypedBlock;
 
 #[derive(Debug, Clone)]
@@ -12,47 +14,158 @@ pub enum TypedExpression {
         condition: Box<TypedExpression>,
         true_block: TypedBlock,
         else_block: Option<TypedBlock>,
+        span: Span
     },
     BinaryOperation {
         lhs: Box<TypedExpression>,
         operator: BinaryOperator,
         rhs: Box<TypedExpression>,
-        type_: Type
+        type_: Type,
+        span: Span
     },
+    /// A call straight to a named function, with the types its type parameters stand for
+    /// if it is generic.
     FunctionCall {
         name: Token,
         arguments: Vec<TypedExpression>,
-        type_: Type
+        type_arguments: Vec<Type>,
+        type_: Type,
+        span: Span
     },
     UnaryOperation {
         operator: UnaryOperator,
-        rhs: Box<TypedExpression>
+        rhs: Box<TypedExpression>,
+        span: Span
     },
     Int(Token),
+    Float(Token),
     Bool(bool, Token),
-    List(Vec<TypedExpression>, Type),
+    Str(Token),
+    Char(Token),
+    List(Vec<TypedExpression>, Type, Span),
+    Tuple(Vec<TypedExpression>, Type, Span),
+    /// The fields in the order they were written, each with its index in the struct.
+    StructLiteral {
+        fields: Vec<(usize, TypedExpression)>,
+        type_: Type,
+        span: Span
+    },
+    /// The variant of `type_` that comes `tag`th in its declaration.
+    EnumVariant {
+        type_: Type,
+        variant: Token,
+        tag: usize,
+        payload: Vec<TypedExpression>,
+        span: Span
+    },
+    Match {
+        scrutinee: Box<TypedExpression>,
+        arms: Vec<TypedMatchArm>,
+        type_: Type,
+        span: Span
+    },
+    FieldAccess {
+        of: Box<TypedExpression>,
+        index: usize,
+        type_: Type,
+        span: Span
+    },
+    /// `of[index]`, where `type_` is the type of the element.
+    Index {
+        of: Box<TypedExpression>,
+        index: Box<TypedExpression>,
+        type_: Type,
+        span: Span
+    },
+    /// A lambda, with the variables from outside it that its body uses.
+    Lambda {
+        params: Vec<(Token, Type)>,
+        captures: Vec<(Token, Type)>,
+        body: TypedBlock,
+        type_: Type,
+        span: Span
+    },
+    /// A call through a function value rather than straight to a named function, where
+    /// `type_` is what the call returns.
+    Call {
+        callee: Box<TypedExpression>,
+        arguments: Vec<TypedExpression>,
+        type_: Type,
+        span: Span
+    },
+    /// `receiver.method(arguments)`, calling the method of `trait_` for the type of the
+    /// receiver, where `type_` is what the method returns.
+    MethodCall {
+        receiver: Box<TypedExpression>,
+        trait_: String,
+        method: Token,
+        arguments: Vec<TypedExpression>,
+        type_: Type,
+        span: Span
+    },
+    /// A named function used as a value.
+    Function(Token, Type),
     Name(Token, Type)
 }
 
 impl TypedExpression {
     pub fn get_type(&self) -> Type {
         match self {
-            TypedExpression::If { condition: _condition, true_block, else_block: _else_block } => true_block.type_.clone(),
-            TypedExpression::BinaryOperation { lhs: _, operator: _, rhs: _, type_ } => type_.clone(),
-            TypedExpression::FunctionCall { name: _, arguments: _, type_ } => type_.clone(),
-            TypedExpression::UnaryOperation { operator: _, rhs } => rhs.get_type(),
+            TypedExpression::If { condition: _condition, true_block, else_block: _else_block, span: _ } => true_block.type_.clone(),
+            TypedExpression::BinaryOperation { lhs: _, operator: _, rhs: _, type_, span: _ } => type_.clone(),
+            TypedExpression::FunctionCall { type_, .. } => type_.clone(),
+            TypedExpression::UnaryOperation { operator: _, rhs, span: _ } => rhs.get_type(),
             TypedExpression::Int(_) => Type::Int,
+            TypedExpression::Float(_) => Type::Float,
             TypedExpression::Bool(_, _) => Type::Bool,
-            TypedExpression::List(_, t) => t.clone(),
+            TypedExpression::Str(_) => Type::Str,
+            TypedExpression::Char(_) => Type::Char,
+            TypedExpression::List(_, t, _) => t.clone(),
+            TypedExpression::Tuple(_, t, _) => t.clone(),
+            TypedExpression::StructLiteral { type_, .. } => type_.clone(),
+            TypedExpression::FieldAccess { type_, .. } => type_.clone(),
+            TypedExpression::Index { type_, .. } => type_.clone(),
+            TypedExpression::Lambda { type_, .. } => type_.clone(),
+            TypedExpression::Call { type_, .. } => type_.clone(),
+            TypedExpression::MethodCall { type_, .. } => type_.clone(),
+            TypedExpression::Function(_, t) => t.clone(),
+            TypedExpression::EnumVariant { type_, .. } => type_.clone(),
+            TypedExpression::Match { type_, .. } => type_.clone(),
             TypedExpression::Name(_, t) => t.clone(),
         }
     }
+
+    pub fn span(&self) -> Span {
+        match self {
+            TypedExpression::If { span, .. } => *span,
+            TypedExpression::BinaryOperation { span, .. } => *span,
+            TypedExpression::FunctionCall { span, .. } => *span,
+            TypedExpression::UnaryOperation { span, .. } => *span,
+            TypedExpression::Int(t) => t.span(),
+            TypedExpression::Float(t) => t.span(),
+            TypedExpression::Bool(_, t) => t.span(),
+            TypedExpression::Str(t) => t.span(),
+            TypedExpression::Char(t) => t.span(),
+            TypedExpression::List(_, _, span) => *span,
+            TypedExpression::Tuple(_, _, span) => *span,
+            TypedExpression::StructLiteral { span, .. } => *span,
+            TypedExpression::FieldAccess { span, .. } => *span,
+            TypedExpression::Index { span, .. } => *span,
+            TypedExpression::Lambda { span, .. } => *span,
+            TypedExpression::Call { span, .. } => *span,
+            TypedExpression::MethodCall { span, .. } => *span,
+            TypedExpression::Function(t, _) => t.span(),
+            TypedExpression::EnumVariant { span, .. } => *span,
+            TypedExpression::Match { span, .. } => *span,
+            TypedExpression::Name(t, _) => t.span(),
+        }
+    }
 }
 
 impl ToSExpr for TypedExpression {
     fn to_s_expr(self) -> SExpr<String> {
         match self {
-            TypedExpression::If { condition, true_block, else_block } => {
+            TypedExpression::If { condition, true_block, else_block, span: _ } => {
                 let t_block = true_block.to_s_expr();
                 let mut args = vec![condition.to_s_expr(), t_block];
                 if let Some(else_block) = else_block {
@@ -62,26 +175,72 @@ impl ToSExpr for TypedExpression {
                 let t = SExpr::Function(String::from("if"), args);
                 t
             }
-            TypedExpression::BinaryOperation { lhs, operator, rhs, type_: _ } => {
+            TypedExpression::BinaryOperation { lhs, operator, rhs, type_: _, span: _ } => {
                 SExpr::Function(operator.to_string(), vec![lhs.to_s_expr(), rhs.to_s_expr()])
             }
-            TypedExpression::FunctionCall { name, arguments, type_:_ } => {
+            TypedExpression::FunctionCall { name, arguments, type_arguments: _, type_:_, span: _ } => {
                 SExpr::Function(
                     name.lexeme().to_string(),
                     arguments.into_iter().map(|x| x.to_s_expr()).collect()
                 )
             }
-            TypedExpression::UnaryOperation { operator, rhs } => {
+            TypedExpression::UnaryOperation { operator, rhs, span: _ } => {
                 SExpr::Function(operator.to_string(), vec![rhs.to_s_expr()])
             }
             TypedExpression::Int(_) => SExpr::Value(Type::Int.to_string()),
+            TypedExpression::Float(_) => SExpr::Value(Type::Float.to_string()),
             TypedExpression::Bool(_, _) => SExpr::Value(Type::Bool.to_string()),
-            TypedExpression::List(elements, _) => {
+            TypedExpression::Str(_) => SExpr::Value(Type::Str.to_string()),
+            TypedExpression::Char(_) => SExpr::Value(Type::Char.to_string()),
+            TypedExpression::List(elements, _, _) => {
                 SExpr::Function(
                     String::from("list"),
                     elements.into_iter().map(|x| x.to_s_expr()).collect()
                 )
             }
+            TypedExpression::Tuple(elements, _, _) => {
+                SExpr::Function(
+                    String::from("tuple"),
+                    elements.into_iter().map(|x| x.to_s_expr()).collect()
+                )
+            }
+            TypedExpression::StructLiteral { fields, type_, span: _ } => {
+                SExpr::Function(type_.to_string(), fields.into_iter().map(|(_, x)| x.to_s_expr()).collect())
+            }
+            TypedExpression::EnumVariant { type_, variant, tag: _, payload, span: _ } => {
+                SExpr::Function(format!("{}::{}", type_, variant.lexeme()), payload.into_iter().map(|x| x.to_s_expr()).collect())
+            }
+            TypedExpression::Match { scrutinee, arms, type_: _, span: _ } => {
+                let mut args = vec![scrutinee.to_s_expr()];
+                args.extend(arms.into_iter().map(|x| x.to_s_expr()));
+                SExpr::Function(String::from("match"), args)
+            }
+            TypedExpression::FieldAccess { of, index: _, type_, span: _ } => {
+                SExpr::Function(String::from("."), vec![of.to_s_expr(), SExpr::Value(type_.to_string())])
+            }
+            TypedExpression::Index { of, index, type_: _, span: _ } => {
+                SExpr::Function(String::from("index"), vec![of.to_s_expr(), index.to_s_expr()])
+            }
+            TypedExpression::Lambda { params, captures: _, body, type_, span: _ } => {
+                let Type::Function(_, return_type) = type_ else { unreachable!("a lambda has a function type") };
+                let mut args = params.into_iter()
+                    .map(|(name, type_)| SExpr::Value(format!("{}:{}", name.lexeme(), type_)))
+                    .collect::<Vec<_>>();
+                args.push(body.to_s_expr());
+                args.push(SExpr::Value(format!("->{}", return_type)));
+                SExpr::Function(String::from("lambda"), args)
+            }
+            TypedExpression::Call { callee, arguments, type_: _, span: _ } => {
+                let mut args = vec![callee.to_s_expr()];
+                args.extend(arguments.into_iter().map(|x| x.to_s_expr()));
+                SExpr::Function(String::from("call"), args)
+            }
+            TypedExpression::MethodCall { receiver, trait_, method, arguments, type_: _, span: _ } => {
+                let mut args = vec![receiver.to_s_expr(), SExpr::Value(format!("{}.{}", trait_, method.lexeme()))];
+                args.extend(arguments.into_iter().map(|x| x.to_s_expr()));
+                SExpr::Function(String::from("method_call"), args)
+            }
+            TypedExpression::Function(t, _) => SExpr::Value(t.lexeme().to_string()),
             TypedExpression::Name(_, t) => SExpr::Value(t.to_string())
         }
     }
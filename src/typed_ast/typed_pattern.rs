@@ -0,0 +1,64 @@
+use crate::parser::span::Span;
+use crate::parser::token::Token;
+use crate::testing::s_expr::SExpr;
+use crate::testing::to_s_expr::ToSExpr;
+use crate::typed_ast::r#type::Type;
+use crate::typed_ast::typed_expression::TypedExpression;
+use crate::typed_ast::TypedBlock;
+
+#[derive(Debug, Clone)]
+pub enum TypedPattern {
+    Wildcard,
+    Binding(Token, Type),
+    Literal(TypedExpression),
+    /// A variant of the enum `type_`, with `tag` being its place in the declaration.
+    Variant {
+        type_: Type,
+        variant: Token,
+        tag: usize,
+        fields: Vec<TypedPattern>
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct TypedMatchArm {
+    pub pattern: TypedPattern,
+    pub body: TypedBlock,
+    pub span: Span
+}
+
+impl TypedPattern {
+    /// Whether every value matches, so there is nothing to test.
+    pub fn is_irrefutable(&self) -> bool {
+        matches!(self, TypedPattern::Wildcard | TypedPattern::Binding(..))
+    }
+
+    /// Whether the pattern gives a name to any part of the value.
+    pub fn binds(&self) -> bool {
+        match self {
+            TypedPattern::Binding(..) => true,
+            TypedPattern::Variant { fields, .. } => fields.iter().any(|x| x.binds()),
+            _ => false
+        }
+    }
+}
+
+impl ToSExpr for TypedPattern {
+    fn to_s_expr(self) -> SExpr<String> {
+        match self {
+            TypedPattern::Wildcard => SExpr::Value(String::from("_")),
+            TypedPattern::Binding(name, type_) => SExpr::Value(format!("{}:{}", name.lexeme(), type_)),
+            TypedPattern::Literal(e) => e.to_s_expr(),
+            TypedPattern::Variant { type_, variant, tag: _, fields } => SExpr::Function(
+                format!("{}::{}", type_, variant.lexeme()),
+                fields.into_iter().map(|x| x.to_s_expr()).collect()
+            ),
+        }
+    }
+}
+
+impl ToSExpr for TypedMatchArm {
+    fn to_s_expr(self) -> SExpr<String> {
+        SExpr::Function(String::from("=>"), vec![self.pattern.to_s_expr(), self.body.to_s_expr()])
+    }
+}
@@ -1,3 +1,7 @@
+use itertools::Itertools;
+use crate::ast::binary_operator::BinaryOperator;
+use crate::ast::statement::{generic_name, label_s_expr, place_name, TypeParam};
+use crate::parser::span::Span;
 use crate::parser::token::Token;
 use crate::testing::s_expr::SExpr;
 use crate::testing::to_s_expr::ToSExpr;
@@ -8,11 +12,23 @@ use crate::typed_ast::TypedBlock;
 #[derive(Debug, Clone)]
 pub struct FunctionDefinition {
     pub name: Token,
+    /// Empty unless the function is generic, which only lasts until it is monomorphized.
+    pub type_params: Vec<TypeParam>,
     pub type_: Type,
     pub arg_list: Vec<(Token, Type)>,
     pub body: TypedBlock
 }
 
+#[derive(Debug, Clone)]
+pub enum TypedForIterable {
+    Range {
+        start: Box<TypedExpression>,
+        end: Box<TypedExpression>,
+        inclusive: bool
+    },
+    List(Box<TypedExpression>)
+}
+
 #[derive(Debug, Clone)]
 pub enum TypedStatement {
     VariableDeclaration {
@@ -20,15 +36,66 @@ pub enum TypedStatement {
         type_: Type,
         value: TypedExpression
     },
+    /// `let (a, b): type_ = value;`, where a name of `_` is not kept.
+    TupleDeclaration {
+        names: Vec<Token>,
+        type_: Type,
+        value: TypedExpression
+    },
     FunctionDefinitionStatement(FunctionDefinition),
+    StructDefinition {
+        name: Token,
+        fields: Vec<(Token, Type)>
+    },
+    EnumDefinition {
+        name: Token,
+        variants: Vec<(Token, Vec<Type>)>
+    },
+    /// A trait, with the type of each of its methods, where `self` has the type `Self`.
+    TraitDefinition {
+        name: Token,
+        methods: Vec<(Token, Type)>
+    },
+    /// The methods of `trait_` for `type_`, in the order they were written.
+    Impl {
+        trait_: Token,
+        type_: Type,
+        methods: Vec<FunctionDefinition>
+    },
     Assignment {
         to: Token,
         value: TypedExpression
     },
+    /// An assignment to a field of the struct in `to`, which has type `type_`. Each field in
+    /// the path comes with its index in the struct that holds it.
+    FieldAssignment {
+        to: Token,
+        type_: Type,
+        fields: Vec<(Token, usize)>,
+        value: TypedExpression
+    },
+    /// `list[index] = value`, or `list[index] += value` and the like when there is an operator,
+    /// with the span of the whole assignment to report a bad index at.
+    IndexAssignment {
+        list: Box<TypedExpression>,
+        index: Box<TypedExpression>,
+        operator: Option<BinaryOperator>,
+        value: TypedExpression,
+        span: Span
+    },
     While {
+        label: Option<Token>,
         condition: TypedExpression,
         body: TypedBlock
     },
+    For {
+        label: Option<Token>,
+        variable: Token,
+        iterable: TypedForIterable,
+        body: TypedBlock
+    },
+    Break(Option<Token>),
+    Continue(Option<Token>),
     Return(TypedExpression),
     Expression(TypedExpression)
 }
@@ -37,9 +104,17 @@ impl TypedStatement {
     pub fn get_type(&self) -> Type {
         match self {
             TypedStatement::VariableDeclaration { name: _, type_, value:_ } => type_.clone(),
+            TypedStatement::TupleDeclaration { type_, .. } => type_.clone(),
             TypedStatement::FunctionDefinitionStatement(def) => def.type_.clone(),
+            TypedStatement::StructDefinition { .. } => Type::Unit,
+            TypedStatement::EnumDefinition { .. } => Type::Unit,
+            TypedStatement::TraitDefinition { .. } | TypedStatement::Impl { .. } => Type::Unit,
             TypedStatement::Assignment { to: _, value } => value.get_type(),
-            TypedStatement::While { condition: _, body } => body.type_.clone(),
+            TypedStatement::FieldAssignment { value, .. } => value.get_type(),
+            TypedStatement::IndexAssignment { value, .. } => value.get_type(),
+            TypedStatement::While { label: _, condition: _, body } => body.type_.clone(),
+            TypedStatement::For { label: _, variable: _, iterable: _, body } => body.type_.clone(),
+            TypedStatement::Break(_) | TypedStatement::Continue(_) => Type::Unit,
             TypedStatement::Return(e) => e.get_type(),
             TypedStatement::Expression(e) => e.get_type()
         }
@@ -55,19 +130,34 @@ impl ToSExpr for TypedStatement {
                     value.to_s_expr()
                 ])
             }
-            S::FunctionDefinitionStatement(def) => {
-                let mut args = vec![SExpr::Value(def.name.lexeme().to_string())];
-                for t in def.arg_list
-                    .into_iter()
-                    .map(|(name, ast_type)| {
-                        SExpr::Value(format!("{}:{}", name.lexeme(), ast_type))
-                    }) {
-                    args.push(t)
-                }
-                args.push(def.body.to_s_expr());
-                args.push(SExpr::Value(format!("->{}", def.type_.to_string())));
-
-                SExpr::Function(String::from("function_define"), args)
+            S::TupleDeclaration { names, type_, value } => {
+                SExpr::Function(String::from("variable_declaration"), vec![
+                    SExpr::Value(format!("({}):{}", names.iter().map(|x| x.lexeme()).join(", "), type_)),
+                    value.to_s_expr()
+                ])
+            }
+            S::FunctionDefinitionStatement(def) => def.to_s_expr(),
+            S::StructDefinition { name, fields } => {
+                let mut args = vec![SExpr::Value(name.lexeme().to_string())];
+                args.extend(fields.into_iter().map(|(name, type_)| SExpr::Value(format!("{}:{}", name.lexeme(), type_))));
+                SExpr::Function(String::from("struct"), args)
+            }
+            S::EnumDefinition { name, variants } => {
+                let mut args = vec![SExpr::Value(name.lexeme().to_string())];
+                args.extend(variants.into_iter().map(|(name, payload)| {
+                    SExpr::Function(name.lexeme().to_string(), payload.into_iter().map(|x| SExpr::Value(x.to_string())).collect())
+                }));
+                SExpr::Function(String::from("enum"), args)
+            }
+            S::TraitDefinition { name, methods } => {
+                let mut args = vec![SExpr::Value(name.lexeme().to_string())];
+                args.extend(methods.into_iter().map(|(name, type_)| SExpr::Value(format!("{}:{}", name.lexeme(), type_))));
+                SExpr::Function(String::from("trait"), args)
+            }
+            S::Impl { trait_, type_, methods } => {
+                let mut args = vec![SExpr::Value(trait_.lexeme().to_string()), SExpr::Value(type_.to_string())];
+                args.extend(methods.into_iter().map(|x| x.to_s_expr()));
+                SExpr::Function(String::from("impl"), args)
             }
             S::Assignment { to, value } => {
                 SExpr::Function(String::from("="), vec![
@@ -75,11 +165,56 @@ impl ToSExpr for TypedStatement {
                     value.to_s_expr(),
                 ])
             }
-            S::While { condition, body } => {
-                SExpr::Function(String::from("while"), vec![condition.to_s_expr(), body.to_s_expr()])
+            S::FieldAssignment { to, type_: _, fields, value } => {
+                SExpr::Function(String::from("="), vec![
+                    SExpr::Value(place_name(&to, fields.iter().map(|(x, _)| x))),
+                    value.to_s_expr(),
+                ])
+            }
+            S::IndexAssignment { list, index, operator, value, span: _ } => {
+                let name = operator.map(|x| format!("{}=", x)).unwrap_or(String::from("="));
+                SExpr::Function(name, vec![
+                    SExpr::Function(String::from("index"), vec![list.to_s_expr(), index.to_s_expr()]),
+                    value.to_s_expr(),
+                ])
+            }
+            S::While { label, condition, body } => {
+                SExpr::Function(String::from("while"), label_s_expr(label).into_iter()
+                    .chain([condition.to_s_expr(), body.to_s_expr()])
+                    .collect())
             }
+            S::For { label, variable: _, iterable, body } => {
+                let iterable = match iterable {
+                    TypedForIterable::Range { start, end, inclusive } => {
+                        SExpr::Function(String::from(if inclusive { "..=" } else { ".." }), vec![start.to_s_expr(), end.to_s_expr()])
+                    }
+                    TypedForIterable::List(list) => list.to_s_expr()
+                };
+                SExpr::Function(String::from("for"), label_s_expr(label).into_iter()
+                    .chain([iterable, body.to_s_expr()])
+                    .collect())
+            }
+            S::Break(label) => SExpr::Function(String::from("break"), label_s_expr(label).into_iter().collect()),
+            S::Continue(label) => SExpr::Function(String::from("continue"), label_s_expr(label).into_iter().collect()),
             S::Return(e) => SExpr::Function(e.get_type().to_string(), vec![e.to_s_expr()]),
             S::Expression(e) => e.to_s_expr(),
         }
     }
-}
\ No newline at end of file
+}
+
+impl ToSExpr for FunctionDefinition {
+    fn to_s_expr(self) -> SExpr<String> {
+        let mut args = vec![SExpr::Value(generic_name(&self.name, &self.type_params))];
+        for t in self.arg_list
+            .into_iter()
+            .map(|(name, ast_type)| {
+                SExpr::Value(format!("{}:{}", name.lexeme(), ast_type))
+            }) {
+            args.push(t)
+        }
+        args.push(self.body.to_s_expr());
+        args.push(SExpr::Value(format!("->{}", self.type_.to_string())));
+
+        SExpr::Function(String::from("function_define"), args)
+    }
+}
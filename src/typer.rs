@@ -1,34 +1,252 @@
-use std::collections::HashMap;
+use std::collections::{BTreeSet, HashMap};
 use itertools::Itertools;
 use thiserror::Error;
 use crate::ast::binary_operator::BinaryOperator;
 use crate::ast::Block;
 use crate::ast::expression::Expression;
-use crate::ast::statement::Statement;
+use crate::ast::pattern::{MatchArm, Pattern};
+use crate::ast::statement::{ForIterable, MethodSignature, Statement};
+use crate::ast::unary_operator::UnaryOperator;
+use crate::diagnostic::{Diagnostic, ToDiagnostic};
+use crate::llvm::runtime::Runtime;
+use crate::parser::span::Span;
 use crate::parser::token::Token;
 use crate::parser::token_kind::TokenKind;
 use crate::typed_ast::r#type::Type;
 use crate::typed_ast::r#type::Type::Unit;
 use crate::typed_ast::typed_expression::TypedExpression;
-use crate::typed_ast::typed_statement::{FunctionDefinition, TypedStatement};
+use crate::typed_ast::typed_pattern::{TypedMatchArm, TypedPattern};
+use crate::typed_ast::typed_statement::{FunctionDefinition, TypedForIterable, TypedStatement};
 use crate::typed_ast::typed_statement::TypedStatement::VariableDeclaration;
 use crate::typed_ast::TypedBlock;
-use crate::typer::TypingError::{ConflictingTypes, InvalidType, NameNotFound};
+use crate::typer::TypingError::{ArgumentCount, ConflictingTypes, IntegerOutOfRange, InvalidType, NameNotFound, Duplicate, MissingFields, NoField, NotIterable, OutsideLoop, UnknownLabel, UnknownType, UnsupportedOperator, UnsupportedUnaryOperator, NoVariant, NonExhaustive, UnreachableArm, TupleArity, NotIndexable, EmptyList, NotCallable, CannotInfer, UnknownTrait, ConflictingImpl, MissingMethod, NotInTrait, NoMethod, AmbiguousMethod, NotImplemented, NestedGeneric, Builtin, Reserved, AssignToCapture};
+use crate::typer::exhaustiveness::{Exhaustiveness, Pat};
+
+mod exhaustiveness;
 
 #[derive(Debug, Error)]
 pub enum TypingError {
     #[error("Unable to find {0} in enclosing scopes.")]
     NameNotFound(Token),
 
-    #[error("{0} declared as {1} but assigned to {1}.")]
+    #[error("{0} declared as {1} but assigned to {2}.")]
     ConflictingTypes(Token, Type, Type),
 
-    #[error("{0} MUST be {1}")]
-    InvalidType(Type, Type)
+    #[error("{0} MUST be {1} at ({2}).")]
+    InvalidType(Type, Type, Span),
+
+    #[error("{0} can not be applied to {1} at ({2}).")]
+    UnsupportedOperator(BinaryOperator, Type, Span),
+
+    #[error("{0} can not be applied to {1} at ({2}).")]
+    UnsupportedUnaryOperator(UnaryOperator, Type, Span),
+
+    #[error("{0} takes {1} arguments but was given {2}.")]
+    ArgumentCount(Token, usize, usize),
+
+    #[error("{0} does not fit in an int.")]
+    IntegerOutOfRange(Token),
+
+    #[error("{0} can not be looped over at ({1}).")]
+    NotIterable(Type, Span),
+
+    #[error("{0} can only be used inside a loop at ({1}).")]
+    OutsideLoop(&'static str, Span),
+
+    #[error("{0} does not label an enclosing loop.")]
+    UnknownLabel(Token),
+
+    #[error("{0} is not a known type at ({1}).")]
+    UnknownType(Type, Span),
+
+    #[error("{0} has no field {1}.")]
+    NoField(Type, Token),
+
+    #[error("{0} is missing the fields {1:?} at ({2}).")]
+    MissingFields(Type, Vec<String>, Span),
+
+    #[error("{0} is defined more than once.")]
+    Duplicate(Token),
+
+    #[error("{0} has no variant {1}.")]
+    NoVariant(Type, Token),
+
+    #[error("{0} is not covered by the match at ({2}), for example {1}.")]
+    NonExhaustive(Type, String, Span),
+
+    #[error("The match arm at ({0}) can never be reached.")]
+    UnreachableArm(Span),
+
+    #[error("{0} can not be taken apart into {1} names at ({2}).")]
+    TupleArity(Type, usize, Span),
+
+    #[error("{0} can not be indexed at ({1}).")]
+    NotIndexable(Type, Span),
+
+    #[error("The type of the empty list at ({0}) is unknown.")]
+    EmptyList(Span),
+
+    #[error("{0} can not be called at ({1}).")]
+    NotCallable(Type, Span),
+
+    #[error("The type {1} stands for in {0} can not be inferred.")]
+    CannotInfer(Token, String),
+
+    #[error("{0} is not a known trait.")]
+    UnknownTrait(Token),
+
+    #[error("{0} is already implemented for {1}.")]
+    ConflictingImpl(Token, Type),
+
+    #[error("The impl of {0} is missing the method {1}.")]
+    MissingMethod(Token, String),
+
+    #[error("{0} is not a method of {1}.")]
+    NotInTrait(Token, Token),
+
+    #[error("{0} has no method {1}.")]
+    NoMethod(Type, Token),
+
+    #[error("{1} is a method of more than one trait {0} implements.")]
+    AmbiguousMethod(Type, Token),
+
+    #[error("{0} does not implement {1} at ({2}).")]
+    NotImplemented(Type, String, Span),
+
+    #[error("{0} is generic but not declared at the top level.")]
+    NestedGeneric(Token),
+
+    #[error("{0} is already a builtin function.")]
+    Builtin(Token),
+
+    #[error("{0} is the name of a function in the runtime.")]
+    Reserved(Token),
+
+    #[error("{0} is captured by the lambda assigning to it.")]
+    AssignToCapture(Token),
+}
+
+impl ToDiagnostic for TypingError {
+    fn to_diagnostic(&self) -> Diagnostic {
+        match self {
+            NameNotFound(name) => Diagnostic::error(format!("cannot find `{}` in this scope", name.lexeme()))
+                .with_primary(name.span(), "not found"),
+            ConflictingTypes(name, declared, assigned) => Diagnostic::error("mismatched types")
+                .with_primary(name.span(), format!("`{}` is {} but is given {}", name.lexeme(), declared, assigned)),
+            InvalidType(found, expected, span) => Diagnostic::error("mismatched types")
+                .with_primary(*span, format!("expected {}, found {}", expected, found)),
+            UnsupportedOperator(operator, type_, span) => Diagnostic::error(format!("`{}` cannot be applied to {}", operator, type_))
+                .with_primary(*span, format!("{} has no `{}`", type_, operator)),
+            UnsupportedUnaryOperator(operator, type_, span) => Diagnostic::error(format!("`{}` cannot be applied to {}", operator, type_))
+                .with_primary(*span, format!("{} has no `{}`", type_, operator)),
+            ArgumentCount(name, expected, given) => Diagnostic::error(format!("`{}` takes {} arguments but was given {}", name.lexeme(), expected, given))
+                .with_primary(name.span(), format!("expected {} arguments", expected)),
+            IntegerOutOfRange(literal) => Diagnostic::error("integer literal is out of range")
+                .with_primary(literal.span(), "does not fit in an int")
+                .with_note("an int is 32 bits, from -2147483648 to 2147483647"),
+            NotIterable(type_, span) => Diagnostic::error(format!("{} cannot be looped over", type_))
+                .with_primary(*span, "not a list or a range")
+                .with_help("loop over a range of ints with `start..end`"),
+            OutsideLoop(keyword, span) => Diagnostic::error(format!("`{}` outside of a loop", keyword))
+                .with_primary(*span, format!("cannot `{}` outside of a loop", keyword)),
+            UnknownLabel(label) => Diagnostic::error(format!("use of undeclared label `{}`", label.lexeme()))
+                .with_primary(label.span(), "no enclosing loop has this label"),
+            UnknownType(type_, span) => Diagnostic::error(format!("cannot find type `{}`", type_))
+                .with_primary(*span, "not declared before here")
+                .with_note("a struct or enum has to be declared before it is used, so it can't contain itself"),
+            NoField(type_, field) => Diagnostic::error(format!("no field `{}` on type {}", field.lexeme(), type_))
+                .with_primary(field.span(), "unknown field"),
+            MissingFields(type_, fields, span) => Diagnostic::error(format!("missing fields in {}", type_))
+                .with_primary(*span, format!("missing {}", fields.iter().map(|x| format!("`{}`", x)).join(", "))),
+            Duplicate(name) => Diagnostic::error(format!("`{}` is defined more than once", name.lexeme()))
+                .with_primary(name.span(), "defined again here"),
+            NoVariant(type_, variant) => Diagnostic::error(format!("no variant `{}` on enum {}", variant.lexeme(), type_))
+                .with_primary(variant.span(), "unknown variant"),
+            NonExhaustive(type_, witness, span) => Diagnostic::error(format!("non-exhaustive patterns: `{}` not covered", witness))
+                .with_primary(*span, format!("this {} may be `{}`", type_, witness))
+                .with_help("add an arm for it, or a `_` arm to match everything else"),
+            UnreachableArm(span) => Diagnostic::error("unreachable match arm")
+                .with_primary(*span, "the arms before this one already match everything it does"),
+            TupleArity(type_, names, span) => Diagnostic::error(format!("{} can not be taken apart into {} names", type_, names))
+                .with_primary(*span, match type_ {
+                    Type::Tuple(elements) => format!("expected {} names", elements.len()),
+                    _ => String::from("not a tuple"),
+                }),
+            NotIndexable(type_, span) => Diagnostic::error(format!("cannot index into {}", type_))
+                .with_primary(*span, "not a list"),
+            EmptyList(span) => Diagnostic::error("type annotations needed for an empty list")
+                .with_primary(*span, "cannot tell what this list holds")
+                .with_help("declare it with its type, like `let xs: list int = [];`"),
+            NotCallable(type_, span) => Diagnostic::error(format!("{} is not a function", type_))
+                .with_primary(*span, "cannot be called"),
+            CannotInfer(name, param) => Diagnostic::error(format!("type annotations needed for `{}`", name.lexeme()))
+                .with_primary(name.span(), format!("cannot infer the type of `{}`", param))
+                .with_help("a generic function can only be called, with each type parameter in the type of an argument"),
+            UnknownTrait(name) => Diagnostic::error(format!("cannot find trait `{}`", name.lexeme()))
+                .with_primary(name.span(), "not declared before here"),
+            ConflictingImpl(trait_, type_) => Diagnostic::error(format!("conflicting implementations of trait `{}` for {}", trait_.lexeme(), type_))
+                .with_primary(trait_.span(), "implemented again here")
+                .with_note("a type can only implement each trait once"),
+            MissingMethod(trait_, method) => Diagnostic::error(format!("not all trait methods implemented, missing `{}`", method))
+                .with_primary(trait_.span(), format!("missing `{}` in this impl", method)),
+            NotInTrait(method, trait_) => Diagnostic::error(format!("method `{}` is not a member of trait `{}`", method.lexeme(), trait_.lexeme()))
+                .with_primary(method.span(), format!("not a member of `{}`", trait_.lexeme())),
+            NoMethod(type_, method) => Diagnostic::error(format!("no method named `{}` found for {}", method.lexeme(), type_))
+                .with_primary(method.span(), "method not found")
+                .with_help("implement a trait with this method for the type, or bound the type parameter by one"),
+            AmbiguousMethod(type_, method) => Diagnostic::error(format!("multiple applicable methods named `{}` for {}", method.lexeme(), type_))
+                .with_primary(method.span(), "more than one trait has this method"),
+            NotImplemented(type_, trait_, span) => Diagnostic::error(format!("the trait bound `{}: {}` is not satisfied", type_, trait_))
+                .with_primary(*span, format!("the trait `{}` is not implemented for {}", trait_, type_)),
+            NestedGeneric(name) => Diagnostic::error(format!("generic function `{}` is declared inside a block", name.lexeme()))
+                .with_primary(name.span(), "declared here")
+                .with_help("generic functions have to be declared at the top level of the program"),
+            Builtin(name) => Diagnostic::error(format!("`{}` is a builtin function", name.lexeme()))
+                .with_primary(name.span(), "defined again here")
+                .with_help("give the function another name"),
+            Reserved(name) => Diagnostic::error(format!("`{}` is reserved for the runtime", name.lexeme()))
+                .with_primary(name.span(), "defined here")
+                .with_help("give the function another name"),
+            AssignToCapture(name) => Diagnostic::error(format!("cannot assign to `{}`, which the lambda captured", name.lexeme()))
+                .with_primary(name.span(), "assigned here")
+                .with_help("a lambda works on a copy of what it captures, so copy it into a variable of the lambda's own and assign to that"),
+        }
+    }
+}
+
+/// Generic type parameters, each with the traits it must implement.
+type TypeParams = Vec<(String, Vec<String>)>;
+
+/// A lambda whose body is being typed.
+struct LambdaScope {
+    /// The index of the scope holding its parameters, so anything declared in an earlier
+    /// scope comes from outside the lambda.
+    base: usize,
+    captures: Vec<(Token, Type)>,
 }
 
 pub struct Typer {
     scopes: Vec<HashMap<String, Type>>,
+    /// Every named function declared so far with its type parameters and their bounds, which
+    /// variables can shadow.
+    functions: HashMap<String, (TypeParams, Type)>,
+    /// The type parameters of the generic functions around the statement being typed, with
+    /// the traits each must implement.
+    type_params: TypeParams,
+    /// What `Self` is in the trait or impl being typed.
+    self_type: Option<Type>,
+    /// Every trait declared so far, with the types of its methods in order.
+    traits: HashMap<String, Vec<(Token, Type)>>,
+    /// Every trait and type with an impl, which there can only be one of.
+    impls: BTreeSet<(String, Type)>,
+    /// The lambdas around the expression being typed, innermost last.
+    lambdas: Vec<LambdaScope>,
+    /// The labels of the loops around the statement being typed, innermost last.
+    loops: Vec<Option<Token>>,
+    /// Every struct declared so far, with its fields in order.
+    structs: HashMap<String, Vec<(Token, Type)>>,
+    /// Every enum declared so far, with its variants and their payloads in order.
+    enums: HashMap<String, Vec<(Token, Vec<Type>)>>,
     typed_ast: Vec<TypedStatement>
 }
 
@@ -36,6 +254,15 @@ impl Typer {
     pub fn type_ast(ast: Vec<Statement>) -> Result<Vec<TypedStatement>, TypingError> {
         let mut typer = Self {
             scopes: vec![Default::default()],
+            functions: HashMap::new(),
+            type_params: vec![],
+            self_type: None,
+            traits: HashMap::new(),
+            impls: BTreeSet::new(),
+            lambdas: vec![],
+            loops: vec![],
+            structs: HashMap::new(),
+            enums: HashMap::new(),
             typed_ast: vec![],
         };
 
@@ -43,19 +270,34 @@ impl Typer {
         Ok(typer.typed_ast)
     }
 
-    fn find_in_scopes(&self, name: impl AsRef<str>) -> Option<Type> {
-        let name = name.as_ref();
-        for scope_index in (0..self.scopes.len()).rev() {
-            if self.scopes[scope_index].contains_key(name) {
-                return Some(self.scopes[scope_index][name].clone())
+    /// The type of the variable `name`, which the lambdas it is used in capture if it was
+    /// declared outside them.
+    fn find_in_scopes(&mut self, name: &Token) -> Option<Type> {
+        let scope_index = self.scope_of(name.lexeme())?;
+        let type_ = self.scopes[scope_index][name.lexeme()].clone();
+        self.capture(name, scope_index, &type_);
+        Some(type_)
+    }
+
+    fn scope_of(&self, name: &str) -> Option<usize> {
+        (0..self.scopes.len()).rev().find(|x| self.scopes[*x].contains_key(name))
+    }
+
+    fn capture(&mut self, name: &Token, scope_index: usize, type_: &Type) {
+        for lambda in self.lambdas.iter_mut().filter(|x| x.base > scope_index) {
+            if !lambda.captures.iter().any(|(x, _)| x.lexeme() == name.lexeme()) {
+                lambda.captures.push((name.clone(), type_.clone()));
             }
         }
-
-        return None
     }
 
-    fn current_scope(&self) -> &HashMap<String, Type> {
-        self.scopes.last().unwrap()
+    /// A lambda only has a copy of what it captures, so an assignment to a variable from
+    /// outside it would never be seen there.
+    fn check_not_captured(&self, name: &Token, scope_index: usize) -> Result<(), TypingError> {
+        match self.lambdas.last() {
+            Some(lambda) if lambda.base > scope_index => Err(AssignToCapture(name.clone())),
+            _ => Ok(())
+        }
     }
 
     fn current_scope_mut(&mut self) -> &mut HashMap<String, Type> {
@@ -72,13 +314,15 @@ impl Typer {
     }
 
     fn type_var_assignment(&mut self, var: Token, value: Expression) -> Result<TypedStatement, TypingError> {
-        let typed_value = self.type_expression(value)?;
-
-        if !self.current_scope().contains_key(var.lexeme()) {
+        let Some(scope_index) = self.scope_of(var.lexeme()) else {
             return Err(NameNotFound(var))
-        }
+        };
+        self.check_not_captured(&var, scope_index)?;
+        let expected = self.scopes[scope_index][var.lexeme()].clone();
+        self.capture(&var, scope_index, &expected);
+        let typed_value = self.type_expression_as(value, &expected)?;
 
-        let value_in_scope = self.current_scope_mut().get_mut(var.lexeme()).unwrap();
+        let value_in_scope = self.scopes[scope_index].get_mut(var.lexeme()).unwrap();
 
         if value_in_scope.clone() != typed_value.get_type() {
             return Err(ConflictingTypes(var, value_in_scope.clone(), typed_value.get_type()))
@@ -91,29 +335,216 @@ impl Typer {
         })
     }
 
+    /// Type `to.a.b = value`, where `fields` is `[a, b]`.
+    fn type_field_assignment(&mut self, to: Token, fields: Vec<Token>, value: Expression) -> Result<TypedStatement, TypingError> {
+        let typed_value = self.type_expression(value)?;
+        let type_ = self.find_in_scopes(&to).ok_or(NameNotFound(to.clone()))?;
+        self.check_not_captured(&to, self.scope_of(to.lexeme()).unwrap())?;
+
+        let mut field_type = type_.clone();
+        let mut path = vec![];
+        for field in fields {
+            let (index, next) = self.field(&field_type, &field)?;
+            path.push((field, index));
+            field_type = next;
+        }
+
+        if field_type != typed_value.get_type() {
+            let (field, _) = path.pop().unwrap();
+            return Err(ConflictingTypes(field, field_type, typed_value.get_type()))
+        }
+
+        Ok(TypedStatement::FieldAssignment {
+            to,
+            type_,
+            fields: path,
+            value: typed_value,
+        })
+    }
+
+    /// The index and type of `field` in a struct of type `of`, or of the element numbered
+    /// `field` in a tuple.
+    fn field(&self, of: &Type, field: &Token) -> Result<(usize, Type), TypingError> {
+        if let Type::Tuple(elements) = of {
+            return field.lexeme().parse::<usize>().ok()
+                .and_then(|index| Some((index, elements.get(index)?.clone())))
+                .ok_or(NoField(of.clone(), field.clone()))
+        }
+        let Type::Struct(name) = of else {
+            return Err(NoField(of.clone(), field.clone()))
+        };
+        self.structs[name].iter()
+            .find_position(|(x, _)| x.lexeme() == field.lexeme())
+            .map(|(index, (_, type_))| (index, type_.clone()))
+            .ok_or(NoField(of.clone(), field.clone()))
+    }
+
+    /// Look up whether each name in `type_` is `Self`, a type parameter, a struct or an enum,
+    /// which must have been declared.
+    fn resolve_type(&self, type_: Type, span: Span) -> Result<Type, TypingError> {
+        match type_ {
+            Type::Named(name) if name == "Self" && self.self_type.is_some() => Ok(self.self_type.clone().unwrap()),
+            Type::Named(name) if self.type_params.iter().any(|(x, _)| x == &name) => Ok(Type::Param(name)),
+            Type::Named(name) if self.structs.contains_key(&name) => Ok(Type::Struct(name)),
+            Type::Named(name) if self.enums.contains_key(&name) => Ok(Type::Enum(name)),
+            Type::Named(_) => Err(UnknownType(type_, span)),
+            Type::List(element) => Ok(Type::List(Box::new(self.resolve_type(*element, span)?))),
+            Type::Tuple(elements) => Ok(Type::Tuple(elements.into_iter().map(|x| self.resolve_type(x, span)).try_collect()?)),
+            Type::Function(params, return_type) => Ok(Type::Function(
+                params.into_iter().map(|x| self.resolve_type(x, span)).try_collect()?,
+                Box::new(self.resolve_type(*return_type, span)?)
+            )),
+            _ => Ok(type_)
+        }
+    }
+
+    /// Structs and enums share one namespace.
+    fn check_type_name(&self, name: &Token) -> Result<(), TypingError> {
+        if self.structs.contains_key(name.lexeme()) || self.enums.contains_key(name.lexeme()) {
+            return Err(Duplicate(name.clone()))
+        }
+        Ok(())
+    }
+
+    /// The tag and payload types of `Enum::variant`.
+    fn variant(&self, enum_name: &Token, variant: &Token) -> Result<(usize, Vec<Type>), TypingError> {
+        let type_ = Type::Enum(enum_name.lexeme().to_string());
+        let Some(variants) = self.enums.get(enum_name.lexeme()) else {
+            return Err(UnknownType(type_, enum_name.span()))
+        };
+        variants.iter()
+            .find_position(|(x, _)| x.lexeme() == variant.lexeme())
+            .map(|(tag, (_, payload))| (tag, payload.clone()))
+            .ok_or(NoVariant(type_, variant.clone()))
+    }
+
+    /// Type `list[index]`, giving back the type of the element too.
+    fn type_index(&mut self, list: Expression, index: Expression) -> Result<(TypedExpression, TypedExpression, Type), TypingError> {
+        let list = self.type_expression(list)?;
+        let Type::List(element) = list.get_type() else {
+            return Err(NotIndexable(list.get_type(), list.span()))
+        };
+        let index = self.type_expression(index)?;
+        if index.get_type() != Type::Int {
+            return Err(InvalidType(index.get_type(), Type::Int, index.span()))
+        }
+        Ok((list, index, *element))
+    }
+
+    /// Type `expression` where a value of type `expected` is wanted, which is the only way to
+    /// know what an empty list holds.
+    fn type_expression_as(&mut self, expression: Expression, expected: &Type) -> Result<TypedExpression, TypingError> {
+        match (expression, expected) {
+            (Expression::List(elements, span), Type::List(_)) if elements.is_empty() => {
+                Ok(TypedExpression::List(vec![], expected.clone(), span))
+            }
+            (expression, _) => self.type_expression(expression)
+        }
+    }
+
     fn type_statement(&mut self, statement: Statement) -> Result<TypedStatement, TypingError> {
         type S = Statement;
         type TS = TypedStatement;
         match statement {
             S::FunctionDefinitionStatement(def) => {
-                let def_clone = def.clone();
-                let func_def_in_scope = self.current_scope_mut()
-                    .entry(def.name.lexeme().to_string())
-                    .or_insert(def.type_.clone());
-                *func_def_in_scope = def.type_;
-
-                self.push_function(&def.arg_list);
-                let typed_func = TS::FunctionDefinitionStatement(FunctionDefinition {
-                    name: def_clone.name,
-                    type_: def_clone.type_,
-                    arg_list: def_clone.arg_list,
-                    body: self.type_block(def_clone.body)?,
+                // copies are only made of the generic functions at the top level
+                if !def.type_params.is_empty() && self.scopes.len() > 1 {
+                    return Err(NestedGeneric(def.name))
+                }
+                // calls are told apart from builtin ones by name alone
+                if Self::BUILTINS.contains(&def.name.lexeme()) {
+                    return Err(Builtin(def.name))
+                }
+                // nor from the runtime's, which live alongside them in the module
+                if Runtime::reserves(def.name.lexeme()) {
+                    return Err(Reserved(def.name))
+                }
+                let mut type_params: TypeParams = vec![];
+                for param in &def.type_params {
+                    if type_params.iter().any(|(x, _)| x == param.name.lexeme()) {
+                        return Err(Duplicate(param.name.clone()))
+                    }
+                    if let Some(bound) = param.bounds.iter().find(|x| !self.traits.contains_key(x.lexeme())) {
+                        return Err(UnknownTrait(bound.clone()))
+                    }
+                    type_params.push((
+                        param.name.lexeme().to_string(),
+                        param.bounds.iter().map(|x| x.lexeme().to_string()).collect()
+                    ));
+                }
+
+                // the type parameters are only types inside the function
+                let outer_type_params = self.type_params.len();
+                self.type_params.extend(type_params.iter().cloned());
+                let typed_func = self.resolve_signature(&def).and_then(|(arg_list, type_)| {
+                    let signature = Type::Function(arg_list.iter().map(|(_, x)| x.clone()).collect(), Box::new(type_.clone()));
+                    self.functions.insert(def.name.lexeme().to_string(), (type_params, signature));
+                    self.type_function_body(def, arg_list, type_)
                 });
-                self.scopes.pop();
+                self.type_params.truncate(outer_type_params);
+
+                Ok(TS::FunctionDefinitionStatement(typed_func?))
+            }
+            S::TraitDefinitionStatement(def) => {
+                if self.traits.contains_key(def.name.lexeme()) {
+                    return Err(Duplicate(def.name))
+                }
+
+                // `Self` stands for whichever type implements the trait
+                let outer_self = self.self_type.replace(Type::Param(String::from("Self")));
+                let methods = self.type_method_signatures(def.methods);
+                self.self_type = outer_self;
+                let methods = methods?;
+
+                self.traits.insert(def.name.lexeme().to_string(), methods.clone());
+                Ok(TS::TraitDefinition { name: def.name, methods })
+            }
+            S::ImplStatement(block) => {
+                let Some(trait_methods) = self.traits.get(block.trait_.lexeme()).cloned() else {
+                    return Err(UnknownTrait(block.trait_))
+                };
+                let type_ = self.resolve_type(block.type_, block.span)?;
+                // coherence: which method a call means can't depend on which impl was found first
+                if !self.impls.insert((block.trait_.lexeme().to_string(), type_.clone())) {
+                    return Err(ConflictingImpl(block.trait_, type_))
+                }
+
+                let outer_self = self.self_type.replace(type_.clone());
+                let methods = self.type_impl_methods(&block.trait_, &type_, &trait_methods, block.methods);
+                self.self_type = outer_self;
+
+                Ok(TS::Impl { trait_: block.trait_, type_, methods: methods? })
+            }
+            S::StructDefinitionStatement(def) => {
+                self.check_type_name(&def.name)?;
+                let mut fields = vec![];
+                for (field, type_) in def.fields {
+                    if fields.iter().any(|(x, _): &(Token, Type)| x.lexeme() == field.lexeme()) {
+                        return Err(Duplicate(field))
+                    }
+                    let type_ = self.resolve_type(type_, field.span())?;
+                    fields.push((field, type_));
+                }
+
+                self.structs.insert(def.name.lexeme().to_string(), fields.clone());
+                Ok(TS::StructDefinition { name: def.name, fields })
+            }
+            S::EnumDefinitionStatement(def) => {
+                self.check_type_name(&def.name)?;
+                let mut variants = vec![];
+                for (variant, payload) in def.variants {
+                    if variants.iter().any(|(x, _): &(Token, Vec<Type>)| x.lexeme() == variant.lexeme()) {
+                        return Err(Duplicate(variant))
+                    }
+                    let payload: Vec<Type> = payload.into_iter().map(|x| self.resolve_type(x, variant.span())).try_collect()?;
+                    variants.push((variant, payload));
+                }
 
-                Ok(typed_func)
+                self.enums.insert(def.name.lexeme().to_string(), variants.clone());
+                Ok(TS::EnumDefinition { name: def.name, variants })
             }
-            S::VariableDeclaration { name:to, type_:t, value } => {
+            S::VariableDeclaration { name:to, type_:t, value, span } => {
+                let t = self.resolve_type(t, span)?;
                 let decl = self.current_scope_mut()
                     .entry(to.lexeme().to_string())
                     .or_insert(t.clone());
@@ -127,27 +558,443 @@ impl Typer {
 
                 Ok(VariableDeclaration {
                     name: to,
+                    value: self.type_expression_as(value, &t)?,
                     type_: t,
-                    value: self.type_expression(value)?,
                 })
             }
+            S::TupleDeclaration { names, type_, value, span } => {
+                let type_ = self.resolve_type(type_, span)?;
+                let elements = match &type_ {
+                    Type::Tuple(elements) if elements.len() == names.len() => elements.clone(),
+                    _ => return Err(TupleArity(type_, names.len(), span))
+                };
 
-            S::Assignment { to, value } => {
-                self.type_var_assignment(to, value)
+                let value = self.type_expression(value)?;
+                if value.get_type() != type_ {
+                    return Err(InvalidType(value.get_type(), type_, value.span()))
+                }
+
+                // `_` is a place to put an element that isn't needed, so it can come up more than once
+                let mut declared: Vec<&str> = vec![];
+                for (name, element) in names.iter().zip(elements) {
+                    if name.lexeme() == "_" {
+                        continue
+                    }
+                    if declared.contains(&name.lexeme()) {
+                        return Err(Duplicate(name.clone()))
+                    }
+                    declared.push(name.lexeme());
+                    self.current_scope_mut().insert(name.lexeme().to_string(), element);
+                }
+
+                Ok(TS::TupleDeclaration { names, type_, value })
+            }
+
+            S::Assignment { to, fields, operator, value, span } => {
+                // `x += 1` is `x = x + 1`, with errors in the operator pointing at the whole assignment
+                let value = match operator {
+                    Some(operator) => {
+                        let place = fields.iter().fold(Expression::Name(to.clone()), |of, field| Expression::FieldAccess {
+                            of: Box::new(of),
+                            field: field.clone(),
+                            span: to.span().to(field.span()),
+                        });
+                        Expression::BinaryOperation {
+                            lhs: Box::new(place),
+                            operator,
+                            rhs: Box::new(value),
+                            span,
+                        }
+                    },
+                    None => value
+                };
+                if fields.is_empty() {
+                    return self.type_var_assignment(to, value)
+                }
+                self.type_field_assignment(to, fields, value)
             },
-            S::While { condition, body } => {
+            S::IndexAssignment { list, index, operator, value, span } => {
+                let (list, index, element) = self.type_index(*list, *index)?;
+                let value = self.type_expression_as(value, &element)?;
+                // unlike `x += 1`, `xs[i] += 1` keeps its operator, so the list and index are only evaluated once
+                let result = match operator {
+                    Some(operator) => Self::binary_operation_type(&element, operator, &value, span)?,
+                    None => value.get_type()
+                };
+                if result != element {
+                    return Err(InvalidType(result, element, value.span()))
+                }
+                Ok(TS::IndexAssignment { list: Box::new(list), index: Box::new(index), operator, value, span })
+            },
+            S::While { label, condition, body, span: _ } => {
+                let condition = self.type_expression(condition)?;
                 Ok(TypedStatement::While {
-                    condition: self.type_expression(condition)?,
-                    body: self.type_block(body)?
+                    label: label.clone(),
+                    condition,
+                    body: self.type_loop_body(label, body)?
                 })
             },
-            S::Return(e) => Ok(TS::Return(self.type_expression(e)?)),
+            S::For { label, variable, iterable, body, span: _ } => {
+                let (iterable, variable_type) = match iterable {
+                    ForIterable::Range { start, end, inclusive } => {
+                        let start = self.type_expression(*start)?;
+                        let end = self.type_expression(*end)?;
+                        for bound in [&start, &end] {
+                            if bound.get_type() != Type::Int {
+                                return Err(InvalidType(bound.get_type(), Type::Int, bound.span()))
+                            }
+                        }
+                        (TypedForIterable::Range { start: Box::new(start), end: Box::new(end), inclusive }, Type::Int)
+                    }
+                    ForIterable::List(list) => {
+                        let list = self.type_expression(*list)?;
+                        let Type::List(element) = list.get_type() else {
+                            return Err(NotIterable(list.get_type(), list.span()))
+                        };
+                        (TypedForIterable::List(Box::new(list)), *element)
+                    }
+                };
+
+                // the loop variable only lives as long as the body
+                self.scopes.push(HashMap::from([(variable.lexeme().to_string(), variable_type)]));
+                let body = self.type_loop_body(label.clone(), body);
+                self.scopes.pop();
+
+                Ok(TS::For { label, variable, iterable, body: body? })
+            },
+            S::Break(label, span) => {
+                self.check_loop_control("break", &label, span)?;
+                Ok(TS::Break(label))
+            },
+            S::Continue(label, span) => {
+                self.check_loop_control("continue", &label, span)?;
+                Ok(TS::Continue(label))
+            },
+            S::Return(e, _) => Ok(TS::Return(self.type_expression(e)?)),
             S::Expression(e) => Ok(TS::Expression(self.type_expression(e)?)),
         }
     }
 
 
 
+    /// The arguments of `def` and what it returns, with their types resolved.
+    fn resolve_signature(&self, def: &crate::ast::statement::FunctionDefinition) -> Result<(Vec<(Token, Type)>, Type), TypingError> {
+        let arg_list: Vec<(Token, Type)> = def.arg_list.iter()
+            .map(|(name, type_)| Ok((name.clone(), self.resolve_type(type_.clone(), name.span())?)))
+            .try_collect()?;
+        let type_ = self.resolve_type(def.type_.clone(), def.name.span())?;
+        Ok((arg_list, type_))
+    }
+
+    /// The type of each method of a trait, where `self` is always the first argument.
+    fn type_method_signatures(&self, methods: Vec<MethodSignature>) -> Result<Vec<(Token, Type)>, TypingError> {
+        let mut typed: Vec<(Token, Type)> = vec![];
+        for method in methods {
+            if typed.iter().any(|(x, _)| x.lexeme() == method.name.lexeme()) {
+                return Err(Duplicate(method.name))
+            }
+            let params: Vec<Type> = method.arg_list.into_iter()
+                .map(|(name, type_)| self.resolve_type(type_, name.span()))
+                .try_collect()?;
+            let type_ = self.resolve_type(method.type_, method.name.span())?;
+            typed.push((method.name, Type::Function(params, Box::new(type_))));
+        }
+        Ok(typed)
+    }
+
+    /// Type the methods of the impl of `trait_` for `type_`, which must be exactly the methods
+    /// of the trait with `Self` replaced by `type_`.
+    fn type_impl_methods(
+        &mut self,
+        trait_: &Token,
+        type_: &Type,
+        trait_methods: &[(Token, Type)],
+        methods: Vec<crate::ast::statement::FunctionDefinition>
+    ) -> Result<Vec<FunctionDefinition>, TypingError> {
+        let bindings = HashMap::from([(String::from("Self"), type_.clone())]);
+        let mut typed: Vec<FunctionDefinition> = vec![];
+        for def in methods {
+            if typed.iter().any(|x| x.name.lexeme() == def.name.lexeme()) {
+                return Err(Duplicate(def.name))
+            }
+            let Some((_, expected)) = trait_methods.iter().find(|(x, _)| x.lexeme() == def.name.lexeme()) else {
+                return Err(NotInTrait(def.name, trait_.clone()))
+            };
+
+            let (arg_list, return_type) = self.resolve_signature(&def)?;
+            let signature = Type::Function(arg_list.iter().map(|(_, x)| x.clone()).collect(), Box::new(return_type.clone()));
+            let expected = expected.substitute(&bindings);
+            if signature != expected {
+                return Err(ConflictingTypes(def.name, expected, signature))
+            }
+            typed.push(self.type_function_body(def, arg_list, return_type)?);
+        }
+
+        if let Some((missing, _)) = trait_methods.iter().find(|(x, _)| !typed.iter().any(|y| y.name.lexeme() == x.lexeme())) {
+            return Err(MissingMethod(trait_.clone(), missing.lexeme().to_string()))
+        }
+        Ok(typed)
+    }
+
+    /// Whether there is an impl of `trait_` for `type_`, or `type_` is a type parameter that
+    /// must implement it.
+    fn implements(&self, type_: &Type, trait_: &str) -> bool {
+        match type_ {
+            Type::Param(name) => self.type_params.iter().rev()
+                .find(|(x, _)| x == name)
+                .is_some_and(|(_, bounds)| bounds.iter().any(|x| x == trait_)),
+            _ => self.impls.contains(&(trait_.to_string(), type_.clone()))
+        }
+    }
+
+    fn type_function_body(&mut self, def: crate::ast::statement::FunctionDefinition, arg_list: Vec<(Token, Type)>, type_: Type) -> Result<FunctionDefinition, TypingError> {
+        self.push_function(&arg_list);
+        // a function inside a loop can't break out of it
+        let outer_loops = std::mem::take(&mut self.loops);
+        let body = self.type_block(def.body);
+        self.loops = outer_loops;
+        self.scopes.pop();
+
+        Ok(FunctionDefinition {
+            name: def.name,
+            type_params: def.type_params,
+            type_,
+            arg_list,
+            body: body?,
+        })
+    }
+
+    fn type_loop_body(&mut self, label: Option<Token>, body: Block) -> Result<TypedBlock, TypingError> {
+        self.loops.push(label);
+        let body = self.type_block(body);
+        self.loops.pop();
+        body
+    }
+
+    /// `break` and `continue` need a loop to leave, and the loop their label names if they have one.
+    fn check_loop_control(&self, keyword: &'static str, label: &Option<Token>, span: Span) -> Result<(), TypingError> {
+        if self.loops.is_empty() {
+            return Err(OutsideLoop(keyword, span))
+        }
+        if let Some(label) = label {
+            let known = self.loops.iter().flatten().any(|x| x.lexeme() == label.lexeme());
+            if !known {
+                return Err(UnknownLabel(label.clone()))
+            }
+        }
+        Ok(())
+    }
+
+    fn type_match(&mut self, scrutinee: Expression, arms: Vec<MatchArm>, span: Span) -> Result<TypedExpression, TypingError> {
+        let scrutinee = self.type_expression(scrutinee)?;
+        let type_ = scrutinee.get_type();
+
+        let mut typed_arms: Vec<TypedMatchArm> = vec![];
+        for arm in arms {
+            // the names a pattern binds only live as long as its arm
+            self.scopes.push(HashMap::new());
+            let typed = self.type_pattern(arm.pattern, &type_)
+                .and_then(|pattern| Ok((pattern, self.type_block(arm.body)?)));
+            self.scopes.pop();
+            let (pattern, body) = typed?;
+
+            if let Some(first) = typed_arms.first() {
+                if body.type_ != first.body.type_ {
+                    return Err(InvalidType(body.type_, first.body.type_.clone(), arm.span))
+                }
+            }
+            typed_arms.push(TypedMatchArm { pattern, body, span: arm.span });
+        }
+
+        let checker = Exhaustiveness { enums: &self.enums };
+        let types = [type_.clone()];
+        let mut rows = vec![];
+        for arm in &typed_arms {
+            let row = vec![Pat::from(&arm.pattern)];
+            if checker.useful(&rows, &row, &types).is_none() {
+                return Err(UnreachableArm(arm.span))
+            }
+            rows.push(row);
+        }
+        if let Some(witness) = checker.useful(&rows, &[Pat::Wild], &types) {
+            return Err(NonExhaustive(type_.clone(), checker.describe(&witness[0], &type_), span))
+        }
+
+        Ok(TypedExpression::Match {
+            scrutinee: Box::new(scrutinee),
+            type_: typed_arms.first().map(|x| x.body.type_.clone()).unwrap_or(Unit),
+            arms: typed_arms,
+            span,
+        })
+    }
+
+    /// Type a pattern matching values of type `expected`, declaring its bindings in the current scope.
+    fn type_pattern(&mut self, pattern: Pattern, expected: &Type) -> Result<TypedPattern, TypingError> {
+        match pattern {
+            Pattern::Wildcard(_) => Ok(TypedPattern::Wildcard),
+            Pattern::Binding(name) => {
+                if self.current_scope_mut().insert(name.lexeme().to_string(), expected.clone()).is_some() {
+                    return Err(Duplicate(name))
+                }
+                Ok(TypedPattern::Binding(name, expected.clone()))
+            }
+            Pattern::Literal(literal) => {
+                let literal = self.type_expression(literal)?;
+                if literal.get_type() != *expected {
+                    return Err(InvalidType(literal.get_type(), expected.clone(), literal.span()))
+                }
+                Ok(TypedPattern::Literal(literal))
+            }
+            Pattern::Variant { enum_name, variant, fields, span } => {
+                let (tag, payload_types) = self.variant(&enum_name, &variant)?;
+                let type_ = Type::Enum(enum_name.lexeme().to_string());
+                if type_ != *expected {
+                    return Err(InvalidType(type_, expected.clone(), span))
+                }
+                if fields.len() != payload_types.len() {
+                    return Err(ArgumentCount(variant, payload_types.len(), fields.len()))
+                }
+
+                let fields = fields.into_iter().zip(payload_types.iter())
+                    .map(|(field, field_type)| self.type_pattern(field, field_type))
+                    .try_collect()?;
+                Ok(TypedPattern::Variant { type_, variant, tag, fields })
+            }
+        }
+    }
+
+    /// Type a lambda, which can't break out of loops around it but can use any variable
+    /// declared before it.
+    fn type_lambda(&mut self, params: Vec<(Token, Type)>, return_type: Type, body: Block, span: Span) -> Result<TypedExpression, TypingError> {
+        let params: Vec<(Token, Type)> = params.into_iter()
+            .map(|(name, type_)| Ok((name.clone(), self.resolve_type(type_, name.span())?)))
+            .try_collect()?;
+        let return_type = self.resolve_type(return_type, span)?;
+
+        self.lambdas.push(LambdaScope { base: self.scopes.len(), captures: vec![] });
+        self.push_function(&params);
+        let outer_loops = std::mem::take(&mut self.loops);
+        let body = self.type_block(body);
+        self.loops = outer_loops;
+        self.scopes.pop();
+        let captures = self.lambdas.pop().unwrap().captures;
+
+        // the body's value is what the lambda returns
+        let body = body?;
+        if body.type_ != return_type {
+            return Err(InvalidType(body.type_, return_type, span))
+        }
+
+        Ok(TypedExpression::Lambda {
+            type_: Type::Function(params.iter().map(|(_, x)| x.clone()).collect(), Box::new(return_type)),
+            params,
+            captures,
+            body,
+            span,
+        })
+    }
+
+    fn type_call(&mut self, callee: TypedExpression, arguments: Vec<TypedExpression>, span: Span) -> Result<TypedExpression, TypingError> {
+        let type_ = self.check_arguments(&callee, &arguments)?;
+        Ok(TypedExpression::Call { callee: Box::new(callee), arguments, type_, span })
+    }
+
+    /// `receiver.method(arguments)`, which calls the method of the one trait with a method of
+    /// that name that the type of the receiver implements.
+    fn type_method_call(&self, receiver: TypedExpression, method: Token, arguments: Vec<TypedExpression>, span: Span) -> Result<TypedExpression, TypingError> {
+        let receiver_type = receiver.get_type();
+        let candidates = self.traits.iter()
+            .filter(|(trait_, _)| self.implements(&receiver_type, trait_))
+            .filter_map(|(trait_, methods)| {
+                methods.iter()
+                    .find(|(x, _)| x.lexeme() == method.lexeme())
+                    .map(|(_, signature)| (trait_.clone(), signature.clone()))
+            })
+            .collect_vec();
+        let (trait_, signature) = match candidates.len() {
+            0 => return Err(NoMethod(receiver_type, method)),
+            1 => candidates.into_iter().next().unwrap(),
+            _ => return Err(AmbiguousMethod(receiver_type, method)),
+        };
+
+        let bindings = HashMap::from([(String::from("Self"), receiver_type)]);
+        let Type::Function(params, return_type) = signature.substitute(&bindings) else {
+            unreachable!("a method has a function type")
+        };
+        // the receiver is the `self` argument, so only the rest are checked against the call
+        let callee = TypedExpression::Function(method.clone(), Type::Function(params[1..].to_vec(), return_type));
+        let type_ = self.check_arguments(&callee, &arguments)?;
+
+        Ok(TypedExpression::MethodCall {
+            receiver: Box::new(receiver),
+            trait_,
+            method,
+            arguments,
+            type_,
+            span,
+        })
+    }
+
+    /// What each of `type_params` stands for in a call to a function with `signature`, going by
+    /// the types of the arguments. Arguments that don't fit are left for checking the call to
+    /// report.
+    fn infer(type_params: &[String], signature: &Type, arguments: &[TypedExpression]) -> HashMap<String, Type> {
+        let Type::Function(params, _) = signature else { unreachable!("a function has a function type") };
+        let mut bindings = HashMap::new();
+        for (param, argument) in params.iter().zip(arguments) {
+            Self::unify(type_params, param, &argument.get_type(), &mut bindings);
+        }
+        bindings
+    }
+
+    /// Bind the type parameters in `param` to the parts of `argument` in the same place. The
+    /// first binding of a parameter sticks.
+    fn unify(type_params: &[String], param: &Type, argument: &Type, bindings: &mut HashMap<String, Type>) {
+        match (param, argument) {
+            (Type::Param(name), _) if type_params.contains(name) => {
+                bindings.entry(name.clone()).or_insert(argument.clone());
+            }
+            (Type::List(param), Type::List(argument)) => Self::unify(type_params, param, argument, bindings),
+            (Type::Tuple(params), Type::Tuple(arguments)) => {
+                for (param, argument) in params.iter().zip(arguments) {
+                    Self::unify(type_params, param, argument, bindings);
+                }
+            }
+            (Type::Function(params, param_return), Type::Function(arguments, argument_return)) => {
+                for (param, argument) in params.iter().zip(arguments) {
+                    Self::unify(type_params, param, argument, bindings);
+                }
+                Self::unify(type_params, param_return, argument_return, bindings);
+            }
+            _ => {}
+        }
+    }
+
+    /// What calling `callee` with `arguments` returns, if they are what it takes.
+    fn check_arguments(&self, callee: &TypedExpression, arguments: &[TypedExpression]) -> Result<Type, TypingError> {
+        let Type::Function(params, return_type) = callee.get_type() else {
+            return Err(NotCallable(callee.get_type(), callee.span()))
+        };
+
+        if params.len() != arguments.len() {
+            return Err(match callee {
+                TypedExpression::Name(name, _) | TypedExpression::Function(name, _) => ArgumentCount(name.clone(), params.len(), arguments.len()),
+                // with no name to blame, say what type of function these arguments would need
+                _ => InvalidType(
+                    callee.get_type(),
+                    Type::Function(arguments.iter().map(|x| x.get_type()).collect(), return_type),
+                    callee.span()
+                ),
+            })
+        }
+        for (argument, param) in arguments.iter().zip(params) {
+            if argument.get_type() != param {
+                return Err(InvalidType(argument.get_type(), param, argument.span()))
+            }
+        }
+        Ok(*return_type)
+    }
+
     fn push_function(&mut self, args: &Vec<(Token, Type)>) {
         let mut new_scope = HashMap::new();
         for (token, type_) in args {
@@ -178,6 +1025,7 @@ impl Typer {
         match self.type_expression(condition)? {
             TypedExpression::Int(c) => {
                 Ok(TypedExpression::BinaryOperation {
+                    span: c.span(),
                     lhs: Box::new(TypedExpression::Int(c)),
                     operator: BinaryOperator::GreaterThan,
                     rhs: Box::new(TypedExpression::Int(Token::un_located(TokenKind::Int, "0"))),
@@ -188,14 +1036,126 @@ impl Typer {
         }
     }
 
+    /// The type of `lhs operator rhs`, where `lhs` has type `lhs`.
+    fn binary_operation_type(lhs: &Type, operator: BinaryOperator, rhs: &TypedExpression, span: Span) -> Result<Type, TypingError> {
+        // structs, enums, tuples, lists, functions and type parameters have no operators, not even ==
+        for operand in [lhs.clone(), rhs.get_type()] {
+            if let Type::Struct(_) | Type::Enum(_) | Type::Tuple(_) | Type::List(_) | Type::Function(..) | Type::Param(_) = operand {
+                return Err(UnsupportedOperator(operator, operand, span))
+            }
+        }
+
+        let arithmetic = matches!(operator,
+            BinaryOperator::Add | BinaryOperator::Sub | BinaryOperator::Mul | BinaryOperator::Div | BinaryOperator::Mod
+        );
+        let equality = matches!(operator, BinaryOperator::Equals | BinaryOperator::NotEquals);
+        let ordering = matches!(operator,
+            BinaryOperator::GreaterThan | BinaryOperator::GreaterEquals | BinaryOperator::LessThan | BinaryOperator::LessEquals
+        );
+
+        if *lhs == Type::Str || rhs.get_type() == Type::Str {
+            if *lhs != rhs.get_type() {
+                return Err(InvalidType(rhs.get_type(), lhs.clone(), rhs.span()))
+            }
+            if !(equality || operator == BinaryOperator::Add) {
+                return Err(UnsupportedOperator(operator, Type::Str, span))
+            }
+        }
+
+        // ints and floats never mix implicitly
+        if *lhs == Type::Float || rhs.get_type() == Type::Float {
+            if *lhs != rhs.get_type() {
+                return Err(InvalidType(rhs.get_type(), lhs.clone(), rhs.span()))
+            }
+            if matches!(operator, BinaryOperator::And | BinaryOperator::Or) {
+                return Err(UnsupportedOperator(operator, Type::Float, span))
+            }
+        }
+
+        // chars only compare; arithmetic goes through ord and chr
+        if *lhs == Type::Char || rhs.get_type() == Type::Char {
+            if *lhs != rhs.get_type() {
+                return Err(InvalidType(rhs.get_type(), lhs.clone(), rhs.span()))
+            }
+            if !(equality || ordering) {
+                return Err(UnsupportedOperator(operator, Type::Char, span))
+            }
+        }
+
+        if (equality || ordering) && *lhs != rhs.get_type() {
+            return Err(InvalidType(rhs.get_type(), lhs.clone(), rhs.span()))
+        }
+
+        // bools can be compared for equality but have no order
+        if ordering && *lhs == Type::Bool {
+            return Err(UnsupportedOperator(operator, Type::Bool, span))
+        }
+
+        // whatever arithmetic is left over after str and float is on ints
+        if arithmetic && !matches!(lhs, Type::Str | Type::Float) {
+            if *lhs != Type::Int {
+                return Err(UnsupportedOperator(operator, lhs.clone(), span))
+            }
+            if rhs.get_type() != Type::Int {
+                return Err(InvalidType(rhs.get_type(), Type::Int, rhs.span()))
+            }
+        }
+
+        Ok(if arithmetic { lhs.clone() } else { Type::Bool })
+    }
+
+    /// Int literals are lexed without their sign, so a negated literal may be one larger.
+    fn check_int_range(literal: &Token, negated: bool) -> Result<(), TypingError> {
+        let limit = if negated { i32::MIN.unsigned_abs() as u128 } else { i32::MAX as u128 };
+        match literal.lexeme().parse::<u128>() {
+            Ok(value) if value <= limit => Ok(()),
+            _ => Err(IntegerOutOfRange(literal.clone()))
+        }
+    }
+
+    /// The functions the compiler provides, which variables can shadow but functions can't.
+    const BUILTINS: [&'static str; 4] = ["len", "push", "ord", "chr"];
+
+    /// Functions the compiler provides rather than the program, or None if `name` is not one.
+    fn builtin_type(name: &Token, arguments: &[TypedExpression]) -> Option<Result<Type, TypingError>> {
+        match name.lexeme() {
+            "len" => Some(match arguments {
+                [arg] if matches!(arg.get_type(), Type::Str | Type::List(_)) => Ok(Type::Int),
+                [arg] => Err(InvalidType(arg.get_type(), Type::Str, arg.span())),
+                _ => Err(ArgumentCount(name.clone(), 1, arguments.len()))
+            }),
+            // adds to the end of a list in place, so every copy of the list sees it
+            "push" => Some(match arguments {
+                [list, value] => match list.get_type() {
+                    Type::List(element) if *element == value.get_type() => Ok(Unit),
+                    Type::List(element) => Err(InvalidType(value.get_type(), *element, value.span())),
+                    other => Err(InvalidType(other, Type::List(Box::new(value.get_type())), list.span())),
+                },
+                _ => Err(ArgumentCount(name.clone(), 2, arguments.len()))
+            }),
+            // explicit conversions between a char and its unicode scalar value
+            "ord" => Some(match arguments {
+                [arg] if arg.get_type() == Type::Char => Ok(Type::Int),
+                [arg] => Err(InvalidType(arg.get_type(), Type::Char, arg.span())),
+                _ => Err(ArgumentCount(name.clone(), 1, arguments.len()))
+            }),
+            "chr" => Some(match arguments {
+                [arg] if arg.get_type() == Type::Int => Ok(Type::Char),
+                [arg] => Err(InvalidType(arg.get_type(), Type::Int, arg.span())),
+                _ => Err(ArgumentCount(name.clone(), 1, arguments.len()))
+            }),
+            _ => None
+        }
+    }
+
     fn type_expression(&mut self, expression: Expression) -> Result<TypedExpression, TypingError> {
         match expression {
-            Expression::If { condition, true_block, else_block } => {
+            Expression::If { condition, true_block, else_block, span } => {
 
                 let condition = self.int_to_bool_demote(*condition)?;
 
                 if condition.get_type() != Type::Bool {
-                    return Err(InvalidType(condition.get_type(), Type::Bool))
+                    return Err(InvalidType(condition.get_type(), Type::Bool, condition.span()))
                 }
 
                 let true_block = self.type_block(true_block)?;
@@ -206,44 +1166,180 @@ impl Typer {
                     condition: Box::new(condition),
                     true_block,
                     else_block,
+                    span,
                 })
             },
-            Expression::BinaryOperation { lhs, operator, rhs } => {
+            Expression::BinaryOperation { lhs, operator, rhs, span } => {
                 let lhs = self.type_expression(*lhs)?;
                 let rhs = self.type_expression(*rhs)?;
-
-                let mut new_type = lhs.get_type();
-
-                match operator {
-                    BinaryOperator::Add => {}
-                    _ => {new_type = Type::Bool}
-                }
+                let new_type = Self::binary_operation_type(&lhs.get_type(), operator, &rhs, span)?;
                 Ok(TypedExpression::BinaryOperation {
                     lhs: Box::new(lhs),
                     operator,
                     rhs: Box::new(rhs),
                     type_: new_type,
+                    span,
                 })
             },
-            Expression::FunctionCall { name, arguments } => {
+            Expression::FunctionCall { name, arguments, span } => {
+                let arguments: Vec<TypedExpression> = arguments.into_iter().map(|x| self.type_expression(x)).try_collect()?;
+                // a variable holding a function is called through its value
+                if let Some(type_) = self.find_in_scopes(&name) {
+                    return self.type_call(TypedExpression::Name(name, type_), arguments, span)
+                }
+                let (type_, type_arguments) = match Self::builtin_type(&name, &arguments) {
+                    Some(type_) => (type_?, vec![]),
+                    None => {
+                        let (type_params, signature) = self.functions.get(name.lexeme()).cloned().ok_or(NameNotFound(name.clone()))?;
+                        let names = type_params.iter().map(|(x, _)| x.clone()).collect_vec();
+                        let bindings = Self::infer(&names, &signature, &arguments);
+                        let type_ = self.check_arguments(&TypedExpression::Function(name.clone(), signature.substitute(&bindings)), &arguments)?;
+                        let mut type_arguments = vec![];
+                        for (param, bounds) in type_params {
+                            let Some(type_argument) = bindings.get(&param) else {
+                                return Err(CannotInfer(name, param))
+                            };
+                            if let Some(bound) = bounds.into_iter().find(|x| !self.implements(type_argument, x)) {
+                                return Err(NotImplemented(type_argument.clone(), bound, span))
+                            }
+                            type_arguments.push(type_argument.clone());
+                        }
+                        (type_, type_arguments)
+                    }
+                };
+
                 Ok(TypedExpression::FunctionCall {
-                    name: name.clone(),
-                    arguments: arguments.into_iter().map(|x| self.type_expression(x)).try_collect()?,
-                    type_: self.find_in_scopes(name.lexeme()).ok_or(NameNotFound(name))?,
+                    name,
+                    arguments,
+                    type_arguments,
+                    type_,
+                    span,
                 })
             },
-            Expression::UnaryOperation { operator, rhs } =>  {
+            Expression::UnaryOperation { operator, rhs, span } =>  {
+                let rhs = match (operator, *rhs) {
+                    (UnaryOperator::Sub, Expression::Int(i)) => {
+                        Self::check_int_range(&i, true)?;
+                        TypedExpression::Int(i)
+                    }
+                    (_, rhs) => self.type_expression(rhs)?
+                };
+
+                // `-` negates numbers and `!` bools, and nothing else
+                let supported = match operator {
+                    UnaryOperator::Sub => matches!(rhs.get_type(), Type::Int | Type::Float),
+                    UnaryOperator::Not => rhs.get_type() == Type::Bool,
+                };
+                if !supported {
+                    return Err(UnsupportedUnaryOperator(operator, rhs.get_type(), span))
+                }
+
                 Ok(TypedExpression::UnaryOperation {
-                    operator, rhs: Box::new(self.type_expression(*rhs)?)
+                    operator, rhs: Box::new(rhs), span
                 })
             },
-            Expression::Int(i) => Ok(TypedExpression::Int(i)),
+            Expression::Int(i) => {
+                Self::check_int_range(&i, false)?;
+                Ok(TypedExpression::Int(i))
+            },
+            Expression::Float(f) => Ok(TypedExpression::Float(f)),
             Expression::Bool(b, t) => Ok(TypedExpression::Bool(b, t)),
-            Expression::List(_) => unimplemented!(),
+            Expression::Str(t) => Ok(TypedExpression::Str(t)),
+            Expression::Char(t) => Ok(TypedExpression::Char(t)),
+            Expression::List(elements, span) => {
+                let elements: Vec<TypedExpression> = elements.into_iter().map(|x| self.type_expression(x)).try_collect()?;
+                let Some(first) = elements.first() else {
+                    return Err(EmptyList(span))
+                };
+                let element = first.get_type();
+                if let Some(other) = elements.iter().find(|x| x.get_type() != element) {
+                    return Err(InvalidType(other.get_type(), element, other.span()))
+                }
+                Ok(TypedExpression::List(elements, Type::List(Box::new(element)), span))
+            },
+            Expression::Index { of, index, span } => {
+                let (of, index, type_) = self.type_index(*of, *index)?;
+                Ok(TypedExpression::Index { of: Box::new(of), index: Box::new(index), type_, span })
+            },
+            Expression::Tuple(elements, span) => {
+                let elements: Vec<TypedExpression> = elements.into_iter().map(|x| self.type_expression(x)).try_collect()?;
+                let type_ = Type::Tuple(elements.iter().map(|x| x.get_type()).collect());
+                Ok(TypedExpression::Tuple(elements, type_, span))
+            },
+            Expression::StructLiteral { name, fields, span } => {
+                let type_ = Type::Struct(name.lexeme().to_string());
+                let Some(declared) = self.structs.get(name.lexeme()).cloned() else {
+                    return Err(UnknownType(type_, name.span()))
+                };
+
+                let mut typed_fields: Vec<(usize, TypedExpression)> = vec![];
+                for (field, value) in fields {
+                    let (index, field_type) = self.field(&type_, &field)?;
+                    if typed_fields.iter().any(|(x, _)| *x == index) {
+                        return Err(Duplicate(field))
+                    }
+                    let value = self.type_expression(value)?;
+                    if value.get_type() != field_type {
+                        return Err(InvalidType(value.get_type(), field_type, value.span()))
+                    }
+                    typed_fields.push((index, value));
+                }
+
+                let missing = declared.iter().enumerate()
+                    .filter(|(index, _)| !typed_fields.iter().any(|(x, _)| x == index))
+                    .map(|(_, (field, _))| field.lexeme().to_string())
+                    .collect_vec();
+                if !missing.is_empty() {
+                    return Err(MissingFields(type_, missing, span))
+                }
+
+                Ok(TypedExpression::StructLiteral { fields: typed_fields, type_, span })
+            },
+            Expression::EnumVariant { enum_name, variant, payload, span } => {
+                let type_ = Type::Enum(enum_name.lexeme().to_string());
+                let (tag, payload_types) = self.variant(&enum_name, &variant)?;
+                if payload.len() != payload_types.len() {
+                    return Err(ArgumentCount(variant, payload_types.len(), payload.len()))
+                }
+
+                let payload: Vec<TypedExpression> = payload.into_iter().map(|x| self.type_expression(x)).try_collect()?;
+                for (value, expected) in payload.iter().zip(payload_types) {
+                    if value.get_type() != expected {
+                        return Err(InvalidType(value.get_type(), expected, value.span()))
+                    }
+                }
+
+                Ok(TypedExpression::EnumVariant { type_, variant, tag, payload, span })
+            },
+            Expression::Match { scrutinee, arms, span } => self.type_match(*scrutinee, arms, span),
+            Expression::FieldAccess { of, field, span } => {
+                let of = self.type_expression(*of)?;
+                let (index, type_) = self.field(&of.get_type(), &field)?;
+                Ok(TypedExpression::FieldAccess { of: Box::new(of), index, type_, span })
+            },
+            Expression::Lambda { params, return_type, body, span } => self.type_lambda(params, return_type, body, span),
+            Expression::Call { callee, arguments, span } => {
+                let callee = self.type_expression(*callee)?;
+                let arguments = arguments.into_iter().map(|x| self.type_expression(x)).try_collect()?;
+                self.type_call(callee, arguments, span)
+            }
+            Expression::MethodCall { receiver, method, arguments, span } => {
+                let receiver = self.type_expression(*receiver)?;
+                let arguments: Vec<TypedExpression> = arguments.into_iter().map(|x| self.type_expression(x)).try_collect()?;
+                self.type_method_call(receiver, method, arguments, span)
+            }
             Expression::Name(name) => {
-                self.find_in_scopes(name.lexeme())
-                    .map(|x| TypedExpression::Name(name.clone(), x))
-                    .ok_or(NameNotFound(name))
+                if let Some(type_) = self.find_in_scopes(&name) {
+                    return Ok(TypedExpression::Name(name, type_))
+                }
+                let Some((type_params, type_)) = self.functions.get(name.lexeme()) else {
+                    return Err(NameNotFound(name))
+                };
+                // with no arguments to go on there is nothing to say what a type parameter is
+                if let Some((param, _)) = type_params.first() {
+                    return Err(CannotInfer(name, param.clone()))
+                }
+                Ok(TypedExpression::Function(name, type_.clone()))
             }
         }
     }
@@ -262,8 +1358,508 @@ mod tests {
 
     #[test]
     fn forty_two() {
-        let ast = Parser::new(crate::testing::demo_programs::THE_UNIVERSE).parse().unwrap();
+        let ast = Parser::new(crate::testing::demo_programs::THE_UNIVERSE).unwrap().parse().unwrap();
         let typed = Typer::type_ast(ast).unwrap();
         assert_eq!(to_s_expr(typed)[0], SExpr::parse("(function_define main (int int) ->int)"))
     }
+
+    #[test]
+    fn spans_survive_typing() {
+        let text = crate::testing::demo_programs::THE_UNIVERSE_BY_ADDITION;
+        let ast = Parser::new(text).unwrap().parse().unwrap();
+        let typed = Typer::type_ast(ast).unwrap();
+        let TypedStatement::FunctionDefinitionStatement(def) = &typed[0] else { panic!() };
+        let TypedStatement::Return(e) = &def.body.body[0] else { panic!() };
+        assert_eq!(&text[e.span().byte_range()], "20 + 22");
+    }
+
+    #[test]
+    fn for_loops() {
+        let ast = Parser::new("fn main() -> int { let total: int = 0; for i in 0..=10 { total = total + i; } return total; }").unwrap().parse().unwrap();
+        assert!(Typer::type_ast(ast).is_ok());
+
+        let ast = Parser::new("for i in 0..true {}").unwrap().parse().unwrap();
+        assert!(matches!(Typer::type_ast(ast), Err(InvalidType(Type::Bool, Type::Int, _))));
+
+        let ast = Parser::new(r#"for c in "abc" {}"#).unwrap().parse().unwrap();
+        assert!(matches!(Typer::type_ast(ast), Err(NotIterable(Type::Str, _))));
+
+        // the loop variable is gone once the loop is over
+        let ast = Parser::new("fn main() -> int { for i in 0..3 {} return i; }").unwrap().parse().unwrap();
+        assert!(matches!(Typer::type_ast(ast), Err(NameNotFound(_))));
+    }
+
+    #[test]
+    fn loop_control() {
+        let ast = Parser::new("fn main() -> int { 'a: while true { for i in 0..3 { continue 'a; } break; } return 0; }").unwrap().parse().unwrap();
+        assert!(Typer::type_ast(ast).is_ok());
+
+        let ast = Parser::new("fn main() -> int { break; return 0; }").unwrap().parse().unwrap();
+        assert!(matches!(Typer::type_ast(ast), Err(OutsideLoop("break", _))));
+
+        let ast = Parser::new("'a: while true { while true { continue 'b; } }").unwrap().parse().unwrap();
+        assert!(matches!(Typer::type_ast(ast), Err(UnknownLabel(label)) if label.lexeme() == "'b"));
+
+        // functions don't see the loops they are declared in
+        let ast = Parser::new("while true { fn f() -> int { break; return 1; } }").unwrap().parse().unwrap();
+        assert!(matches!(Typer::type_ast(ast), Err(OutsideLoop("break", _))));
+    }
+
+    #[test]
+    fn compound_assignment() {
+        let ast = Parser::new(r#"fn main() -> int { let s: str = "a"; s += "b"; let f: float = 1.0; f *= 2.0; let x: int = 1; x %= 2; return x; }"#).unwrap().parse().unwrap();
+        assert!(Typer::type_ast(ast).is_ok());
+
+        let text = "fn main() -> int { let b: bool = true; b += true; return 0; }";
+        let ast = Parser::new(text).unwrap().parse().unwrap();
+        let Err(UnsupportedOperator(BinaryOperator::Add, Type::Bool, span)) = Typer::type_ast(ast) else { panic!() };
+        assert_eq!(&text[span.byte_range()], "b += true");
+
+        let ast = Parser::new(r#"fn main() -> int { let s: str = "a"; s -= "b"; return 0; }"#).unwrap().parse().unwrap();
+        assert!(matches!(Typer::type_ast(ast), Err(UnsupportedOperator(BinaryOperator::Sub, Type::Str, _))));
+
+        let ast = Parser::new("fn main() -> int { let x: int = 1; x += 1.5; return 0; }").unwrap().parse().unwrap();
+        assert!(matches!(Typer::type_ast(ast), Err(InvalidType(Type::Float, Type::Int, _))));
+
+        let ast = Parser::new("fn main() -> int { y += 1; return 0; }").unwrap().parse().unwrap();
+        assert!(matches!(Typer::type_ast(ast), Err(NameNotFound(_))));
+    }
+
+    fn type_error(text: &str) -> TypingError {
+        Typer::type_ast(Parser::new(text).unwrap().parse().unwrap()).unwrap_err()
+    }
+
+    #[test]
+    fn structs() {
+        let text = "struct P { x: int, b: bool } struct L { from: P, to: P }
+fn main() -> int { let l: L = L { to: P { x: 1, b: true }, from: P { b: false, x: 2 } }; l.to.x = l.from.x; return l.to.x; }";
+        let typed = Typer::type_ast(Parser::new(text).unwrap().parse().unwrap()).unwrap();
+        let TypedStatement::FunctionDefinitionStatement(def) = &typed[2] else { panic!() };
+        let TypedStatement::FieldAssignment { type_, fields, .. } = &def.body.body[1] else { panic!() };
+        assert_eq!(type_, &Type::Struct(String::from("L")));
+        assert_eq!(fields.iter().map(|(_, index)| *index).collect_vec(), vec![1, 0]);
+    }
+
+    #[test]
+    fn struct_errors() {
+        assert!(matches!(type_error("let p: P = 1;"), UnknownType(Type::Named(name), _) if name == "P"));
+        assert!(matches!(type_error("struct P { p: P }"), UnknownType(..)));
+        assert!(matches!(type_error("struct P { x: int, x: int }"), Duplicate(name) if name.lexeme() == "x"));
+        assert!(matches!(type_error("struct P { x: int } struct P { y: int }"), Duplicate(name) if name.lexeme() == "P"));
+        assert!(matches!(type_error("struct P { x: int } P { x: 1, y: 2 };"), NoField(_, name) if name.lexeme() == "y"));
+        assert!(matches!(type_error("struct P { x: int } P { x: 1, x: 2 };"), Duplicate(name) if name.lexeme() == "x"));
+        assert!(matches!(type_error("struct P { x: int, y: int, z: int } P { y: 1 };"), MissingFields(_, fields, _) if fields == ["x", "z"]));
+        assert!(matches!(type_error("struct P { x: int } P { x: true };"), InvalidType(Type::Bool, Type::Int, _)));
+        assert!(matches!(type_error("let x: int = 1; x.y;"), NoField(Type::Int, _)));
+        assert!(matches!(
+            type_error("struct P { x: int } let p: P = P { x: 1 }; p.x = true;"),
+            ConflictingTypes(name, Type::Int, Type::Bool) if name.lexeme() == "x"
+        ));
+        assert!(matches!(
+            type_error("struct P { x: int } let p: P = P { x: 1 }; p == p;"),
+            UnsupportedOperator(BinaryOperator::Equals, Type::Struct(_), _)
+        ));
+    }
+
+    const SHAPE: &str = "enum Shape { Circle(int), Rect(int, int), Empty }\n";
+
+    #[test]
+    fn enums() {
+        let text = format!("{}fn area(s: Shape) -> int {{ return match s {{ Shape::Circle(r) => 3 * r * r, Shape::Rect(w, h) => w * h, _ => 0 }}; }}", SHAPE);
+        let typed = Typer::type_ast(Parser::new(&text).unwrap().parse().unwrap()).unwrap();
+        assert_eq!(to_s_expr(typed)[1], SExpr::parse(
+            "(function_define area s:Shape (int (match Shape (=> (Shape::Circle r:int) (* (* int int) int)) (=> (Shape::Rect w:int h:int) (* int int)) (=> _ (int)))) ->int)"
+        ));
+
+        let text = "enum O { N, S(bool) } fn f(o: O, b: bool) -> int { return match o { O::S(true) => 1, O::S(false) => 2, O::N => match b { true => 3, false => 4 } }; }";
+        assert!(Typer::type_ast(Parser::new(text).unwrap().parse().unwrap()).is_ok());
+    }
+
+    #[test]
+    fn enum_errors() {
+        assert!(matches!(type_error(&format!("{}Shape::Square(1);", SHAPE)), NoVariant(_, name) if name.lexeme() == "Square"));
+        assert!(matches!(type_error(&format!("{}Shape::Rect(1);", SHAPE)), ArgumentCount(_, 2, 1)));
+        assert!(matches!(type_error(&format!("{}Shape::Circle(true);", SHAPE)), InvalidType(Type::Bool, Type::Int, _)));
+        assert!(matches!(type_error(&format!("{}Shape::Empty == Shape::Empty;", SHAPE)), UnsupportedOperator(BinaryOperator::Equals, Type::Enum(_), _)));
+        assert!(matches!(type_error("enum E { A, A }"), Duplicate(name) if name.lexeme() == "A"));
+        assert!(matches!(type_error("struct E { x: int } enum E { A }"), Duplicate(name) if name.lexeme() == "E"));
+        assert!(matches!(type_error("Nope::A;"), UnknownType(..)));
+    }
+
+    #[test]
+    fn match_errors() {
+        let error = type_error(&format!("{}let s: Shape = Shape::Empty; match s {{ Shape::Circle(_) => 1, Shape::Empty => 2 }};", SHAPE));
+        assert!(matches!(&error, NonExhaustive(_, witness, _) if witness == "Shape::Rect(_, _)"));
+        assert_eq!(error.to_diagnostic().message, "non-exhaustive patterns: `Shape::Rect(_, _)` not covered");
+
+        assert!(matches!(type_error("match 1 { 1 => 1, 2 => 2 };"), NonExhaustive(Type::Int, witness, _) if witness == "_"));
+        assert!(matches!(type_error("enum O { S(bool) } let o: O = O::S(true); match o { O::S(true) => 1 };"), NonExhaustive(_, witness, _) if witness == "O::S(false)"));
+
+        let text = "match true { _ => 1, false => 2 };";
+        let UnreachableArm(span) = type_error(text) else { panic!() };
+        assert_eq!(&text[span.byte_range()], "false => 2");
+
+        assert!(matches!(type_error("match 1 { x => 1, _ => 2 };"), UnreachableArm(_)));
+        assert!(matches!(type_error("match 1 { 1 => 1, _ => true };"), InvalidType(Type::Bool, Type::Int, _)));
+        assert!(matches!(type_error("match 1 { true => 1, _ => 2 };"), InvalidType(Type::Bool, Type::Int, _)));
+        assert!(matches!(type_error(&format!("{}match 1 {{ Shape::Empty => 1, _ => 2 }};", SHAPE)), InvalidType(Type::Enum(_), Type::Int, _)));
+        assert!(matches!(type_error(&format!("{}let s: Shape = Shape::Empty; match s {{ Shape::Rect(x, x) => 1, _ => 2 }};", SHAPE)), Duplicate(_)));
+        // bindings stay in their arm
+        assert!(matches!(type_error("match 1 { x => 1 }; x;"), NameNotFound(_)));
+    }
+
+    #[test]
+    fn tuples() {
+        let text = "fn divmod(a: int, b: int) -> (int, int) { return (a / b, a % b); }
+fn main() -> int { let (q, r): (int, int) = divmod(7, 2); let t: (int, (bool, int)) = (q, (true, r)); t.1.1 = 3; return t.1.1 + t.0; }";
+        let typed = Typer::type_ast(Parser::new(text).unwrap().parse().unwrap()).unwrap();
+        let TypedStatement::FunctionDefinitionStatement(def) = &typed[1] else { panic!() };
+        let TypedStatement::FieldAssignment { type_, fields, .. } = &def.body.body[2] else { panic!() };
+        assert_eq!(type_.to_string(), "(int, (bool, int))");
+        assert_eq!(fields.iter().map(|(_, index)| *index).collect_vec(), vec![1, 1]);
+
+        assert!(Typer::type_ast(Parser::new("let (_, _, x): (int, int, bool) = (1, 2, true); x;").unwrap().parse().unwrap()).is_ok());
+    }
+
+    #[test]
+    fn tuple_errors() {
+        assert!(matches!(type_error("let (a, b): (int, int, int) = (1, 2, 3);"), TupleArity(_, 2, _)));
+        assert!(matches!(type_error("let (a, b): int = 1;"), TupleArity(Type::Int, 2, _)));
+        assert!(matches!(type_error("let (a, b): (int, int) = (1, true);"), InvalidType(Type::Tuple(_), Type::Tuple(_), _)));
+        assert!(matches!(type_error("let (a, a): (int, int) = (1, 2);"), Duplicate(name) if name.lexeme() == "a"));
+        assert!(matches!(type_error("let t: (int, int) = (1, 2); t.2;"), NoField(_, index) if index.lexeme() == "2"));
+        assert!(matches!(type_error("(1, 2) == (1, 2);"), UnsupportedOperator(BinaryOperator::Equals, Type::Tuple(_), _)));
+        assert!(matches!(type_error("let t: (int, Nope) = (1, 2);"), UnknownType(Type::Named(_), _)));
+    }
+
+    #[test]
+    fn lists() {
+        let text = "fn main() -> int { let xs: list int = []; push(xs, 1); xs[0] += 2; let total: int = 0; for x in xs { total += x; } return xs[0] + len(xs) + total; }";
+        let typed = Typer::type_ast(Parser::new(text).unwrap().parse().unwrap()).unwrap();
+        let TypedStatement::FunctionDefinitionStatement(def) = &typed[0] else { panic!() };
+        assert_eq!(to_s_expr(def.body.body.clone()), vec![
+            SExpr::parse("(variable_declaration xs:list<int> (list))"),
+            SExpr::parse("(push list<int> int)"),
+            SExpr::parse("(+= (index list<int> int) int)"),
+            SExpr::parse("(variable_declaration total:int int)"),
+            SExpr::parse("(for list<int> (= total (+ int int)))"),
+            SExpr::parse("(int (+ (+ (index list<int> int) (len list<int>)) int))"),
+        ]);
+
+        let nested = "let grid: list list int = [[1, 2], [3]]; grid[1][0] = grid[0][1];";
+        assert!(Typer::type_ast(Parser::new(nested).unwrap().parse().unwrap()).is_ok());
+    }
+
+    #[test]
+    fn list_errors() {
+        assert!(matches!(type_error("[1, true];"), InvalidType(Type::Bool, Type::Int, _)));
+        assert!(matches!(type_error("[];"), EmptyList(_)));
+        assert!(matches!(type_error("let xs: list int = [true];"), ConflictingTypes(..)));
+        assert!(matches!(type_error("let xs: list int = [1]; xs[true];"), InvalidType(Type::Bool, Type::Int, _)));
+        assert!(matches!(type_error("let x: int = 1; x[0];"), NotIndexable(Type::Int, _)));
+        assert!(matches!(type_error("let xs: list int = [1]; xs[0] = 'a';"), InvalidType(Type::Char, Type::Int, _)));
+        assert!(matches!(type_error("let xs: list int = [1]; xs[0] += 1.5;"), InvalidType(Type::Float, Type::Int, _)));
+        assert!(matches!(type_error("let xs: list bool = [true]; xs[0] -= false;"), UnsupportedOperator(BinaryOperator::Sub, Type::Bool, _)));
+        assert!(matches!(type_error("let xs: list int = [1]; push(xs, 1.0);"), InvalidType(Type::Float, Type::Int, _)));
+        assert!(matches!(type_error("push(1, 2);"), InvalidType(Type::Int, Type::List(_), _)));
+        assert!(matches!(type_error("let xs: list int = [1]; push(xs);"), ArgumentCount(_, 2, 1)));
+        assert!(matches!(type_error("len(true);"), InvalidType(Type::Bool, Type::Str, _)));
+        assert!(matches!(type_error("[1] == [1];"), UnsupportedOperator(BinaryOperator::Equals, Type::List(_), _)));
+        assert!(matches!(type_error("let xs: list int = [1]; xs + xs;"), UnsupportedOperator(BinaryOperator::Add, Type::List(_), _)));
+        assert!(matches!(type_error("let xs: list list int = [[1]]; xs[0] += [2];"), UnsupportedOperator(BinaryOperator::Add, Type::List(_), _)));
+    }
+
+    #[test]
+    fn closures() {
+        let text = "fn twice(f: fn(int) -> int, x: int) -> int { return f(f(x)); }
+fn inc(x: int) -> int { return x + 1; }
+fn main() -> int {
+    let n: int = 1;
+    let m: int = 2;
+    let add: fn(int) -> fn(int) -> int = |x: int| -> fn(int) -> int { |y: int| -> int { x + y + n } };
+    return twice(inc, 1) + twice(add(m), 0) + add(1)(2);
+}";
+        let typed = Typer::type_ast(Parser::new(text).unwrap().parse().unwrap()).unwrap();
+        let TypedStatement::FunctionDefinitionStatement(def) = &typed[2] else { panic!() };
+        let TypedStatement::VariableDeclaration { value: TypedExpression::Lambda { captures, body, .. }, .. } = &def.body.body[2] else { panic!() };
+        // the outer lambda captures `n` for the one inside it, but not `m`, which it never uses
+        assert_eq!(captures.iter().map(|(x, _)| x.lexeme()).collect_vec(), vec!["n"]);
+        let TypedStatement::Expression(TypedExpression::Lambda { captures, .. }) = &body.body[0] else { panic!() };
+        assert_eq!(captures.iter().map(|(x, _)| x.lexeme()).collect_vec(), vec!["x", "n"]);
+
+        let TypedStatement::Return(value) = &def.body.body[3] else { panic!() };
+        // calls through `add` go through its value, `twice` and `inc` are called and passed by name
+        let call_add = SExpr::Function(String::from("call"), vec![SExpr::Value(String::from("fn(int) -> fn(int) -> int")), SExpr::Value(String::from("int"))]);
+        let SExpr::Function(_, sum) = value.clone().to_s_expr() else { panic!() };
+        let SExpr::Function(_, calls) = &sum[0] else { panic!() };
+        assert_eq!(calls[0], SExpr::parse("(twice inc int)"));
+        assert_eq!(calls[1], SExpr::Function(String::from("twice"), vec![call_add.clone(), SExpr::Value(String::from("int"))]));
+        assert_eq!(sum[1], SExpr::Function(String::from("call"), vec![call_add, SExpr::Value(String::from("int"))]));
+    }
+
+    #[test]
+    fn closure_errors() {
+        let inc = "fn inc(x: int) -> int { return x + 1; } ";
+        assert!(matches!(type_error(&format!("{}inc(true);", inc)), InvalidType(Type::Bool, Type::Int, _)));
+        assert!(matches!(type_error(&format!("{}inc(1, 2);", inc)), ArgumentCount(name, 1, 2) if name.lexeme() == "inc"));
+        assert!(matches!(type_error(&format!("{}let f: fn(int) -> bool = inc;", inc)), ConflictingTypes(..)));
+        assert!(matches!(type_error(&format!("{}inc == inc;", inc)), UnsupportedOperator(BinaryOperator::Equals, Type::Function(..), _)));
+        assert!(matches!(type_error("let x: int = 1; x(2);"), NotCallable(Type::Int, _)));
+        assert!(matches!(type_error("(|x: int| -> int { x })();"), InvalidType(Type::Function(..), Type::Function(..), _)));
+        assert!(matches!(type_error("|x: int| -> bool { x };"), InvalidType(Type::Int, Type::Bool, _)));
+        // the lambda's body is not inside the loop around it
+        assert!(matches!(type_error("while true { || -> int { break; 1 }; }"), OutsideLoop("break", _)));
+        // nor are its parameters outside it
+        assert!(matches!(type_error("|x: int| -> int { x }; x;"), NameNotFound(_)));
+        // it has a copy of what it captures, which it can't assign to
+        assert!(matches!(type_error("let n: int = 1; || -> int { n += 1; n };"), AssignToCapture(name) if name.lexeme() == "n"));
+        assert!(matches!(type_error("let n: int = 1; || -> int { || -> int { n = 2; n }; 1 };"), AssignToCapture(_)));
+        assert!(matches!(type_error("struct P { x: int } let p: P = P { x: 1 }; || -> int { p.x = 2; 1 };"), AssignToCapture(_)));
+        let text = "let n: int = 1; let f: fn(int) -> int = |x: int| -> int { let m: int = n; m += x; x = m; x }; n = f(n);";
+        assert!(Typer::type_ast(Parser::new(text).unwrap().parse().unwrap()).is_ok());
+    }
+
+    #[test]
+    fn builtin_names() {
+        assert!(matches!(type_error("fn len(x: int) -> int { return x; }"), Builtin(name) if name.lexeme() == "len"));
+        assert!(matches!(type_error("fn main() -> int { fn chr(x: int) -> int { return x; } return 1; }"), Builtin(_)));
+        assert!(matches!(type_error("fn malloc(x: int) -> int { return x; }"), Reserved(name) if name.lexeme() == "malloc"));
+        assert!(matches!(type_error("fn exit(x: int) -> int { return x; }"), Reserved(_)));
+        assert!(matches!(type_error("fn __list_new() -> int { return 1; }"), Reserved(_)));
+
+        // a variable named like a builtin hides it
+        let text = "let push: fn(int) -> int = |x: int| -> int { x + 1 }; push(2);";
+        let typed = Typer::type_ast(Parser::new(text).unwrap().parse().unwrap()).unwrap();
+        assert_eq!(to_s_expr(typed[1..].to_vec()), vec![SExpr::Function(
+            String::from("call"),
+            vec![SExpr::Value(String::from("fn(int) -> int")), SExpr::Value(String::from("int"))]
+        )]);
+        assert!(matches!(type_error("let len: int = 3; len(\"abc\");"), NotCallable(Type::Int, _)));
+    }
+
+    #[test]
+    fn generics() {
+        let text = "fn id<T>(x: T) -> T { return x; }\
+            fn first<T>(xs: list T) -> T { return xs[0]; }\
+            fn twice<T>(x: T) -> (T, T) { return (id(x), id(x)); }\
+            let a: int = id(1);\
+            let b: bool = first([true]);\
+            let c: (char, char) = twice('c');";
+        let typed = Typer::type_ast(Parser::new(text).unwrap().parse().unwrap()).unwrap();
+        assert_eq!(to_s_expr(typed[..2].to_vec()), vec![
+            SExpr::parse("(function_define id<T> x:T (T T) ->T)"),
+            SExpr::parse("(function_define first<T> xs:list<T> (T (index list<T> int)) ->T)"),
+        ]);
+        let type_arguments = typed[3..].iter()
+            .map(|x| match x {
+                TypedStatement::VariableDeclaration { value: TypedExpression::FunctionCall { type_arguments, .. }, .. } => type_arguments.clone(),
+                _ => panic!("{:?} is not a call", x)
+            })
+            .collect_vec();
+        assert_eq!(type_arguments, vec![vec![Type::Int], vec![Type::Bool], vec![Type::Char]]);
+
+        // inside twice, id is called with twice's own type parameter
+        let TypedStatement::FunctionDefinitionStatement(twice) = &typed[2] else { panic!() };
+        let TypedStatement::Return(TypedExpression::Tuple(elements, ..)) = &twice.body.body[0] else { panic!() };
+        assert!(matches!(&elements[0], TypedExpression::FunctionCall { type_arguments, .. } if type_arguments == &[Type::Param(String::from("T"))]));
+    }
+
+    #[test]
+    fn generic_errors() {
+        let id = "fn id<T>(x: T) -> T { return x; } ";
+        assert!(matches!(type_error(&format!("{}let a: bool = id(1);", id)), ConflictingTypes(_, Type::Bool, Type::Int)));
+        assert!(matches!(type_error(&format!("{}let f: fn(int) -> int = id;", id)), CannotInfer(name, param) if name.lexeme() == "id" && param == "T"));
+        assert!(matches!(type_error("fn pick<T>(a: T, b: T) -> T { return a; } pick(1, true);"), InvalidType(Type::Bool, Type::Int, _)));
+        assert!(matches!(type_error("fn make<T>() -> list T { let xs: list T = []; return xs; } make();"), CannotInfer(_, param) if param == "T"));
+        assert!(matches!(type_error("fn f<T, T>(x: T) -> T { return x; }"), Duplicate(name) if name.lexeme() == "T"));
+        assert!(matches!(type_error("fn add<T>(a: T, b: T) -> T { return a + b; }"), UnsupportedOperator(BinaryOperator::Add, Type::Param(_), _)));
+        // a type parameter means nothing outside its function
+        assert!(matches!(type_error(&format!("{}let x: T = 1;", id)), UnknownType(..)));
+        assert!(matches!(
+            type_error("fn main() -> int { fn id<T>(x: T) -> T { return x; } return id(1); }"),
+            NestedGeneric(name) if name.lexeme() == "id"
+        ));
+    }
+
+    #[test]
+    fn traits() {
+        let text = "struct P { x: int }\
+            trait Size { fn size(self) -> int; fn bigger(self, other: Self) -> bool; }\
+            impl Size for P { fn size(self) -> int { return self.x; } fn bigger(self, other: Self) -> bool { return self.size() > other.size(); } }\
+            impl Size for int { fn size(self) -> int { return self; } fn bigger(self, other: int) -> bool { return self > other; } }\
+            fn largest<T: Size>(a: T, b: T) -> int { if a.bigger(b) { return a.size(); } return b.size(); }\
+            let n: int = 1;\
+            let a: int = largest(P { x: 2 }, P { x: 3 }) + n.size();";
+        let typed = Typer::type_ast(Parser::new(text).unwrap().parse().unwrap()).unwrap();
+        assert_eq!(typed[1].clone().to_s_expr(), SExpr::Function(String::from("trait"), vec![
+            SExpr::Value(String::from("Size")),
+            SExpr::Value(String::from("size:fn(Self) -> int")),
+            SExpr::Value(String::from("bigger:fn(Self, Self) -> bool")),
+        ]));
+        // `Self` is the type the impl is for
+        let TypedStatement::Impl { type_, methods, .. } = &typed[2] else { panic!() };
+        assert_eq!(type_, &Type::Struct(String::from("P")));
+        assert_eq!(methods[1].arg_list.iter().map(|(_, x)| x.clone()).collect_vec(), vec![type_.clone(), type_.clone()]);
+        assert_eq!(to_s_expr(typed[4..5].to_vec()), vec![SExpr::parse(
+            "(function_define largest<T:Size> a:T b:T (if (method_call T Size.bigger T) (int (method_call T Size.size)) (int (method_call T Size.size))) ->int)"
+        )]);
+        assert_eq!(to_s_expr(typed[6..].to_vec()), vec![SExpr::parse(
+            "(variable_declaration a:int (+ (largest (P int) (P int)) (method_call int Size.size)))"
+        )]);
+    }
+
+    #[test]
+    fn trait_errors() {
+        let show = "trait Show { fn show(self) -> int; } ";
+        let int_show = "impl Show for int { fn show(self) -> int { return self; } } ";
+        assert!(matches!(type_error(int_show), UnknownTrait(name) if name.lexeme() == "Show"));
+        assert!(matches!(type_error(&format!("{}{}", show, show)), Duplicate(name) if name.lexeme() == "Show"));
+        assert!(matches!(type_error(&format!("{}{}{}", show, int_show, int_show)), ConflictingImpl(_, Type::Int)));
+        assert!(matches!(type_error(&format!("{}impl Show for int {{ }}", show)), MissingMethod(_, method) if method == "show"));
+        assert!(matches!(
+            type_error(&format!("{}impl Show for int {{ fn show(self) -> int {{ return 1; }} fn hide(self) -> int {{ return 1; }} }}", show)),
+            NotInTrait(method, _) if method.lexeme() == "hide"
+        ));
+        assert!(matches!(
+            type_error(&format!("{}impl Show for bool {{ fn show(self) -> bool {{ return self; }} }}", show)),
+            ConflictingTypes(_, Type::Function(..), Type::Function(..))
+        ));
+        assert!(matches!(type_error(&format!("{}true.show();", show)), NoMethod(Type::Bool, _)));
+        assert!(matches!(type_error(&format!("{}{}let x: int = 1; x.show(2);", show, int_show)), ArgumentCount(name, 0, 1) if name.lexeme() == "show"));
+        assert!(matches!(
+            type_error(&format!("{}{}trait Print {{ fn show(self) -> int; }} impl Print for int {{ fn show(self) -> int {{ return 0; }} }} let x: int = 1; x.show();", show, int_show)),
+            AmbiguousMethod(Type::Int, _)
+        ));
+        // a type parameter only has the methods of its bounds
+        assert!(matches!(type_error(&format!("{}fn f<T>(x: T) -> int {{ return x.show(); }}", show)), NoMethod(Type::Param(_), _)));
+        assert!(matches!(
+            type_error(&format!("{}{}fn f<T: Show>(x: T) -> int {{ return x.show(); }} f(true);", show, int_show)),
+            NotImplemented(Type::Bool, name, _) if name == "Show"
+        ));
+        assert!(matches!(type_error("fn f<T: Show>(x: T) -> T { return x; }"), UnknownTrait(_)));
+        assert!(matches!(type_error("let x: Self = 1;"), UnknownType(..)));
+    }
+
+    #[test]
+    fn str_operators() {
+        let ast = Parser::new(r#"fn main() -> bool { return "a" + "b" == "ab"; }"#).unwrap().parse().unwrap();
+        let typed = Typer::type_ast(ast).unwrap();
+        assert_eq!(to_s_expr(typed)[0], SExpr::parse("(function_define main (bool (== (+ str str) str)) ->bool)"));
+    }
+
+    #[test]
+    fn str_mismatches() {
+        let ast = Parser::new(r#""a" + 1;"#).unwrap().parse().unwrap();
+        assert!(matches!(Typer::type_ast(ast), Err(InvalidType(Type::Int, Type::Str, _))));
+
+        let ast = Parser::new(r#""a" > "b";"#).unwrap().parse().unwrap();
+        assert!(matches!(Typer::type_ast(ast), Err(UnsupportedOperator(BinaryOperator::GreaterThan, Type::Str, _))));
+
+        let ast = Parser::new(r#"len(1);"#).unwrap().parse().unwrap();
+        assert!(matches!(Typer::type_ast(ast), Err(InvalidType(Type::Int, Type::Str, _))));
+
+        let ast = Parser::new(r#"len("a", "b");"#).unwrap().parse().unwrap();
+        assert!(matches!(Typer::type_ast(ast), Err(ArgumentCount(_, 1, 2))));
+    }
+
+    #[test]
+    fn int_arithmetic() {
+        let ast = Parser::new("fn main() -> int { return 7 - 2 * 3 / 1 % 4; }").unwrap().parse().unwrap();
+        let typed = Typer::type_ast(ast).unwrap();
+        assert_eq!(to_s_expr(typed)[0], SExpr::parse("(function_define main (int (- int (% (/ (* int int) int) int))) ->int)"));
+
+        let ast = Parser::new("true * 2;").unwrap().parse().unwrap();
+        assert!(matches!(Typer::type_ast(ast), Err(UnsupportedOperator(BinaryOperator::Mul, Type::Bool, _))));
+
+        let ast = Parser::new("2 % true;").unwrap().parse().unwrap();
+        assert!(matches!(Typer::type_ast(ast), Err(InvalidType(Type::Bool, Type::Int, _))));
+
+        let ast = Parser::new(r#""a" - "b";"#).unwrap().parse().unwrap();
+        assert!(matches!(Typer::type_ast(ast), Err(UnsupportedOperator(BinaryOperator::Sub, Type::Str, _))));
+    }
+
+    #[test]
+    fn comparisons() {
+        let ast = Parser::new("fn main() -> bool { return (1 <= 2) != (3 < 4) == true; }").unwrap().parse().unwrap();
+        let typed = Typer::type_ast(ast).unwrap();
+        assert_eq!(to_s_expr(typed)[0], SExpr::parse("(function_define main (bool (== (!= (<= int int) (< int int)) bool)) ->bool)"));
+
+        let ast = Parser::new("true < false;").unwrap().parse().unwrap();
+        assert!(matches!(Typer::type_ast(ast), Err(UnsupportedOperator(BinaryOperator::LessThan, Type::Bool, _))));
+
+        let ast = Parser::new("1 != true;").unwrap().parse().unwrap();
+        assert!(matches!(Typer::type_ast(ast), Err(InvalidType(Type::Bool, Type::Int, _))));
+
+        let ast = Parser::new(r#""a" <= "b";"#).unwrap().parse().unwrap();
+        assert!(matches!(Typer::type_ast(ast), Err(UnsupportedOperator(BinaryOperator::LessEquals, Type::Str, _))));
+    }
+
+    #[test]
+    fn float_operators() {
+        let ast = Parser::new("fn main() -> bool { return 1.5 + -2.0 > 0.5; }").unwrap().parse().unwrap();
+        let typed = Typer::type_ast(ast).unwrap();
+        assert_eq!(to_s_expr(typed)[0], SExpr::parse("(function_define main (bool (> (+ float (- float)) float)) ->bool)"));
+    }
+
+    #[test]
+    fn unary_operators() {
+        for (text, operator, type_) in [(r#"-"ab";"#, UnaryOperator::Sub, Type::Str), ("-'a';", UnaryOperator::Sub, Type::Char), ("!1;", UnaryOperator::Not, Type::Int)] {
+            let UnsupportedUnaryOperator(found, found_type, span) = type_error(text) else { panic!("{} should not type", text) };
+            assert_eq!((found, found_type), (operator, type_));
+            // the whole operation is underlined, not just its operand
+            assert_eq!(&text[span.byte_range()], text.trim_end_matches(';'));
+        }
+        assert!(Typer::type_ast(Parser::new("-1.5; -(1 + 2); !true;").unwrap().parse().unwrap()).is_ok());
+    }
+
+    #[test]
+    fn no_int_float_mixing() {
+        let ast = Parser::new("1.5 + 1;").unwrap().parse().unwrap();
+        assert!(matches!(Typer::type_ast(ast), Err(InvalidType(Type::Int, Type::Float, _))));
+
+        let ast = Parser::new("1 == 1.0;").unwrap().parse().unwrap();
+        assert!(matches!(Typer::type_ast(ast), Err(InvalidType(Type::Float, Type::Int, _))));
+
+        let ast = Parser::new("let x: float = 1;").unwrap().parse().unwrap();
+        assert!(matches!(Typer::type_ast(ast), Err(ConflictingTypes(_, Type::Float, Type::Int))));
+
+        let ast = Parser::new("1.0 && 2.0;").unwrap().parse().unwrap();
+        assert!(matches!(Typer::type_ast(ast), Err(UnsupportedOperator(BinaryOperator::And, Type::Float, _))));
+    }
+
+    #[test]
+    fn char_operators() {
+        let ast = Parser::new("fn main() -> bool { return 'a' > chr(ord('b') + 1); }").unwrap().parse().unwrap();
+        let typed = Typer::type_ast(ast).unwrap();
+        assert_eq!(to_s_expr(typed)[0], SExpr::parse("(function_define main (bool (> char (chr (+ (ord char) int)))) ->bool)"));
+    }
+
+    #[test]
+    fn char_mismatches() {
+        let ast = Parser::new("'a' == 97;").unwrap().parse().unwrap();
+        assert!(matches!(Typer::type_ast(ast), Err(InvalidType(Type::Int, Type::Char, _))));
+
+        let ast = Parser::new("'a' + 'b';").unwrap().parse().unwrap();
+        assert!(matches!(Typer::type_ast(ast), Err(UnsupportedOperator(BinaryOperator::Add, Type::Char, _))));
+
+        let ast = Parser::new("ord(97);").unwrap().parse().unwrap();
+        assert!(matches!(Typer::type_ast(ast), Err(InvalidType(Type::Int, Type::Char, _))));
+
+        let ast = Parser::new("chr('a');").unwrap().parse().unwrap();
+        assert!(matches!(Typer::type_ast(ast), Err(InvalidType(Type::Char, Type::Int, _))));
+    }
+
+    #[test]
+    fn int_range() {
+        let ast = Parser::new("2147483647; -2147483648; 0x7FFF_FFFF;").unwrap().parse().unwrap();
+        assert!(Typer::type_ast(ast).is_ok());
+
+        let ast = Parser::new("1;\n 99999999999;").unwrap().parse().unwrap();
+        let error = Typer::type_ast(ast).unwrap_err();
+        assert!(matches!(error, IntegerOutOfRange(_)));
+        assert_eq!(error.to_string(), "Int(99999999999)@2, 2 does not fit in an int.");
+
+        let ast = Parser::new("-2147483649;").unwrap().parse().unwrap();
+        assert!(matches!(Typer::type_ast(ast), Err(IntegerOutOfRange(_))));
+    }
 }
\ No newline at end of file
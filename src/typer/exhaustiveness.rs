@@ -0,0 +1,194 @@
+use std::collections::HashMap;
+use itertools::Itertools;
+use crate::parser::token::Token;
+use crate::typed_ast::r#type::Type;
+use crate::typed_ast::typed_expression::TypedExpression;
+use crate::typed_ast::typed_pattern::TypedPattern;
+
+/// A pattern boiled down to what matters for which values it matches.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Pat {
+    /// Wildcards and bindings, which match anything.
+    Wild,
+    Constructor(Constructor, Vec<Pat>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Constructor {
+    Variant(usize),
+    Bool(bool),
+    /// An int or char, of which there are too many to list.
+    Literal(String),
+}
+
+impl From<&TypedPattern> for Pat {
+    fn from(pattern: &TypedPattern) -> Self {
+        match pattern {
+            TypedPattern::Wildcard | TypedPattern::Binding(..) => Pat::Wild,
+            TypedPattern::Literal(TypedExpression::Bool(value, _)) => Pat::Constructor(Constructor::Bool(*value), vec![]),
+            TypedPattern::Literal(TypedExpression::UnaryOperation { rhs, .. }) => {
+                let TypedExpression::Int(int) = rhs.as_ref() else { unreachable!("only ints are negated in patterns") };
+                Pat::Constructor(Constructor::Literal(format!("-{}", int.lexeme())), vec![])
+            }
+            TypedPattern::Literal(TypedExpression::Int(t) | TypedExpression::Char(t)) => {
+                Pat::Constructor(Constructor::Literal(t.lexeme().to_string()), vec![])
+            }
+            TypedPattern::Literal(_) => unreachable!("patterns only hold int, bool and char literals"),
+            TypedPattern::Variant { tag, fields, .. } => {
+                Pat::Constructor(Constructor::Variant(*tag), fields.iter().map(Pat::from).collect())
+            }
+        }
+    }
+}
+
+/// Checks `match` arms against each other with the usefulness algorithm from Maranget's
+/// "Warnings for pattern matching": a pattern is useful after some rows if there is a value
+/// it matches that none of the rows do.
+pub struct Exhaustiveness<'a> {
+    pub enums: &'a HashMap<String, Vec<(Token, Vec<Type>)>>,
+}
+
+impl Exhaustiveness<'_> {
+    /// Every constructor of `type_` with the types of its fields, or None when there are too
+    /// many to list and only a wildcard can cover them all.
+    fn constructors(&self, type_: &Type) -> Option<Vec<(Constructor, Vec<Type>)>> {
+        match type_ {
+            Type::Bool => Some(vec![
+                (Constructor::Bool(false), vec![]),
+                (Constructor::Bool(true), vec![]),
+            ]),
+            Type::Enum(name) => Some(self.enums[name].iter().enumerate()
+                .map(|(tag, (_, payload))| (Constructor::Variant(tag), payload.clone()))
+                .collect()),
+            _ => None
+        }
+    }
+
+    fn field_types(&self, type_: &Type, constructor: &Constructor) -> Vec<Type> {
+        match (type_, constructor) {
+            (Type::Enum(name), Constructor::Variant(tag)) => self.enums[name][*tag].1.clone(),
+            _ => vec![]
+        }
+    }
+
+    /// The rows that can still match once the first column is known to be `constructor`,
+    /// with that column replaced by the constructor's fields.
+    fn specialize(rows: &[Vec<Pat>], constructor: &Constructor, arity: usize) -> Vec<Vec<Pat>> {
+        rows.iter().filter_map(|row| {
+            let fields = match &row[0] {
+                Pat::Constructor(x, fields) if x == constructor => fields.clone(),
+                Pat::Constructor(..) => return None,
+                Pat::Wild => vec![Pat::Wild; arity],
+            };
+            Some(fields.into_iter().chain(row[1..].iter().cloned()).collect())
+        }).collect()
+    }
+
+    /// A value, as patterns, that `row` matches and none of `rows` do, if there is one.
+    pub fn useful(&self, rows: &[Vec<Pat>], row: &[Pat], types: &[Type]) -> Option<Vec<Pat>> {
+        let Some(first) = row.first() else {
+            return rows.is_empty().then(Vec::new)
+        };
+
+        match first {
+            Pat::Constructor(constructor, fields) => {
+                self.useful_constructor(rows, row, constructor, fields.clone(), types)
+            }
+            Pat::Wild => {
+                let seen = rows.iter()
+                    .filter_map(|x| match &x[0] {
+                        Pat::Constructor(constructor, _) => Some(constructor),
+                        Pat::Wild => None,
+                    })
+                    .collect_vec();
+                let all = self.constructors(&types[0]);
+
+                if let Some(all) = all.as_ref().filter(|all| all.iter().all(|(x, _)| seen.contains(&x))) {
+                    // every constructor is covered somewhere, so look inside each of them
+                    return all.iter().find_map(|(constructor, fields)| {
+                        self.useful_constructor(rows, row, constructor, vec![Pat::Wild; fields.len()], types)
+                    })
+                }
+
+                // some constructor is never mentioned, so only the wildcard rows can match it
+                let default = rows.iter()
+                    .filter(|x| x[0] == Pat::Wild)
+                    .map(|x| x[1..].to_vec())
+                    .collect_vec();
+                let rest = self.useful(&default, &row[1..], &types[1..])?;
+                let missing = match all {
+                    Some(all) => {
+                        let (constructor, fields) = all.into_iter().find(|(x, _)| !seen.contains(&x)).unwrap();
+                        Pat::Constructor(constructor, vec![Pat::Wild; fields.len()])
+                    }
+                    None => Pat::Wild,
+                };
+                Some([missing].into_iter().chain(rest).collect())
+            }
+        }
+    }
+
+    fn useful_constructor(&self, rows: &[Vec<Pat>], row: &[Pat], constructor: &Constructor, fields: Vec<Pat>, types: &[Type]) -> Option<Vec<Pat>> {
+        let field_types = self.field_types(&types[0], constructor);
+        let arity = field_types.len();
+        let rows = Self::specialize(rows, constructor, arity);
+        let row = fields.into_iter().chain(row[1..].iter().cloned()).collect_vec();
+        let types = field_types.into_iter().chain(types[1..].iter().cloned()).collect_vec();
+
+        let mut witness = self.useful(&rows, &row, &types)?;
+        let rest = witness.split_off(arity);
+        Some([Pat::Constructor(constructor.clone(), witness)].into_iter().chain(rest).collect())
+    }
+
+    /// How `pattern` of type `type_` would be written, like `Shape::Rect(_, _)`.
+    pub fn describe(&self, pattern: &Pat, type_: &Type) -> String {
+        match (pattern, type_) {
+            (Pat::Wild, _) => String::from("_"),
+            (Pat::Constructor(Constructor::Bool(value), _), _) => value.to_string(),
+            (Pat::Constructor(Constructor::Literal(value), _), _) => value.clone(),
+            (Pat::Constructor(Constructor::Variant(tag), fields), Type::Enum(name)) => {
+                let (variant, field_types) = &self.enums[name][*tag];
+                let mut text = format!("{}::{}", name, variant.lexeme());
+                if !fields.is_empty() {
+                    let fields = fields.iter().zip(field_types).map(|(x, t)| self.describe(x, t)).join(", ");
+                    text += &format!("({})", fields);
+                }
+                text
+            }
+            (Pat::Constructor(..), _) => unreachable!("only enums have variants"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parser::token_kind::TokenKind;
+    use super::*;
+
+    fn variant(tag: usize, fields: Vec<Pat>) -> Pat {
+        Pat::Constructor(Constructor::Variant(tag), fields)
+    }
+
+    #[test]
+    fn witnesses() {
+        let option = (Token::un_located(TokenKind::Name, "None"), vec![]);
+        let some = (Token::un_located(TokenKind::Name, "Some"), vec![Type::Bool]);
+        let enums = HashMap::from([(String::from("O"), vec![option, some])]);
+        let checker = Exhaustiveness { enums: &enums };
+        let type_ = [Type::Enum(String::from("O"))];
+
+        let rows = vec![
+            vec![variant(0, vec![])],
+            vec![variant(1, vec![Pat::Constructor(Constructor::Bool(true), vec![])])],
+        ];
+        let witness = checker.useful(&rows, &[Pat::Wild], &type_).unwrap();
+        assert_eq!(checker.describe(&witness[0], &type_[0]), "O::Some(false)");
+
+        let rows = vec![vec![variant(1, vec![Pat::Wild])], vec![Pat::Wild]];
+        assert!(checker.useful(&rows, &[Pat::Wild], &type_).is_none());
+        assert!(checker.useful(&rows[..1], &[variant(1, vec![Pat::Constructor(Constructor::Bool(false), vec![])])], &type_).is_none());
+
+        let ints = vec![vec![Pat::Constructor(Constructor::Literal(String::from("1")), vec![])]];
+        assert_eq!(checker.useful(&ints, &[Pat::Wild], &[Type::Int]), Some(vec![Pat::Wild]));
+    }
+}